@@ -1,13 +1,16 @@
 use crate::SharedOptions;
 
 use probe_rs::{
-    architecture::arm::ap::AccessPortError, config::TargetSelector, flashing::FileDownloadError,
+    architecture::arm::{ap::AccessPortError, dump::CoreDumpError},
+    config::{get_target_by_name, get_target_with_extension, TargetSelector},
+    debug::DebugError,
+    flashing::{read_flm, FileDownloadError},
     DebugProbeError, Error, Probe, Session,
 };
 
 use thiserror::Error;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 #[derive(Debug, Error)]
 pub enum CliError {
@@ -19,6 +22,10 @@ pub enum CliError {
     StdIO(#[from] std::io::Error),
     #[error(transparent)]
     FileDownload(#[from] FileDownloadError),
+    #[error(transparent)]
+    CoreDump(#[from] CoreDumpError),
+    #[error(transparent)]
+    Debug(#[from] DebugError),
     #[error("Command expected more arguments.")]
     MissingArgument,
     #[error("Failed to parse argument '{argument}'.")]
@@ -58,19 +65,14 @@ pub(crate) fn open_probe(index: Option<usize>) -> Result<Probe, CliError> {
     Ok(probe)
 }
 
-/// Takes a closure that is handed an `DAPLink` instance and then executed.
-/// After the closure is done, the USB device is always closed,
-/// even in an error case inside the closure!
-pub(crate) fn with_device<F>(shared_options: &SharedOptions, f: F) -> Result<()>
-where
-    F: FnOnce(Session) -> Result<()>,
-{
+/// Open the probe selected in the shared options,
+/// and configure its protocol and speed.
+pub(crate) fn open_configured_probe(
+    shared_options: &SharedOptions,
+) -> Result<(Probe, TargetSelector)> {
     let mut probe = open_probe(shared_options.n)?;
 
-    let target_selector = match &shared_options.chip {
-        Some(identifier) => identifier.into(),
-        None => TargetSelector::Auto,
-    };
+    let target_selector = target_selector(shared_options)?;
 
     if let Some(protocol) = shared_options.protocol {
         probe.select_protocol(protocol)?;
@@ -88,6 +90,45 @@ where
         }
     }
 
+    Ok((probe, target_selector))
+}
+
+/// Select the target based on the chip, target extension and
+/// flash algorithm given in the shared options.
+fn target_selector(shared_options: &SharedOptions) -> Result<TargetSelector> {
+    let mut target = match (&shared_options.target_extension, &shared_options.chip) {
+        (Some(path), _) => get_target_with_extension(path)?,
+        (None, Some(identifier)) if shared_options.flash_algorithm.is_some() => {
+            get_target_by_name(identifier)?
+        }
+        (None, Some(identifier)) => return Ok(identifier.into()),
+        (None, None) if shared_options.flash_algorithm.is_some() => {
+            return Err(anyhow!(
+                "A chip or target extension has to be selected to use a flash algorithm."
+            ))
+        }
+        (None, None) => return Ok(TargetSelector::Auto),
+    };
+
+    if let Some(path) = &shared_options.flash_algorithm {
+        let algorithm = read_flm(&std::fs::read(path)?)?;
+        log::info!("Using flash algorithm '{}' from {:?}", algorithm.name, path);
+
+        target.add_flash_algorithm(algorithm);
+    }
+
+    Ok(target.into())
+}
+
+/// Takes a closure that is handed an `DAPLink` instance and then executed.
+/// After the closure is done, the USB device is always closed,
+/// even in an error case inside the closure!
+pub(crate) fn with_device<F>(shared_options: &SharedOptions, f: F) -> Result<()>
+where
+    F: FnOnce(Session) -> Result<()>,
+{
+    let (probe, target_selector) = open_configured_probe(shared_options)?;
+
     let session = if shared_options.connect_under_reset {
         probe.attach_under_reset(target_selector)?
     } else {
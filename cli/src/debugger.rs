@@ -2,12 +2,13 @@ use crate::common::CliError;
 
 use capstone::Capstone;
 use num_traits::Num;
-use probe_rs::architecture::arm::CortexDump;
-use probe_rs::debug::DebugInfo;
-use probe_rs::{Core, CoreRegisterAddress, MemoryInterface};
+use probe_rs::architecture::arm::{dump::CoreDump, fault};
+use probe_rs::config::MemoryRegion;
+use probe_rs::debug::{DebugInfo, SourceLocation, SteppingMode, SteppingResult};
+use probe_rs::{Architecture, Core, MemoryInterface, WatchpointKind};
 
-use std::fs::File;
-use std::{io::prelude::*, time::Duration};
+use std::path::Path;
+use std::time::Duration;
 
 use parse_int::parse;
 
@@ -32,6 +33,26 @@ where
     })
 }
 
+/// Parse the argument at the given index as an address, or look up the function it names.
+fn get_address_argument(cli_data: &CliData, args: &[&str], index: usize) -> Result<u64, CliError> {
+    let arg_str = args.get(index).ok_or(CliError::MissingArgument)?;
+
+    if let Ok(address) = parse::<u64>(arg_str) {
+        return Ok(address);
+    }
+
+    cli_data
+        .debug_info
+        .as_ref()
+        .and_then(|di| di.get_function_range(arg_str))
+        .map(|range| range.start)
+        .ok_or_else(|| CliError::ArgumentParseError {
+            argument_index: index,
+            argument: arg_str.to_string(),
+            source: anyhow::anyhow!("Neither an address nor the name of a known function"),
+        })
+}
+
 impl DebugCli {
     pub fn new() -> DebugCli {
         let mut cli = DebugCli {
@@ -50,6 +71,27 @@ impl DebugCli {
             },
         });
 
+        cli.add_command(Command {
+            name: "next",
+            help_text: "Step over the current source line",
+
+            function: |cli_data, _args| step_source(cli_data, SteppingMode::OverStatement),
+        });
+
+        cli.add_command(Command {
+            name: "step_in",
+            help_text: "Step into the function called on the current source line",
+
+            function: |cli_data, _args| step_source(cli_data, SteppingMode::IntoStatement),
+        });
+
+        cli.add_command(Command {
+            name: "step_out",
+            help_text: "Run until the current function returns",
+
+            function: |cli_data, _args| step_source(cli_data, SteppingMode::OutOfStatement),
+        });
+
         cli.add_command(Command {
             name: "halt",
             help_text: "Stop the CPU",
@@ -76,7 +118,7 @@ impl DebugCli {
                 for (offset, instruction) in code.iter().enumerate() {
                     println!(
                         "{:#010x}: {:010x}",
-                        cpu_info.pc + offset as u32,
+                        cpu_info.pc + offset as u64,
                         instruction
                     );
                 }
@@ -99,6 +141,15 @@ impl DebugCli {
                         .core
                         .read_core_reg(cli_data.core.registers().program_counter())?;
                     println!("Core halted at address {:#010x}", pc);
+
+                    if cli_data.core.architecture() == Architecture::Arm {
+                        if let Some(report) = fault::read_fault_report(&mut cli_data.core)? {
+                            match &cli_data.debug_info {
+                                Some(di) => println!("Fault: {}", report.with_symbols(di)),
+                                None => println!("Fault: {}", report),
+                            }
+                        }
+                    }
                 }
 
                 Ok(CliState::Continue)
@@ -145,7 +196,7 @@ impl DebugCli {
                 }
 
                 for (offset, word) in buff.iter().enumerate() {
-                    println!("0x{:08x} = 0x{:08x}", address + (offset * 4) as u32, word);
+                    println!("0x{:08x} = 0x{:08x}", address + (offset * 4) as u64, word);
                 }
 
                 Ok(CliState::Continue)
@@ -168,10 +219,10 @@ impl DebugCli {
 
         cli.add_command(Command {
             name: "break",
-            help_text: "Set a breakpoint at a specifc address",
+            help_text: "Set a breakpoint at an address or function: break <address|function>",
 
             function: |cli_data, args| {
-                let address = get_int_argument(args, 0)?;
+                let address = get_address_argument(cli_data, args, 0)?;
 
                 cli_data.core.set_hw_breakpoint(address)?;
 
@@ -183,10 +234,10 @@ impl DebugCli {
 
         cli.add_command(Command {
             name: "clear_break",
-            help_text: "Clear a breakpoint",
+            help_text: "Clear a breakpoint at an address or function",
 
             function: |cli_data, args| {
-                let address = get_int_argument(args, 0)?;
+                let address = get_address_argument(cli_data, args, 0)?;
 
                 cli_data.core.clear_hw_breakpoint(address)?;
 
@@ -194,6 +245,56 @@ impl DebugCli {
             },
         });
 
+        cli.add_command(Command {
+            name: "watch",
+            help_text: "Set a watchpoint: watch <address> [size] [r|w|rw]",
+
+            function: |cli_data, args| {
+                let address = get_int_argument(args, 0)?;
+
+                let size = if args.len() > 1 {
+                    get_int_argument(args, 1)?
+                } else {
+                    4
+                };
+
+                let kind = match args.get(2).copied() {
+                    Some("r") => WatchpointKind::Read,
+                    Some("w") | None => WatchpointKind::Write,
+                    Some("rw") => WatchpointKind::Access,
+                    Some(other) => {
+                        return Err(CliError::ArgumentParseError {
+                            argument_index: 2,
+                            argument: other.to_string(),
+                            source: anyhow::anyhow!("Expected one of 'r', 'w' or 'rw'"),
+                        })
+                    }
+                };
+
+                cli_data.core.set_hw_watchpoint(address, size, kind)?;
+
+                println!(
+                    "Set new {:?} watchpoint at address {:#08x} with size {}",
+                    kind, address, size
+                );
+
+                Ok(CliState::Continue)
+            },
+        });
+
+        cli.add_command(Command {
+            name: "clear_watch",
+            help_text: "Clear a watchpoint",
+
+            function: |cli_data, args| {
+                let address = get_int_argument(args, 0)?;
+
+                cli_data.core.clear_hw_watchpoint(address)?;
+
+                Ok(CliState::Continue)
+            },
+        });
+
         cli.add_command(Command {
             name: "bt",
             help_text: "Show backtrace",
@@ -206,11 +307,15 @@ impl DebugCli {
                     let program_counter = cli_data.core.read_core_reg(regs.program_counter())?;
 
                     if let Some(di) = &cli_data.debug_info {
-                        let frames = di.try_unwind(&mut cli_data.core, u64::from(program_counter));
+                        let mut frames = di.try_unwind(&mut cli_data.core, program_counter);
 
-                        for frame in frames {
+                        for frame in &mut frames {
                             println!("{}", frame);
                         }
+
+                        if let Some(error) = frames.unwind_error() {
+                            println!("Unwinding stopped early: {}", error);
+                        }
                     } else {
                         println!("No debug information present!");
                     }
@@ -240,45 +345,34 @@ impl DebugCli {
         });
 
         cli.add_command(Command {
-            name: "dump",
-            help_text: "Store a dump of the current CPU state",
-
-            function: |cli_data, _args| {
-                // dump all relevant data, stack and regs for now..
-                //
-                // stack beginning -> assume beginning to be hardcoded
-
-                let stack_top: u32 = 0x2000_0000 + 0x4000;
-
-                let regs = cli_data.core.registers();
-
-                let stack_bot: u32 = cli_data.core.read_core_reg(regs.stack_pointer())?;
-                let pc: u32 = cli_data.core.read_core_reg(regs.program_counter())?;
+            name: "core_dump",
+            help_text: "Store the registers and the RAM of the target as an ELF core dump (default: core.elf). Optionally, only store the memory at <address> of <size> bytes",
 
-                let mut stack = vec![0u8; (stack_top - stack_bot) as usize];
-
-                cli_data.core.read_8(stack_bot, &mut stack[..])?;
-
-                let mut dump = CortexDump::new(stack_bot, stack);
-
-                for i in 0..12 {
-                    dump.regs[i as usize] =
-                        cli_data
-                            .core
-                            .read_core_reg(Into::<CoreRegisterAddress>::into(i))?;
-                }
+            function: |cli_data, args| {
+                let path = args.first().copied().unwrap_or("core.elf");
 
-                dump.regs[13] = stack_bot;
-                dump.regs[14] = cli_data.core.read_core_reg(regs.return_address())?;
-                dump.regs[15] = pc;
+                let ram_ranges: Vec<_> = if args.len() > 1 {
+                    let address: u64 = get_int_argument(args, 1)?;
+                    let size: u64 = get_int_argument(args, 2)?;
 
-                let serialized = ron::ser::to_string(&dump).expect("Failed to serialize dump");
+                    std::iter::once(address..address.saturating_add(size)).collect()
+                } else {
+                    cli_data
+                        .memory_map
+                        .iter()
+                        .filter_map(|region| match region {
+                            MemoryRegion::Ram(ram) => {
+                                Some(u64::from(ram.range.start)..u64::from(ram.range.end))
+                            }
+                            _ => None,
+                        })
+                        .collect()
+                };
 
-                let mut dump_file = File::create("dump.txt").expect("Failed to create file");
+                let dump = CoreDump::capture(&mut cli_data.core, &ram_ranges)?;
+                dump.store(Path::new(path))?;
 
-                dump_file
-                    .write_all(serialized.as_bytes())
-                    .expect("Failed to write dump file");
+                println!("Stored core dump in {}", path);
 
                 Ok(CliState::Continue)
             },
@@ -362,10 +456,55 @@ impl DebugCli {
     }
 }
 
+/// Step through the source of the program, which requires debug information.
+fn step_source(cli_data: &mut CliData, stepping_mode: SteppingMode) -> Result<CliState, CliError> {
+    let di = match &cli_data.debug_info {
+        Some(di) => di,
+        None => {
+            println!("No debug information present!");
+            return Ok(CliState::Continue);
+        }
+    };
+
+    let cpu_info = match stepping_mode.step(&mut cli_data.core, di)? {
+        SteppingResult::Halted(cpu_info) => cpu_info,
+        SteppingResult::Running {
+            temporary_breakpoint,
+        } => {
+            // The command line waits for the step, so a function call which does not return is interrupted.
+            let cpu_info = cli_data.core.halt(Duration::from_millis(100))?;
+
+            if let Some(address) = temporary_breakpoint {
+                cli_data.core.clear_hw_breakpoint(address)?;
+            }
+
+            println!("The function call did not return, halting the core");
+
+            cpu_info
+        }
+    };
+
+    match di.get_source_location(cpu_info.pc) {
+        Some(SourceLocation {
+            file: Some(file),
+            line: Some(line),
+            ..
+        }) => println!(
+            "Core stopped at address 0x{:08x} ({}:{})",
+            cpu_info.pc, file, line
+        ),
+        _ => println!("Core stopped at address 0x{:08x}", cpu_info.pc),
+    }
+
+    Ok(CliState::Continue)
+}
+
 pub struct CliData<'p> {
     pub core: Core<'p>,
     pub debug_info: Option<DebugInfo>,
     pub capstone: Capstone,
+    /// The memory map of the target, empty when inspecting a core dump.
+    pub memory_map: Vec<MemoryRegion>,
 }
 
 pub enum CliState {
@@ -2,12 +2,17 @@ mod common;
 mod debugger;
 mod info;
 
-use common::with_device;
+use common::{open_configured_probe, with_device};
 use debugger::CliState;
 
 use probe_rs::{
+    architecture::arm::dump::CoreDump,
     debug::DebugInfo,
-    flashing::{download_file, Format},
+    flashing::{
+        download_file_with_options, erase_all, erase_sectors, read_configuration, readback_to_file,
+        write_configuration, Configuration, DownloadOptions, Format, ReadbackFormat,
+        ReadbackOptions, Uf2Options,
+    },
     MemoryInterface, Probe, Session, WireProtocol,
 };
 
@@ -19,26 +24,64 @@ use structopt::StructOpt;
 use anyhow::{anyhow, Result};
 
 use std::num::ParseIntError;
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
-fn parse_hex(src: &str) -> Result<u32, ParseIntError> {
+fn parse_hex(src: &str) -> Result<u64, ParseIntError> {
+    u64::from_str_radix(src, 16)
+}
+
+fn parse_hex_u32(src: &str) -> Result<u32, ParseIntError> {
     u32::from_str_radix(src, 16)
 }
 
+/// Build the address range of `size` bytes starting at `start`, if both are given.
+fn address_range(start: Option<u32>, size: Option<u32>) -> Result<Option<Range<u32>>> {
+    match start.zip(size) {
+        Some((start, size)) => {
+            let end = start.checked_add(size).ok_or_else(|| {
+                anyhow!(
+                    "The range of {:#x} bytes starting at {:#010x} exceeds the 32 bit address space",
+                    size,
+                    start
+                )
+            })?;
+
+            Ok(Some(start..end))
+        }
+        None => Ok(None),
+    }
+}
+
+fn parse_field_assignment(src: &str) -> Result<(String, u32)> {
+    let mut parts = src.splitn(2, '=');
+
+    match (parts.next(), parts.next()) {
+        (Some(name), Some(value)) => Ok((name.to_owned(), parse_int::parse(value)?)),
+        _ => Err(anyhow!("Expected NAME=VALUE, found '{}'", src)),
+    }
+}
+
 arg_enum! {
     #[derive(Debug, Clone, Copy)]
     enum DownloadFileType {
         Elf,
         Hex,
+        Uf2,
+        Srec,
     }
 }
 
-impl From<DownloadFileType> for Format {
-    fn from(this: DownloadFileType) -> Format {
-        match this {
+impl DownloadFileType {
+    fn into_format(self, uf2_family_id: Option<u32>) -> Format {
+        match self {
             DownloadFileType::Elf => Format::Elf,
             DownloadFileType::Hex => Format::Hex,
+            DownloadFileType::Uf2 => Format::Uf2(Uf2Options {
+                family_id: uf2_family_id,
+            }),
+            DownloadFileType::Srec => Format::Srec,
         }
     }
 }
@@ -68,6 +111,12 @@ enum Cli {
         /// Whether the reset pin should be asserted or deasserted. If left open, just pulse it
         assert: Option<bool>,
     },
+    /// Unlocks a locked target by erasing all of its memory
+    #[structopt(name = "recover")]
+    Recover {
+        #[structopt(flatten)]
+        shared: SharedOptions,
+    },
     #[structopt(name = "debug")]
     Debug {
         #[structopt(flatten)]
@@ -77,6 +126,17 @@ enum Cli {
         /// Binary to debug
         exe: Option<PathBuf>,
     },
+    /// Inspect an ELF core dump, created with the `core_dump` debugger command
+    #[structopt(name = "inspect")]
+    Inspect {
+        /// The path to the core dump
+        #[structopt(parse(from_os_str))]
+        core_dump: PathBuf,
+
+        #[structopt(long, parse(from_os_str))]
+        /// Binary which was running on the target
+        exe: Option<PathBuf>,
+    },
     /// Dump memory from attached target
     #[structopt(name = "dump")]
     Dump {
@@ -85,7 +145,7 @@ enum Cli {
 
         /// The address of the memory to dump from the target (in hexadecimal without 0x prefix)
         #[structopt(parse(try_from_str = parse_hex))]
-        loc: u32,
+        loc: u64,
         /// The amount of memory (in words) to dump
         words: u32,
     },
@@ -104,9 +164,91 @@ enum Cli {
         )]
         format: DownloadFileType,
 
+        /// Only download the UF2 blocks with this family ID (in hexadecimal without 0x prefix)
+        #[structopt(long, parse(try_from_str = parse_hex_u32))]
+        uf2_family_id: Option<u32>,
+
+        /// Verify the flash contents after downloading
+        #[structopt(long)]
+        verify: bool,
+
+        /// Run an image which is linked for RAM after downloading it,
+        /// starting at its entry point
+        #[structopt(long)]
+        run_from_ram: bool,
+
+        /// Skip erasing and programming the sectors which already contain the data
+        #[structopt(long)]
+        skip_unchanged: bool,
+
         /// The path to the file to be downloaded to the flash
         path: String,
     },
+    /// Erase the flash of the attached target
+    #[structopt(name = "erase")]
+    Erase {
+        #[structopt(flatten)]
+        shared: SharedOptions,
+
+        /// The start address of the range to erase (in hexadecimal without 0x prefix).
+        /// All sectors intersecting the range are erased. If no range is given,
+        /// the entire flash is erased
+        #[structopt(long, parse(try_from_str = parse_hex_u32), requires = "size")]
+        start: Option<u32>,
+
+        /// The number of bytes to erase, starting at the start address (in hexadecimal without 0x prefix)
+        #[structopt(long, parse(try_from_str = parse_hex_u32), requires = "start")]
+        size: Option<u32>,
+    },
+    /// Read back the flash contents of the attached target into a file
+    #[structopt(name = "readback")]
+    Readback {
+        #[structopt(flatten)]
+        shared: SharedOptions,
+
+        /// Format of the file (bin, hex or elf)
+        #[structopt(long, default_value = "elf")]
+        format: ReadbackFormat,
+
+        /// The start address of the range to read (in hexadecimal without 0x prefix).
+        /// If no range is given, all flash regions of the target are read
+        #[structopt(long, parse(try_from_str = parse_hex_u32), requires = "size")]
+        start: Option<u32>,
+
+        /// The number of bytes to read, starting at the start address (in hexadecimal without 0x prefix)
+        #[structopt(long, parse(try_from_str = parse_hex_u32), requires = "start")]
+        size: Option<u32>,
+
+        /// The path of the file to be written
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+    },
+    /// Read the configuration region of the target, like the option bytes of STM32 chips
+    #[structopt(name = "config-read")]
+    ConfigRead {
+        #[structopt(flatten)]
+        shared: SharedOptions,
+    },
+    /// Change fields of the configuration region of the target
+    #[structopt(name = "config-write")]
+    ConfigWrite {
+        #[structopt(flatten)]
+        shared: SharedOptions,
+
+        /// Do not reset the target after writing. Most targets only apply
+        /// the new configuration after a reset
+        #[structopt(long)]
+        no_reset: bool,
+
+        /// Allow a configuration which locks the target permanently,
+        /// like read protection level 2 of STM32 chips
+        #[structopt(long)]
+        allow_permanent_lock: bool,
+
+        /// The fields to change, as NAME=VALUE. Values with a 0x prefix are hexadecimal
+        #[structopt(required = true, parse(try_from_str = parse_field_assignment))]
+        fields: Vec<(String, u32)>,
+    },
     #[structopt(name = "trace")]
     Trace {
         #[structopt(flatten)]
@@ -114,7 +256,7 @@ enum Cli {
 
         /// The address of the memory to dump from the target (in hexadecimal without 0x prefix)
         #[structopt(parse(try_from_str = parse_hex))]
-        loc: u32,
+        loc: u64,
     },
 }
 
@@ -129,6 +271,15 @@ struct SharedOptions {
     #[structopt(short, long)]
     chip: Option<String>,
 
+    /// Use a board-level target extension, which adds memory regions and
+    /// flash algorithms to the chip it extends.
+    #[structopt(long, parse(from_os_str), conflicts_with = "chip")]
+    target_extension: Option<PathBuf>,
+
+    /// Use the flash algorithm from a CMSIS-Pack FLM file for the selected target.
+    #[structopt(long, parse(from_os_str))]
+    flash_algorithm: Option<PathBuf>,
+
     /// Protocol to use for target connection
     #[structopt(short, long)]
     protocol: Option<WireProtocol>,
@@ -151,13 +302,45 @@ fn main() -> Result<()> {
         Cli::List {} => list_connected_devices(),
         Cli::Info { shared } => crate::info::show_info_of_device(&shared),
         Cli::Reset { shared, assert } => reset_target_of_device(&shared, assert),
+        Cli::Recover { shared } => recover_target_of_device(&shared),
         Cli::Debug { shared, exe } => debug(&shared, exe),
+        Cli::Inspect { core_dump, exe } => inspect_core_dump(&core_dump, exe),
         Cli::Dump { shared, loc, words } => dump_memory(&shared, loc, words),
         Cli::Download {
             shared,
             format,
+            uf2_family_id,
+            verify,
+            run_from_ram,
+            skip_unchanged,
             path,
-        } => download_program_fast(&shared, format.into(), &path),
+        } => download_program_fast(
+            &shared,
+            format.into_format(uf2_family_id),
+            verify,
+            run_from_ram,
+            skip_unchanged,
+            &path,
+        ),
+        Cli::Erase {
+            shared,
+            start,
+            size,
+        } => erase_flash(&shared, address_range(start, size)?),
+        Cli::Readback {
+            shared,
+            format,
+            start,
+            size,
+            path,
+        } => readback_flash(&shared, format, address_range(start, size)?, &path),
+        Cli::ConfigRead { shared } => read_configuration_of_device(&shared),
+        Cli::ConfigWrite {
+            shared,
+            no_reset,
+            allow_permanent_lock,
+            fields,
+        } => write_configuration_of_device(&shared, &fields, !no_reset, allow_permanent_lock),
         Cli::Trace { shared, loc } => trace_u32_on_target(&shared, loc),
     }
 }
@@ -178,7 +361,7 @@ fn list_connected_devices() -> Result<()> {
     Ok(())
 }
 
-fn dump_memory(shared_options: &SharedOptions, loc: u32, words: u32) -> Result<()> {
+fn dump_memory(shared_options: &SharedOptions, loc: u64, words: u32) -> Result<()> {
     with_device(shared_options, |mut session| {
         let mut data = vec![0_u32; words as usize];
 
@@ -197,7 +380,7 @@ fn dump_memory(shared_options: &SharedOptions, loc: u32, words: u32) -> Result<(
         for word in 0..words {
             println!(
                 "Addr 0x{:08x?}: 0x{:08x}",
-                loc + 4 * word,
+                loc + 4 * u64::from(word),
                 data[word as usize]
             );
         }
@@ -208,14 +391,127 @@ fn dump_memory(shared_options: &SharedOptions, loc: u32, words: u32) -> Result<(
     })
 }
 
-fn download_program_fast(shared_options: &SharedOptions, format: Format, path: &str) -> Result<()> {
+fn download_program_fast(
+    shared_options: &SharedOptions,
+    format: Format,
+    verify: bool,
+    run_from_ram: bool,
+    skip_unchanged_sectors: bool,
+    path: &str,
+) -> Result<()> {
+    with_device(shared_options, |mut session| {
+        download_file_with_options(
+            &mut session,
+            &path,
+            format,
+            DownloadOptions {
+                verify,
+                boot_from_ram: run_from_ram,
+                run_after_boot: run_from_ram,
+                skip_unchanged_sectors,
+                ..Default::default()
+            },
+        )?;
+
+        Ok(())
+    })
+}
+
+fn erase_flash(shared_options: &SharedOptions, range: Option<Range<u32>>) -> Result<()> {
+    with_device(shared_options, |mut session| {
+        let instant = Instant::now();
+
+        match range {
+            Some(range) => erase_sectors(&mut session, range)?,
+            None => erase_all(&mut session)?,
+        }
+
+        println!("Erased the flash in {:?}", instant.elapsed());
+
+        Ok(())
+    })
+}
+
+fn readback_flash(
+    shared_options: &SharedOptions,
+    format: ReadbackFormat,
+    range: Option<Range<u32>>,
+    path: &Path,
+) -> Result<()> {
+    with_device(shared_options, |mut session| {
+        let progress = |read, total| eprint!("\rRead {} of {} bytes", read, total);
+
+        let options = ReadbackOptions {
+            range,
+            progress: Some(&progress),
+        };
+
+        let instant = Instant::now();
+
+        readback_to_file(&mut session, path, format, &options)?;
+
+        eprintln!();
+        println!(
+            "Wrote the flash contents to {} in {:?}",
+            path.display(),
+            instant.elapsed()
+        );
+
+        Ok(())
+    })
+}
+
+fn recover_target_of_device(shared_options: &SharedOptions) -> Result<()> {
+    let (probe, target_selector) = open_configured_probe(shared_options)?;
+
+    probe.recover(target_selector)?;
+
+    println!("The target was erased and is unlocked now.");
+
+    Ok(())
+}
+
+fn read_configuration_of_device(shared_options: &SharedOptions) -> Result<()> {
     with_device(shared_options, |mut session| {
-        download_file(&mut session, &path, format)?;
+        let configuration = read_configuration(&mut session)?;
+
+        print_configuration(&configuration);
 
         Ok(())
     })
 }
 
+fn write_configuration_of_device(
+    shared_options: &SharedOptions,
+    fields: &[(String, u32)],
+    reset: bool,
+    allow_permanent_lock: bool,
+) -> Result<()> {
+    with_device(shared_options, |mut session| {
+        let mut configuration = read_configuration(&mut session)?;
+
+        for (name, value) in fields {
+            configuration.set_field(name, *value)?;
+        }
+
+        write_configuration(&mut session, &configuration, allow_permanent_lock)?;
+
+        if reset {
+            session.core(0)?.reset()?;
+        }
+
+        print_configuration(&read_configuration(&mut session)?);
+
+        Ok(())
+    })
+}
+
+fn print_configuration(configuration: &Configuration) {
+    for (field, value) in configuration.fields() {
+        println!("{:<16} {:>#12x}  {}", field.name, value, field.description);
+    }
+}
+
 fn reset_target_of_device(shared_options: &SharedOptions, _assert: Option<bool>) -> Result<()> {
     with_device(shared_options, |mut session| {
         session.core(0)?.reset()?;
@@ -224,7 +520,7 @@ fn reset_target_of_device(shared_options: &SharedOptions, _assert: Option<bool>)
     })
 }
 
-fn trace_u32_on_target(shared_options: &SharedOptions, loc: u32) -> Result<()> {
+fn trace_u32_on_target(shared_options: &SharedOptions, loc: u64) -> Result<()> {
     use scroll::{Pwrite, LE};
     use std::io::prelude::*;
     use std::thread::sleep;
@@ -270,58 +566,80 @@ fn trace_u32_on_target(shared_options: &SharedOptions, loc: u32) -> Result<()> {
 
 fn debug(shared_options: &SharedOptions, exe: Option<PathBuf>) -> Result<()> {
     let runner = |mut session: Session| {
-        let cs = Capstone::new()
-            .arm()
-            .mode(ArchMode::Thumb)
-            .endian(Endian::Little)
-            .build()
-            .map_err(|err| anyhow!("Error creating capstone: {:?}", err))?;
-
-        let di = exe
-            .as_ref()
-            .and_then(|path| DebugInfo::from_file(path).ok());
-
-        let cli = debugger::DebugCli::new();
+        let memory_map = session.target().memory_map.clone();
 
         let core = session.core(0)?;
 
         let mut cli_data = debugger::CliData {
             core,
-            debug_info: di,
-            capstone: cs,
+            debug_info: load_debug_info(exe.as_ref()),
+            capstone: create_capstone()?,
+            memory_map,
         };
 
-        let mut rl = Editor::<()>::new();
+        run_debug_cli(&mut cli_data)
+    };
 
-        loop {
-            let readline = rl.readline(">> ");
-            match readline {
-                Ok(line) => {
-                    let history_entry: &str = line.as_ref();
-                    rl.add_history_entry(history_entry);
-                    let cli_state = cli.handle_line(&line, &mut cli_data)?;
-
-                    match cli_state {
-                        CliState::Continue => (),
-                        CliState::Stop => return Ok(()),
-                    }
+    with_device(shared_options, runner)
+}
+
+fn inspect_core_dump(path: &Path, exe: Option<PathBuf>) -> Result<()> {
+    let mut dump = CoreDump::load(path)?;
+
+    let mut cli_data = debugger::CliData {
+        core: dump.core(),
+        debug_info: load_debug_info(exe.as_ref()),
+        capstone: create_capstone()?,
+        memory_map: vec![],
+    };
+
+    run_debug_cli(&mut cli_data)
+}
+
+fn create_capstone() -> Result<Capstone> {
+    Capstone::new()
+        .arm()
+        .mode(ArchMode::Thumb)
+        .endian(Endian::Little)
+        .build()
+        .map_err(|err| anyhow!("Error creating capstone: {:?}", err))
+}
+
+fn load_debug_info(exe: Option<&PathBuf>) -> Option<DebugInfo> {
+    exe.and_then(|path| DebugInfo::from_file(path).ok())
+}
+
+fn run_debug_cli(cli_data: &mut debugger::CliData) -> Result<()> {
+    let cli = debugger::DebugCli::new();
+
+    let mut rl = Editor::<()>::new();
+
+    loop {
+        let readline = rl.readline(">> ");
+        match readline {
+            Ok(line) => {
+                let history_entry: &str = line.as_ref();
+                rl.add_history_entry(history_entry);
+                let cli_state = cli.handle_line(&line, cli_data)?;
+
+                match cli_state {
+                    CliState::Continue => (),
+                    CliState::Stop => return Ok(()),
                 }
-                Err(e) => {
-                    use rustyline::error::ReadlineError;
-
-                    match e {
-                        // For end of file and ctrl-c, we just quit
-                        ReadlineError::Eof | ReadlineError::Interrupted => return Ok(()),
-                        actual_error => {
-                            // Show error message and quit
-                            println!("Error handling input: {:?}", actual_error);
-                            return Ok(());
-                        }
+            }
+            Err(e) => {
+                use rustyline::error::ReadlineError;
+
+                match e {
+                    // For end of file and ctrl-c, we just quit
+                    ReadlineError::Eof | ReadlineError::Interrupted => return Ok(()),
+                    actual_error => {
+                        // Show error message and quit
+                        println!("Error handling input: {:?}", actual_error);
+                        return Ok(());
                     }
                 }
             }
         }
-    };
-
-    with_device(shared_options, runner)
+    }
 }
@@ -5,7 +5,11 @@ use crate::DebuggerError;
 use anyhow::{anyhow, Result};
 use dap_types::*;
 use parse_int::parse;
-use probe_rs::{debug::VariableKind, CoreStatus, HaltReason, MemoryInterface};
+use probe_rs::{
+    architecture::arm::fault,
+    debug::{DebugInfo, SteppingMode, SteppingResult, VariableKind},
+    Architecture, Core, CoreStatus, HaltReason, MemoryInterface, VectorCatchCondition,
+};
 use rustyline::Editor;
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::json;
@@ -42,6 +46,12 @@ pub struct DebugAdapter<R: Read, W: Write> {
     /// It is cleared by threads(), populated by stack_trace(), for later nested re-use by variables()
     variable_map_key_seq: i64, //Used to create unique values for self.variable_map keys
     variable_map: HashMap<i64, Vec<Variable>>,
+    /// function_breakpoints stores the addresses of the breakpoints set by setFunctionBreakpoints(),
+    /// which replaces them on every request.
+    function_breakpoints: Vec<u64>,
+    /// The breakpoint at the return address of a function call which was still running after
+    /// stepping over it. It is cleared when the core halts.
+    temporary_breakpoint: Option<u64>,
 }
 
 impl<R: Read, W: Write> DebugAdapter<R, W> {
@@ -61,6 +71,8 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
             scope_map: HashMap::new(),
             variable_map: HashMap::new(),
             variable_map_key_seq: -1,
+            function_breakpoints: Vec::new(),
+            temporary_breakpoint: None,
         }
     }
 
@@ -106,12 +118,16 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
 
         match core_data.target_core.halt(Duration::from_millis(100)) {
             Ok(cpu_info) => {
+                self.clear_temporary_breakpoint(&mut core_data.target_core);
                 let event_body = Some(StoppedEventBody {
                     reason: "pause".to_owned(),
                     description: Some(self.last_known_status.short_long_status().1.to_owned()),
                     thread_id: Some(core_data.target_core.id() as i64),
                     preserve_focus_hint: Some(false),
-                    text: None,
+                    text: fault_description(
+                        &mut core_data.target_core,
+                        core_data.debug_info.as_ref(),
+                    ),
                     all_threads_stopped: Some(true),
                     hit_breakpoint_ids: None,
                 });
@@ -169,7 +185,7 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
                 Err(error) => return self.send_response::<()>(&request, Err(error)),
             },
         };
-        let address: u32 = parse(&arguments.memory_reference.as_ref()).unwrap();
+        let address: u64 = parse(&arguments.memory_reference.as_ref()).unwrap();
         let num_words = arguments.count as usize;
         let mut buff = vec![0u32; num_words];
         if num_words > 1 {
@@ -181,7 +197,7 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
             let mut response = "".to_string();
             for (offset, word) in buff.iter().enumerate() {
                 response.push_str(
-                    format!("0x{:08x} = 0x{:08x}\n", address + (offset * 4) as u32, word).as_str(),
+                    format!("0x{:08x} = 0x{:08x}\n", address + (offset * 4) as u64, word).as_str(),
                 );
             }
             self.send_response::<String>(&request, Ok(Some(response)))
@@ -503,6 +519,17 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
             }
         }
 
+        // The function breakpoints were cleared as well, so they are set again.
+        for address in &self.function_breakpoints {
+            if let Err(error) = core_data.target_core.set_hw_breakpoint(*address) {
+                log::warn!(
+                    "Failed to restore function breakpoint at {:#010x}: {}",
+                    address,
+                    error
+                );
+            }
+        }
+
         if let Some(requested_breakpoints) = args.breakpoints.as_ref() {
             for bp in requested_breakpoints {
                 // Try to find source code location
@@ -518,7 +545,7 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
 
                 if let Some(location) = source_location {
                     let (verified, reason_msg) =
-                        match core_data.target_core.set_hw_breakpoint(location as u32) {
+                        match core_data.target_core.set_hw_breakpoint(location) {
                             Ok(_) => (true, None),
                             Err(err) => (false, Some(err.to_string())),
                         };
@@ -560,6 +587,146 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
         self.send_response(&request, Ok(Some(breakpoint_body)))
     }
 
+    pub(crate) fn set_function_breakpoints(
+        &mut self,
+        core_data: &mut CoreData,
+        request: &Request,
+    ) -> bool {
+        let args: SetFunctionBreakpointsArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => {
+                return self.send_response::<()>(
+                    &request,
+                    Err(DebuggerError::Other(anyhow!(
+                        "Could not read arguments : {}",
+                        error
+                    ))),
+                )
+            }
+        };
+
+        // The requested function breakpoints replace the previous ones, but not the source breakpoints.
+        for address in self.function_breakpoints.drain(..) {
+            if let Err(error) = core_data.target_core.clear_hw_breakpoint(address) {
+                log::debug!(
+                    "Failed to clear function breakpoint at {:#010x}: {}",
+                    address,
+                    error
+                );
+            }
+        }
+
+        let mut created_breakpoints: Vec<Breakpoint> = Vec::new(); //For returning in the Response
+
+        for bp in &args.breakpoints {
+            let location = core_data
+                .debug_info
+                .as_ref()
+                .and_then(|di| di.get_function_range(&bp.name))
+                .map(|range| range.start);
+
+            if let Some(location) = location {
+                let (verified, reason_msg) = match core_data.target_core.set_hw_breakpoint(location)
+                {
+                    Ok(_) => {
+                        self.function_breakpoints.push(location);
+                        (true, None)
+                    }
+                    Err(err) => (false, Some(err.to_string())),
+                };
+
+                created_breakpoints.push(Breakpoint {
+                    column: None,
+                    end_column: None,
+                    end_line: None,
+                    id: None,
+                    line: None,
+                    message: reason_msg,
+                    source: None,
+                    instruction_reference: Some(location.to_string()),
+                    offset: None,
+                    verified,
+                });
+            } else {
+                created_breakpoints.push(Breakpoint {
+                    column: None,
+                    end_column: None,
+                    end_line: None,
+                    id: None,
+                    line: None,
+                    message: Some(format!("No function named '{}' found", bp.name)),
+                    source: None,
+                    instruction_reference: None,
+                    offset: None,
+                    verified: false,
+                });
+            }
+        }
+
+        let breakpoint_body = SetFunctionBreakpointsResponseBody {
+            breakpoints: created_breakpoints,
+        };
+        self.send_response(&request, Ok(Some(breakpoint_body)))
+    }
+
+    pub(crate) fn set_exception_breakpoints(
+        &mut self,
+        core_data: &mut CoreData,
+        request: &Request,
+    ) -> bool {
+        let args: SetExceptionBreakpointsArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => {
+                return self.send_response::<()>(
+                    &request,
+                    Err(DebuggerError::Other(anyhow!(
+                        "Could not read arguments : {}",
+                        error
+                    ))),
+                )
+            }
+        };
+
+        // Vector catches which are not requested are disabled, so that the core matches the filters of the DAP client.
+        for (filter, _, condition) in VECTOR_CATCH_FILTERS.iter() {
+            if !args.filters.iter().any(|f| f == filter) {
+                if let Err(error) = core_data.target_core.disable_vector_catch(*condition) {
+                    log::debug!("Failed to disable vector catch {:?}: {}", condition, error);
+                }
+            }
+        }
+
+        let mut created_breakpoints: Vec<Breakpoint> = Vec::new(); //For returning in the Response
+
+        for filter in &args.filters {
+            let result = match VECTOR_CATCH_FILTERS.iter().find(|(f, _, _)| f == filter) {
+                Some((_, _, condition)) => core_data
+                    .target_core
+                    .enable_vector_catch(*condition)
+                    .map_err(|error| error.to_string()),
+                None => Err(format!("Unknown exception filter '{}'", filter)),
+            };
+
+            created_breakpoints.push(Breakpoint {
+                column: None,
+                end_column: None,
+                end_line: None,
+                id: None,
+                line: None,
+                message: result.as_ref().err().cloned(),
+                source: None,
+                instruction_reference: None,
+                offset: None,
+                verified: result.is_ok(),
+            });
+        }
+
+        let breakpoint_body = SetExceptionBreakpointsResponseBody {
+            breakpoints: Some(created_breakpoints),
+        };
+        self.send_response(&request, Ok(Some(breakpoint_body)))
+    }
+
     pub(crate) fn stack_trace(&mut self, core_data: &mut CoreData, request: &Request) -> bool {
         let _statuss = match core_data.target_core.status() {
             Ok(status) => {
@@ -608,20 +775,23 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
         };
 
         if let Some(debug_info) = core_data.debug_info.as_ref() {
-            let current_stackframes =
-                debug_info.try_unwind(&mut core_data.target_core, u64::from(pc));
+            let mut current_stackframes = debug_info.try_unwind(&mut core_data.target_core, pc);
 
             match self.adapter_type {
                 DebugAdapterType::CommandLine => {
                     let mut body = "".to_string();
 
-                    for frame in current_stackframes {
+                    for frame in &mut current_stackframes {
                         body.push_str(format!("{}\n", frame).as_str());
                     }
+                    if let Some(error) = current_stackframes.unwind_error() {
+                        body.push_str(format!("Unwinding stopped early: {}\n", error).as_str());
+                    }
                     self.send_response(&request, Ok(Some(body)))
                 }
                 DebugAdapterType::DapClient => {
                     let frame_list: Vec<StackFrame> = current_stackframes
+                        .by_ref()
                         .map(|frame| {
                             use probe_rs::debug::ColumnType::*;
                             let column = frame
@@ -675,19 +845,11 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
                                 register_scope_reference,
                                 frame
                                     .registers
-                                    .into_iter()
-                                    .map(|register| {
+                                    .iter()
+                                    .map(|(name, register)| {
                                         register_count += 1;
-                                        let register_position = register_count - 1;
                                         Variable {
-                                            name: match register_position {
-                                                7 => "R7: THUMB Frame Pointer".to_owned(),
-                                                11 => "R11: ARM Frame Pointer".to_owned(),
-                                                13 => "SP".to_owned(),
-                                                14 => "LR".to_owned(),
-                                                15 => "PC".to_owned(),
-                                                other => format!("R{}", other),
-                                            },
+                                            name: name.to_owned(),
                                             value: match register {
                                                 None | Some(0) => "<not available>".to_owned(),
                                                 Some(register) => {
@@ -766,6 +928,11 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
                         })
                         .collect();
 
+                    if let Some(error) = current_stackframes.unwind_error() {
+                        let message = format!("Unwinding stopped early: {}", error);
+                        self.log_to_console(message);
+                    }
+
                     let frame_len = frame_list.len();
 
                     let body = StackTraceResponseBody {
@@ -916,6 +1083,7 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
                         let core_status = match core_data.target_core.status() {
                             Ok(new_status) => match new_status {
                                 CoreStatus::Halted(_) => {
+                                    self.clear_temporary_breakpoint(&mut core_data.target_core);
                                     let event_body = Some(StoppedEventBody {
                                         reason: new_status.short_long_status().0.to_owned(),
                                         description: Some(
@@ -923,7 +1091,10 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
                                         ),
                                         thread_id: Some(core_data.target_core.id() as i64),
                                         preserve_focus_hint: None,
-                                        text: None,
+                                        text: fault_description(
+                                            &mut core_data.target_core,
+                                            core_data.debug_info.as_ref(),
+                                        ),
                                         all_threads_stopped: Some(true),
                                         hit_breakpoint_ids: None,
                                     });
@@ -946,13 +1117,68 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
         }
     }
 
-    /// Steps at 'instruction' granularity ONLY.
+    /// Steps over the current source line, or a single instruction at 'instruction' granularity.
     pub(crate) fn next(&mut self, core_data: &mut CoreData, request: &Request) -> bool {
-        //TODO: Implement 'statement' granularity, then update DAP `Capabilities` and read `NextArguments`
-        // let args: NextArguments = get_arguments(&request)?;
+        let granularity = get_arguments::<NextArguments>(request)
+            .ok()
+            .and_then(|arguments| arguments.granularity);
 
-        match core_data.target_core.step() {
-            Ok(cpu_info) => {
+        let stepping_mode = match granularity {
+            Some(SteppingGranularity::Instruction) => SteppingMode::StepInstruction,
+            _ => SteppingMode::OverStatement,
+        };
+
+        self.step(core_data, request, stepping_mode)
+    }
+
+    /// Steps into the function called on the current source line, or a single instruction at 'instruction' granularity.
+    pub(crate) fn step_in(&mut self, core_data: &mut CoreData, request: &Request) -> bool {
+        let granularity = get_arguments::<StepInArguments>(request)
+            .ok()
+            .and_then(|arguments| arguments.granularity);
+
+        let stepping_mode = match granularity {
+            Some(SteppingGranularity::Instruction) => SteppingMode::StepInstruction,
+            _ => SteppingMode::IntoStatement,
+        };
+
+        self.step(core_data, request, stepping_mode)
+    }
+
+    /// Runs until the current function returns to its caller.
+    pub(crate) fn step_out(&mut self, core_data: &mut CoreData, request: &Request) -> bool {
+        self.step(core_data, request, SteppingMode::OutOfStatement)
+    }
+
+    /// Steps the core, and reports where it stopped. Without debug information, a single instruction is stepped.
+    fn step(
+        &mut self,
+        core_data: &mut CoreData,
+        request: &Request,
+        stepping_mode: SteppingMode,
+    ) -> bool {
+        let step_result = match core_data.debug_info.as_ref() {
+            Some(debug_info) => stepping_mode
+                .step(&mut core_data.target_core, debug_info)
+                .map_err(|error| anyhow!("{}", error)),
+            None => core_data
+                .target_core
+                .step()
+                .map(SteppingResult::Halted)
+                .map_err(|error| anyhow!("{}", error)),
+        };
+
+        match step_result {
+            Ok(SteppingResult::Running {
+                temporary_breakpoint,
+            }) => {
+                // The `stopped` event is sent by the polling of the core status, once the function call
+                // returns to the temporary breakpoint, or the core halts somewhere else.
+                self.last_known_status = CoreStatus::Running;
+                self.temporary_breakpoint = temporary_breakpoint;
+                self.send_response::<()>(&request, Ok(None))
+            }
+            Ok(SteppingResult::Halted(cpu_info)) => {
                 let new_status = match core_data.target_core.status() {
                     Ok(new_status) => new_status,
                     Err(error) => {
@@ -977,13 +1203,24 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
                 });
                 self.send_event("stopped", event_body)
             }
-            Err(error) => {
-                self.send_response::<()>(&request, Err(DebuggerError::Other(anyhow!("{}", error))))
-            }
+            Err(error) => self.send_response::<()>(&request, Err(DebuggerError::Other(error))),
         }
     }
 
     //SECTION: Helper functions
+    /// Clear the breakpoint which was left set by a step over a function call that was still running.
+    pub(crate) fn clear_temporary_breakpoint(&mut self, core: &mut Core) {
+        if let Some(address) = self.temporary_breakpoint.take() {
+            if let Err(error) = core.clear_hw_breakpoint(address) {
+                log::warn!(
+                    "Failed to clear the temporary breakpoint at {:#010x}: {}",
+                    address,
+                    error
+                );
+            }
+        }
+    }
+
     pub fn peek_seq(&self) -> i64 {
         self.seq
     }
@@ -1413,6 +1650,9 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
                             "Received and unknown event from the debugger".to_owned()
                         });
                         println!("{}", description);
+                        if let Some(text) = event_body_struct.text {
+                            println!("{}", text);
+                        }
                     }
                 }
                 "continued" => {
@@ -1465,6 +1705,29 @@ impl<R: Read, W: Write> DebugAdapter<R, W> {
 }
 
 // SECTION: Some helper functions
+/// The exception breakpoint filters offered to the DAP client, as filter id, label and
+/// the vector catch which implements the filter.
+pub(crate) const VECTOR_CATCH_FILTERS: [(&str, &str, VectorCatchCondition); 6] = [
+    ("hard_fault", "HardFault", VectorCatchCondition::HardFault),
+    ("mem_manage", "MemManage", VectorCatchCondition::MemManage),
+    ("bus_fault", "BusFault", VectorCatchCondition::BusFault),
+    (
+        "usage_fault",
+        "UsageFault",
+        VectorCatchCondition::UsageFault,
+    ),
+    (
+        "secure_fault",
+        "SecureFault",
+        VectorCatchCondition::SecureFault,
+    ),
+    (
+        "exception_entry",
+        "Fault on exception entry or return",
+        VectorCatchCondition::ExceptionEntry,
+    ),
+];
+
 pub fn get_arguments<T: DeserializeOwned>(req: &Request) -> Result<T, crate::DebuggerError> {
     let value = req
         .arguments
@@ -1493,6 +1756,23 @@ mod test {
 
         assert_eq!(234, get_content_len(&header).unwrap());
     }
+
+    #[test]
+    fn vector_catch_filters() {
+        for (index, (filter, _, condition)) in VECTOR_CATCH_FILTERS.iter().enumerate() {
+            for (other_filter, _, other_condition) in &VECTOR_CATCH_FILTERS[index + 1..] {
+                assert_ne!(filter, other_filter);
+                assert_ne!(condition, other_condition);
+            }
+        }
+    }
+
+    #[test]
+    fn vector_catch_status() {
+        let status = CoreStatus::Halted(HaltReason::VectorCatch(VectorCatchCondition::BusFault));
+
+        assert_eq!(status.short_long_status().0, "exception");
+    }
 }
 
 pub(crate) trait DapStatus {
@@ -1522,6 +1802,10 @@ impl DapStatus for CoreStatus {
                     "data breakpoint",
                     "Core halted due to a watchpoint or data breakpoint",
                 ),
+                HaltReason::VectorCatch(_) => (
+                    "exception",
+                    "Core halted due to a vector catch on a fault exception",
+                ),
                 HaltReason::Step => ("step", "Core halted after a 'step' instruction"),
                 HaltReason::Request => (
                     "pause",
@@ -1534,3 +1818,23 @@ impl DapStatus for CoreStatus {
         }
     }
 }
+
+/// Describe the fault the core is handling, if it is halted inside a Cortex-M fault handler.
+///
+/// Code addresses in the description are symbolized if there is debug information.
+pub(crate) fn fault_description(core: &mut Core, debug_info: Option<&DebugInfo>) -> Option<String> {
+    if core.architecture() != Architecture::Arm {
+        return None;
+    }
+
+    match fault::read_fault_report(core) {
+        Ok(report) => report.map(|report| match debug_info {
+            Some(debug_info) => format!("Fault: {}", report.with_symbols(debug_info)),
+            None => format!("Fault: {}", report),
+        }),
+        Err(error) => {
+            log::warn!("Failed to read the fault status: {}", error);
+            None
+        }
+    }
+}
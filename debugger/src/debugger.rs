@@ -140,6 +140,16 @@ pub struct DebuggerOptions {
     #[serde(default)]
     pub(crate) restore_unwritten_bytes: bool,
 
+    /// Verify the flash contents after flashing
+    #[structopt(long, hidden = true)]
+    #[serde(default)]
+    pub(crate) verify_after_flashing: bool,
+
+    /// Skip erasing and programming the sectors which already contain the data
+    #[structopt(long, hidden = true)]
+    #[serde(default)]
+    pub(crate) skip_unchanged_sectors: bool,
+
     /// Level of information to be logged to the debugger console (Error, Info or Debug )
     #[structopt(long, parse(try_from_str = parse_console_log))]
     #[serde(default = "default_console_log")]
@@ -353,9 +363,21 @@ impl Debugger {
                 DebugCommand {
                     dap_cmd: "next",
                     cli_cmd: "step",
-                    help_text: "Step a single instruction",
+                    help_text: "Step over the current source line",
                     function_name: "next",
                 },
+                DebugCommand {
+                    dap_cmd: "stepIn",
+                    cli_cmd: "step_in",
+                    help_text: "Step into the function called on the current source line",
+                    function_name: "step_in",
+                },
+                DebugCommand {
+                    dap_cmd: "stepOut",
+                    cli_cmd: "step_out",
+                    help_text: "Run until the current function returns",
+                    function_name: "step_out",
+                },
                 DebugCommand {
                     dap_cmd: "pause",
                     cli_cmd: "halt",
@@ -435,6 +457,18 @@ impl Debugger {
                     help_text: "",
                     function_name: "set_breakpoints",
                 },
+                DebugCommand {
+                    dap_cmd: "setFunctionBreakpoints",
+                    cli_cmd: "",
+                    help_text: "",
+                    function_name: "set_function_breakpoints",
+                },
+                DebugCommand {
+                    dap_cmd: "setExceptionBreakpoints",
+                    cli_cmd: "",
+                    help_text: "",
+                    function_name: "set_exception_breakpoints",
+                },
                 DebugCommand {
                     dap_cmd: "stackTrace",
                     cli_cmd: "stack",
@@ -526,12 +560,17 @@ impl Debugger {
                                 debug_adapter.send_event("continued", event_body);
                             }
                             CoreStatus::Halted(_) => {
+                                debug_adapter
+                                    .clear_temporary_breakpoint(&mut core_data.target_core);
                                 let event_body = Some(StoppedEventBody {
                                     reason: new_status.short_long_status().0.to_owned(),
                                     description: Some(new_status.short_long_status().1.to_owned()),
                                     thread_id: Some(core_data.target_core.id() as i64),
                                     preserve_focus_hint: Some(false),
-                                    text: None,
+                                    text: fault_description(
+                                        &mut core_data.target_core,
+                                        core_data.debug_info.as_ref(),
+                                    ),
                                     all_threads_stopped: Some(true),
                                     hit_breakpoint_ids: None,
                                 });
@@ -592,6 +631,8 @@ impl Debugger {
                         match valid_command.function_name {
                             "status" => debug_adapter.status(&mut core_data, &request),
                             "next" => debug_adapter.next(&mut core_data, &request),
+                            "step_in" => debug_adapter.step_in(&mut core_data, &request),
+                            "step_out" => debug_adapter.step_out(&mut core_data, &request),
                             "pause" => debug_adapter.pause(&mut core_data, &request),
                             "read_memory" => debug_adapter.read_memory(&mut core_data, &request),
                             "write" => debug_adapter.write(&mut core_data, &request),
@@ -617,6 +658,12 @@ impl Debugger {
                             "set_breakpoints" => {
                                 debug_adapter.set_breakpoints(&mut core_data, &request)
                             }
+                            "set_function_breakpoints" => {
+                                debug_adapter.set_function_breakpoints(&mut core_data, &request)
+                            }
+                            "set_exception_breakpoints" => {
+                                debug_adapter.set_exception_breakpoints(&mut core_data, &request)
+                            }
                             "stack_trace" => debug_adapter.stack_trace(&mut core_data, &request),
                             "scopes" => debug_adapter.scopes(&mut core_data, &request),
                             "source" => debug_adapter.source(&mut core_data, &request),
@@ -747,11 +794,22 @@ impl Debugger {
                 supports_read_memory_request: Some(true),
                 supports_restart_request: Some(false), // It is better (and cheap enough) to let the client kill and restart the debugadapter, than to try a in-process reset.
                 supports_terminate_request: Some(true),
+                supports_stepping_granularity: Some(true),
                 // supports_value_formatting_options: Some(true),
-                //supports_function_breakpoints: Some(true),
-                //TODO: Use DEMCR register to implement exception breakpoints
-                // supports_exception_options: Some(true),
-                // supports_exception_filter_options: Some (true),
+                supports_function_breakpoints: Some(true),
+                exception_breakpoint_filters: Some(
+                    VECTOR_CATCH_FILTERS
+                        .iter()
+                        .map(|(filter, label, _)| ExceptionBreakpointsFilter {
+                            filter: filter.to_string(),
+                            label: label.to_string(),
+                            description: None,
+                            default: Some(false),
+                            supports_condition: None,
+                            condition_description: None,
+                        })
+                        .collect(),
+                ),
                 ..Default::default()
             };
             debug_adapter.send_response(&request, Ok(Some(capabilities)));
@@ -887,6 +945,10 @@ impl Debugger {
                     dry_run: false,
                     skip_erase: false,
                     do_chip_erase: self.debugger_options.full_chip_erase,
+                    verify: self.debugger_options.verify_after_flashing,
+                    skip_unchanged_sectors: self.debugger_options.skip_unchanged_sectors,
+                    boot_from_ram: false,
+                    run_after_boot: false,
                 };
                 match download_file_with_options(
                     &mut session_data.session,
@@ -987,7 +1049,7 @@ pub fn reset_target_of_device(
     Ok(())
 }
 
-pub fn dump_memory(debugger_options: DebuggerOptions, loc: u32, words: u32) -> Result<()> {
+pub fn dump_memory(debugger_options: DebuggerOptions, loc: u64, words: u32) -> Result<()> {
     let mut session_data = start_session(&debugger_options)?;
     let mut target_core = attach_core(&mut session_data, &debugger_options)
         .unwrap()
@@ -1008,7 +1070,7 @@ pub fn dump_memory(debugger_options: DebuggerOptions, loc: u32, words: u32) -> R
     for word in 0..words {
         println!(
             "Addr 0x{:08x?}: 0x{:08x}",
-            loc + 4 * word,
+            loc + 4 * u64::from(word),
             data[word as usize]
         );
     }
@@ -1024,7 +1086,7 @@ pub fn download_program_fast(debugger_options: DebuggerOptions, path: &str) -> R
     Ok(())
 }
 
-pub fn trace_u32_on_target(debugger_options: DebuggerOptions, loc: u32) -> Result<()> {
+pub fn trace_u32_on_target(debugger_options: DebuggerOptions, loc: u64) -> Result<()> {
     use scroll::{Pwrite, LE};
     use std::io::prelude::*;
     use std::thread::sleep;
@@ -63,8 +63,8 @@ pub enum DebuggerError {
 }
 
 /* Some helper functions for StructOpt parsing */
-fn parse_hex(src: &str) -> Result<u32, std::num::ParseIntError> {
-    u32::from_str_radix(src, 16)
+fn parse_hex(src: &str) -> Result<u64, std::num::ParseIntError> {
+    u64::from_str_radix(src, 16)
 }
 // fn parse_server(src: &str) -> Result<SocketAddr, AddrParseError> {
 //     src.parse()
@@ -118,7 +118,7 @@ enum CliCommands {
 
         /// The address of the memory to dump from the target (in hexadecimal without 0x prefix)
         #[structopt(parse(try_from_str = parse_hex))]
-        loc: u32,
+        loc: u64,
         /// The amount of memory (in words) to dump
         words: u32,
     },
@@ -139,7 +139,7 @@ enum CliCommands {
 
         /// The address of the memory start trace (in hexadecimal without 0x prefix)
         #[structopt(parse(try_from_str = parse_hex))]
-        loc: u32,
+        loc: u64,
     },
 }
 
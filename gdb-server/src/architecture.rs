@@ -88,10 +88,10 @@ pub trait GdbTargetExt {
     /// See https://sourceware.org/gdb/onlinedocs/gdb/Memory-Map-Format.html#Memory-Map-Format
     fn gdb_memory_map(&self) -> String;
 
-    /// Target description in GDB XML Format.
+    /// Target description in GDB XML Format, for the core with number `core_id`.
     ///
     /// See https://sourceware.org/gdb/onlinedocs/gdb/Target-Descriptions.html#Target-Descriptions
-    fn target_description(&self) -> String;
+    fn target_description(&self, core_id: usize) -> String;
 }
 
 impl GdbTargetExt for probe_rs::Target {
@@ -131,7 +131,7 @@ impl GdbTargetExt for probe_rs::Target {
         xml_map
     }
 
-    fn target_description(&self) -> String {
+    fn target_description(&self, core_id: usize) -> String {
         // GDB-architectures
         //
         // - armv6-m      -> Core-M0
@@ -141,7 +141,13 @@ impl GdbTargetExt for probe_rs::Target {
         // - armv8-m.main -> Core-M33
         // - riscv:rv32   -> RISCV
 
-        let architecture = match self.core_type {
+        let core_type = self
+            .cores
+            .get(core_id)
+            .map(|core| core.core_type)
+            .unwrap_or(self.core_type);
+
+        let architecture = match core_type {
             CoreType::M0 => "armv6-m",
             CoreType::M3 => "armv7-m",
             CoreType::M4 | CoreType::M7 => "armv7e-m",
@@ -25,6 +25,13 @@ struct Opt {
         help = "Use this flag to reset and halt (instead of just a halt) the attached core after attaching to the target."
     )]
     reset_halt: bool,
+    #[structopt(
+        name = "core-index",
+        long = "core-index",
+        default_value = "0",
+        help = "The number of the core to debug, for targets with multiple cores."
+    )]
+    core_index: usize,
     #[structopt(
         name = "gdb-connection-string",
         long = "gdb-connection-string",
@@ -124,7 +131,7 @@ fn main_try() -> Result<()> {
         session
             .lock()
             .unwrap()
-            .core(0)?
+            .core(opt.core_index)?
             .reset_and_halt(Duration::from_millis(100))?;
     }
 
@@ -136,7 +143,7 @@ fn main_try() -> Result<()> {
         "Firing up GDB stub at {}",
         gdb_connection_string.as_ref().unwrap()
     );
-    if let Err(e) = probe_rs_gdb_server::run(gdb_connection_string, &session) {
+    if let Err(e) = probe_rs_gdb_server::run(gdb_connection_string, &session, opt.core_index) {
         eprintln!("During the execution of GDB an error was encountered:");
         eprintln!("{:?}", e);
     }
@@ -18,21 +18,31 @@ const CONNECTION_STRING: &str = "127.0.0.1:1337";
 /// This is the main entrypoint which we will call to start the GDB stub.
 /// This function is blocking. If you would like to use it concurently to other users of the session,
 /// please use a thread.
-pub fn run(connection_string: Option<impl Into<String>>, session: &Mutex<Session>) -> Result<()> {
+///
+/// The stub debugs the core with number `core_id` of the session.
+pub fn run(
+    connection_string: Option<impl Into<String>>,
+    session: &Mutex<Session>,
+    core_id: usize,
+) -> Result<()> {
     let connection_string = connection_string
         .map(|cs| cs.into())
         .unwrap_or_else(|| CONNECTION_STRING.to_owned());
     log::info!("GDB stub listening on {}", connection_string);
-    task::block_on(accept_loop(connection_string, session))
+    task::block_on(accept_loop(connection_string, session, core_id))
 }
 
 /// This function accepts any incomming connection.
-async fn accept_loop(addr: impl ToSocketAddrs, session: &Mutex<Session>) -> Result<()> {
+async fn accept_loop(
+    addr: impl ToSocketAddrs,
+    session: &Mutex<Session>,
+    core_id: usize,
+) -> Result<()> {
     let listener = TcpListener::bind(addr).await?;
 
     let mut incoming = listener.incoming();
     while let Some(stream) = incoming.next().await {
-        if let Err(e) = handle_connection(stream?, session).await {
+        if let Err(e) = handle_connection(stream?, session, core_id).await {
             log::error!(
                 "An error with the current connection has been encountered. It has been closed."
             );
@@ -43,7 +53,11 @@ async fn accept_loop(addr: impl ToSocketAddrs, session: &Mutex<Session>) -> Resu
 }
 
 /// Handle a single connection of a client
-async fn handle_connection(stream: TcpStream, session: &Mutex<Session>) -> Result<()> {
+async fn handle_connection(
+    stream: TcpStream,
+    session: &Mutex<Session>,
+    core_id: usize,
+) -> Result<()> {
     let (packet_stream_sender, packet_stream_receiver) = mpsc::unbounded();
     let (tbd_sender, tbd_receiver) = mpsc::unbounded();
 
@@ -55,7 +69,7 @@ async fn handle_connection(stream: TcpStream, session: &Mutex<Session>) -> Resul
         packet_stream_receiver,
     ));
 
-    super::worker::worker(tbd_receiver, packet_stream_sender, session).await?;
+    super::worker::worker(tbd_receiver, packet_stream_sender, session, core_id).await?;
 
     inbound_broker_handle.await?;
 
@@ -140,10 +140,9 @@ pub(crate) fn write_general_registers(reg_values: &str, mut core: Core) -> Optio
     for reg_num in (0..core.num_general_registers() as u32).into_iter() {
         let (addr, bytesize) = core.translate_gdb_register_number(reg_num)?;
 
-        // TODO: remove, when `Core::write_core_reg()` supports larger registers
-        if bytesize as usize > std::mem::size_of::<u32>() {
-            // Currently registers larger than 32 bits are not supported
-            log::warn!("Register {} is truncated, because probe-rs does not currently support registers longer than 32 bit", reg_num);
+        if bytesize as usize > std::mem::size_of::<u64>() {
+            // Currently registers larger than 64 bits are not supported
+            log::warn!("Register {} is truncated, because probe-rs does not currently support registers longer than 64 bit", reg_num);
         }
 
         let current_str_regval_end = current_str_regval_offset + bytesize as usize * 2;
@@ -164,14 +163,16 @@ pub(crate) fn write_general_registers(reg_values: &str, mut core: Core) -> Optio
             .as_bytes()
             .chunks(2)
             .enumerate()
-            // TODO: remove, when `Core::write_core_reg()` supports larger registers
-            .take(std::mem::size_of::<u32>())
+            .take(std::mem::size_of::<u64>())
         {
             value +=
-                u32::from_str_radix(std::str::from_utf8(ch).unwrap(), 16).unwrap() << (8 * exp);
+                u64::from_str_radix(std::str::from_utf8(ch).unwrap(), 16).unwrap() << (8 * exp);
         }
 
-        core.write_core_reg(addr, value).unwrap();
+        if let Err(e) = core.write_core_reg(addr, value) {
+            log::error!("Unable to write register {}: {}", reg_num, e);
+            return Some("E01".to_string());
+        }
 
         current_str_regval_offset = current_str_regval_end;
 
@@ -213,10 +214,9 @@ pub(crate) fn write_register(register: u32, hex_value: &str, mut core: Core) ->
 
     let (probe_rs_number, bytesize) = core.translate_gdb_register_number(register)?;
 
-    // TODO: remove, when `Core::write_core_reg()` supports larger registers
-    if bytesize as usize > std::mem::size_of::<u32>() {
-        // Currently registers larger than 32 bits are not supported
-        log::warn!("Register {} is truncated, because probe-rs does not currently support registers longer than 32 bit", register);
+    if bytesize as usize > std::mem::size_of::<u64>() {
+        // Currently registers larger than 64 bits are not supported
+        log::warn!("Register {} is truncated, because probe-rs does not currently support registers longer than 64 bit", register);
     }
 
     let mut value = 0;
@@ -225,18 +225,20 @@ pub(crate) fn write_register(register: u32, hex_value: &str, mut core: Core) ->
         .as_bytes()
         .chunks(2)
         .enumerate()
-        // TODO: remove, when `Core::write_core_reg()` supports larger registers
-        .take(std::mem::size_of::<u32>())
+        .take(std::mem::size_of::<u64>())
     {
-        value += u32::from_str_radix(std::str::from_utf8(ch).unwrap(), 16).unwrap() << (8 * exp);
+        value += u64::from_str_radix(std::str::from_utf8(ch).unwrap(), 16).unwrap() << (8 * exp);
     }
 
-    core.write_core_reg(probe_rs_number, value).unwrap();
+    if let Err(e) = core.write_core_reg(probe_rs_number, value) {
+        log::error!("Unable to write register {}: {}", register, e);
+        return Some("E01".to_string());
+    }
 
     reply_ok()
 }
 
-pub(crate) fn read_memory(address: u32, length: u32, mut core: Core) -> Option<String> {
+pub(crate) fn read_memory(address: u64, length: u32, mut core: Core) -> Option<String> {
     let mut readback_data = vec![0u8; length as usize];
     match core.read_8(address, &mut readback_data) {
         Ok(_) => Some(
@@ -286,20 +288,34 @@ pub(crate) fn step(mut core: Core, awaits_halt: &mut bool) -> Option<String> {
     Some("S05".into())
 }
 
-pub(crate) fn insert_hardware_break(address: u32, _kind: u32, mut core: Core) -> Option<String> {
-    core.set_hw_breakpoint(address).unwrap();
-    Some("OK".into())
+pub(crate) fn insert_hardware_break(address: u64, _kind: u32, mut core: Core) -> Option<String> {
+    match core.set_hw_breakpoint(address) {
+        Ok(()) => Some("OK".into()),
+        Err(e) => {
+            log::error!("Unable to set breakpoint at {:#010x}: {}", address, e);
+            Some("E01".into())
+        }
+    }
 }
 
-pub(crate) fn remove_hardware_break(address: u32, _kind: u32, mut core: Core) -> Option<String> {
-    core.clear_hw_breakpoint(address).unwrap();
-    Some("OK".into())
+pub(crate) fn remove_hardware_break(address: u64, _kind: u32, mut core: Core) -> Option<String> {
+    match core.clear_hw_breakpoint(address) {
+        Ok(()) => Some("OK".into()),
+        Err(e) => {
+            log::error!("Unable to clear breakpoint at {:#010x}: {}", address, e);
+            Some("E01".into())
+        }
+    }
 }
 
-pub(crate) fn write_memory(address: u32, data: &[u8], mut core: Core) -> Option<String> {
-    core.write_8(address, data).unwrap();
-
-    Some("OK".into())
+pub(crate) fn write_memory(address: u64, data: &[u8], mut core: Core) -> Option<String> {
+    match core.write_8(address, data) {
+        Ok(()) => Some("OK".into()),
+        Err(e) => {
+            log::error!("Unable to write memory at {:#010x}: {}", address, e);
+            Some("E01".into())
+        }
+    }
 }
 
 pub(crate) fn get_memory_map(session: &Session) -> Option<String> {
@@ -345,10 +361,14 @@ fn gdb_sanitize_file(data: &[u8], offset: u32, len: u32) -> Vec<u8> {
     }
 }
 
-pub(crate) fn read_target_description(session: &Session, annex: &[u8]) -> Option<String> {
+pub(crate) fn read_target_description(
+    session: &Session,
+    core_id: usize,
+    annex: &[u8],
+) -> Option<String> {
     // Only target.xml is supported
     if annex == b"target.xml" {
-        let description = session.target().target_description();
+        let description = session.target().target_description(core_id);
 
         Some(String::from_utf8(gdb_sanitize_file(description.as_bytes(), 0, 1000)).unwrap())
     } else {
@@ -5,7 +5,6 @@ use futures::future::FutureExt;
 use futures::select;
 use gdb_protocol::packet::{CheckedPacket, Kind as PacketKind};
 use probe_rs::Session;
-use std::convert::TryFrom;
 use std::{sync::Mutex, time::Duration};
 
 use crate::parser::parse_packet;
@@ -20,13 +19,14 @@ pub async fn worker(
     mut input_stream: Receiver<CheckedPacket>,
     output_stream: Sender<CheckedPacket>,
     session: &Mutex<Session>,
+    core_id: usize,
 ) -> ServerResult<()> {
     // When we first attach to the core, GDB expects us to halt the core, so we do this here when a new client connects.
     // If the core is already halted, nothing happens if we issue a halt command again, so we always do this no matter of core state.
     session
         .lock()
         .unwrap()
-        .core(0)?
+        .core(core_id)?
         .halt(Duration::from_millis(100))?;
 
     let mut awaits_halt = false;
@@ -36,14 +36,14 @@ pub async fn worker(
             potential_packet = input_stream.next().fuse() => {
                 if let Some(packet) = potential_packet {
                     log::warn!("WORKING {}", String::from_utf8_lossy(&packet.data));
-                    if handler(&session, &output_stream, &mut awaits_halt, packet).await? {
+                    if handler(&session, core_id, &output_stream, &mut awaits_halt, packet).await? {
                         break;
                     }
                 } else {
                     break
                 }
             },
-            _ = await_halt(session, &output_stream, &mut awaits_halt).fuse() => {}
+            _ = await_halt(session, core_id, &output_stream, &mut awaits_halt).fuse() => {}
         }
     }
     Ok(())
@@ -51,6 +51,7 @@ pub async fn worker(
 
 pub async fn handler(
     session: &Mutex<Session>,
+    core_id: usize,
     output_stream: &Sender<CheckedPacket>,
     awaits_halt: &mut bool,
     packet: CheckedPacket,
@@ -70,13 +71,13 @@ pub async fn handler(
             let mut session = session.lock().expect("Poisoned Mutex");
             match parsed_packet {
                 HaltReason => handlers::halt_reason(),
-                Continue => handlers::run(session.core(0)?, awaits_halt),
+                Continue => handlers::run(session.core(core_id)?, awaits_halt),
                 V(VPacket::QueryContSupport) => handlers::vcont_supported(),
                 Query(QueryPacket::Supported { .. }) => handlers::q_supported(),
                 Query(QueryPacket::Attached { .. }) => handlers::q_attached(),
                 Query(QueryPacket::Command(cmd)) => {
                     if cmd == b"reset" {
-                        handlers::reset_halt(session.core(0)?)
+                        handlers::reset_halt(session.core(core_id)?)
                     } else {
                         log::debug!("Unknown monitor command: '{:?}'", cmd);
                         Some(hex::encode(
@@ -86,30 +87,24 @@ pub async fn handler(
                     }
                 }
                 Query(QueryPacket::HostInfo) => handlers::host_info(),
-                ReadGeneralRegister => handlers::read_general_registers(session.core(0)?),
-                ReadRegisterHex(register) => handlers::read_register(register, session.core(0)?),
+                ReadGeneralRegister => handlers::read_general_registers(session.core(core_id)?),
+                ReadRegisterHex(register) => {
+                    handlers::read_register(register, session.core(core_id)?)
+                }
                 WriteGeneralRegister { reg_values } => {
-                    handlers::write_general_registers(&reg_values, session.core(0)?)
+                    handlers::write_general_registers(&reg_values, session.core(core_id)?)
                 }
                 WriteRegisterHex { address, value } => {
-                    handlers::write_register(address, &value, session.core(0)?)
+                    handlers::write_register(address, &value, session.core(core_id)?)
                 }
                 ReadMemory { address, length } => {
-                    // LLDB will send 64 bit addresses, which are not supported by probe-rs
-                    // yet.
-
-                    if let Ok(address) = u32::try_from(address) {
-                        handlers::read_memory(address, length, session.core(0)?)
-                    } else {
-                        //
-                        handlers::reply_empty()
-                    }
+                    handlers::read_memory(address, length, session.core(core_id)?)
                 }
                 Detach => handlers::detach(&mut break_due),
                 V(VPacket::Continue(action)) => match action {
-                    Action::Continue => handlers::run(session.core(0)?, awaits_halt),
-                    Action::Stop => handlers::stop(session.core(0)?, awaits_halt),
-                    Action::Step => handlers::step(session.core(0)?, awaits_halt),
+                    Action::Continue => handlers::run(session.core(core_id)?, awaits_halt),
+                    Action::Stop => handlers::stop(session.core(core_id)?, awaits_halt),
+                    Action::Step => handlers::step(session.core(core_id)?, awaits_halt),
                     other => {
                         log::warn!("vCont with action {:?} not supported", other);
                         handlers::reply_empty()
@@ -121,7 +116,7 @@ pub async fn handler(
                     kind,
                 } => match breakpoint_type {
                     BreakpointType::Hardware => {
-                        handlers::insert_hardware_break(address, kind, session.core(0)?)
+                        handlers::insert_hardware_break(address, kind, session.core(core_id)?)
                     }
                     other => {
                         log::warn!("Breakpoint type {:?} is not supported.", other);
@@ -134,7 +129,7 @@ pub async fn handler(
                     kind,
                 } => match breakpoint_type {
                     BreakpointType::Hardware => {
-                        handlers::remove_hardware_break(address, kind, session.core(0)?)
+                        handlers::remove_hardware_break(address, kind, session.core(core_id)?)
                     }
                     other => {
                         log::warn!("Breakpoint type {:?} is not supported.", other);
@@ -142,7 +137,7 @@ pub async fn handler(
                     }
                 },
                 WriteMemoryBinary { address, data } => {
-                    handlers::write_memory(address, &data, session.core(0)?)
+                    handlers::write_memory(address, &data, session.core(core_id)?)
                 }
                 Query(QueryPacket::Transfer { object, operation }) => {
                     use crate::parser::query::TransferOperation;
@@ -162,7 +157,7 @@ pub async fn handler(
                         b"features" => {
                             match operation {
                                 TransferOperation::Read { annex, .. } => {
-                                    handlers::read_target_description(&session, &annex)
+                                    handlers::read_target_description(&session, core_id, &annex)
                                 }
                                 TransferOperation::Write { .. } => {
                                     // not supported
@@ -176,7 +171,7 @@ pub async fn handler(
                         }
                     }
                 }
-                Interrupt => handlers::user_halt(session.core(0)?, awaits_halt),
+                Interrupt => handlers::user_halt(session.core(core_id)?, awaits_halt),
                 other => {
                     log::warn!("Unknown command: '{:?}'", other);
 
@@ -213,13 +208,14 @@ pub async fn handler(
 
 pub async fn await_halt(
     session: &Mutex<Session>,
+    core_id: usize,
     output_stream: &Sender<CheckedPacket>,
     await_halt: &mut bool,
 ) -> ServerResult<()> {
     task::sleep(Duration::from_millis(10)).await;
     if *await_halt {
         let mut session = session.lock().expect("Poisoned Mutex");
-        if session.core(0)?.core_halted().unwrap() {
+        if session.core(core_id)?.core_halted().unwrap() {
             let response = CheckedPacket::from_data(PacketKind::Packet, b"T05hwbreak:;".to_vec());
 
             let mut bytes = Vec::new();
@@ -232,3 +228,32 @@ pub async fn await_halt(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use probe_rs::{FakeProbe, Probe};
+
+    /// Handle `packet` with a session on a fake probe, and return the response.
+    fn response(packet: &[u8]) -> Vec<u8> {
+        let session = Probe::from_specific_probe(Box::new(FakeProbe::new()))
+            .attach("nrf51822_xxAA")
+            .unwrap();
+        let session = Mutex::new(session);
+
+        let (sender, mut receiver) = mpsc::unbounded();
+        let packet = CheckedPacket::from_data(PacketKind::Packet, packet.to_vec());
+
+        task::block_on(handler(&session, 0, &sender, &mut false, packet)).unwrap();
+
+        receiver.try_next().unwrap().unwrap().data
+    }
+
+    #[test]
+    fn address_above_32_bit() {
+        // LLDB sends 64 bit addresses, which the 32 bit core can not access.
+        assert_eq!(response(b"X100000000,1:a"), b"E01");
+        assert_eq!(response(b"Z1,100000000,2"), b"E01");
+        assert_eq!(response(b"z1,100000000,2"), b"E01");
+    }
+}
@@ -1,4 +1,5 @@
 use super::memory::MemoryRegion;
+use super::CoreType;
 use serde::{Deserialize, Serialize};
 
 /// A single chip variant.
@@ -27,4 +28,65 @@ pub struct Chip {
     ///
     /// [`ChipFamily::flash_algorithms`]: crate::ChipFamily::flash_algorithms
     pub flash_algorithms: Vec<String>,
+    /// The cores of the chip.
+    ///
+    /// This only has to be specified for chips with more than one core.
+    /// If it is empty, the chip has a single core of the type given in
+    /// [`ChipFamily::core`], which is accessed through AP 0 or hart 0.
+    ///
+    /// [`ChipFamily::core`]: crate::ChipFamily::core
+    #[serde(default)]
+    #[cfg_attr(not(feature = "bincode"), serde(skip_serializing_if = "Vec::is_empty"))]
+    pub cores: Vec<Core>,
+}
+
+/// A single core of a chip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Core {
+    /// The name of the core, e.g. `application` or `network`.
+    pub name: String,
+    /// The type of the core.
+    #[serde(rename = "type")]
+    pub core_type: CoreType,
+    /// How the core is accessed by the debug probe.
+    pub core_access_options: CoreAccessOptions,
+}
+
+impl Core {
+    /// The single core of a chip which does not list its cores explicitly.
+    pub fn default_for(core_type: CoreType) -> Self {
+        let core_access_options = match core_type {
+            CoreType::Riscv => CoreAccessOptions::Riscv(RiscvCoreAccessOptions { hart_id: 0 }),
+            _ => CoreAccessOptions::Arm(ArmCoreAccessOptions { ap: 0 }),
+        };
+
+        Core {
+            name: "main".to_owned(),
+            core_type,
+            core_access_options,
+        }
+    }
+}
+
+/// The architecture specific options needed to access a core.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CoreAccessOptions {
+    /// Options for an ARM core.
+    Arm(ArmCoreAccessOptions),
+    /// Options for a RISC-V core.
+    Riscv(RiscvCoreAccessOptions),
+}
+
+/// Options to access an ARM core.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArmCoreAccessOptions {
+    /// The number of the MEM-AP through which the core is accessed.
+    pub ap: u8,
+}
+
+/// Options to access a RISC-V core.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiscvCoreAccessOptions {
+    /// The index of the hart in the debug module.
+    pub hart_id: u32,
 }
@@ -1,4 +1,5 @@
 use super::chip::Chip;
+use super::configuration::ConfigurationRegion;
 use super::flash_algorithm::RawFlashAlgorithm;
 use jep106::JEP106Code;
 
@@ -23,7 +24,7 @@ pub enum TargetDescriptionSource {
 }
 
 /// Type of a supported core
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CoreType {
     /// ARM Cortex M0
     M0,
@@ -39,6 +40,16 @@ pub enum CoreType {
     Riscv,
 }
 
+/// Chip specific debug sequence, which is used instead of the default
+/// sequence to attach to, reset and recover the chips of a family.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DebugSequenceKind {
+    /// Nordic nRF52 chips, which can be locked with the APPROTECT register.
+    Nrf52,
+    /// STM32F2, STM32F4 and STM32F7 chips, which can be locked with the RDP option byte.
+    Stm32f4,
+}
+
 /// This describes a chip family with all its variants.
 ///
 /// This struct is usually read from a target description
@@ -63,6 +74,22 @@ pub struct ChipFamily {
     /// The name of the core type.
     /// E.g. `M0` or `M4`.
     pub core: CoreType,
+    /// The debug sequence used for this family.
+    /// If this is not set, the default sequence is used.
+    #[serde(default)]
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub debug_sequence: Option<DebugSequenceKind>,
+    /// The configuration region of the chips of this family,
+    /// like the option bytes of STM32 chips.
+    #[serde(default)]
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub configuration: Option<ConfigurationRegion>,
 
     #[serde(skip, default = "default_source")]
     /// Source of the target description, used for diagnostics
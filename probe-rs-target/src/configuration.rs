@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+/// The kind of a configuration region, which determines
+/// the sequence used to write the region.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConfigurationKind {
+    /// The option bytes of the STM32F2 and STM32F4, written through the `FLASH_OPTCR` register.
+    Stm32f4OptionBytes,
+    /// The UICR of the nRF52 series, written through the NVMC.
+    Nrf52Uicr,
+    /// The user row of the SAMD series, written through the NVMCTRL.
+    SamdUserRow,
+}
+
+/// A region of configuration data, like the option bytes
+/// of STM32 chips or the UICR of nRF chips.
+///
+/// The configuration data usually requires a special sequence to be written,
+/// and is only applied after a reset of the chip.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigurationRegion {
+    /// The kind of the region.
+    pub kind: ConfigurationKind,
+    /// The address the configuration data can be read from.
+    pub address: u32,
+    /// The size of the region in bytes, which has to be a multiple of 4.
+    pub size: u32,
+    /// The named fields of the configuration data.
+    pub fields: Vec<ConfigurationField>,
+}
+
+/// A named field of a [`ConfigurationRegion`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigurationField {
+    /// The name of the field, e.g. `BOR_LEV`.
+    pub name: String,
+    /// A description of the field.
+    #[serde(default)]
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "String::is_empty")
+    )]
+    pub description: String,
+    /// The offset of the 32-bit word which contains the field,
+    /// relative to the start of the region.
+    pub offset: u32,
+    /// The position of the lowest bit of the field in the word.
+    pub bit: u8,
+    /// The width of the field in bits.
+    pub width: u8,
+}
+
+impl ConfigurationField {
+    /// The mask of the field in its word, without shifting.
+    pub fn mask(&self) -> u32 {
+        let width = u32::from(self.width.min(32));
+
+        u32::MAX
+            .checked_shr(32 - width)
+            .and_then(|mask| mask.checked_shl(self.bit.into()))
+            .unwrap_or(0)
+    }
+}
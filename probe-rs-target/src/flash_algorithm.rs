@@ -30,6 +30,13 @@ pub struct RawFlashAlgorithm {
     pub pc_erase_sector: u32,
     /// Address of the `EraseAll()` entry point. Optional.
     pub pc_erase_all: Option<u32>,
+    /// Address of the `Verify()` entry point. Optional.
+    #[serde(default)]
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub pc_verify: Option<u32>,
     /// The offset from the start of RAM to the data section.
     pub data_section_offset: u32,
     /// The properties of the flash on the device.
@@ -13,14 +13,18 @@
 
 mod chip;
 mod chip_family;
+mod configuration;
 mod flash_algorithm;
 mod flash_properties;
 mod memory;
+mod target_extension;
 
-pub use chip::Chip;
-pub use chip_family::{ChipFamily, CoreType, TargetDescriptionSource};
+pub use chip::{ArmCoreAccessOptions, Chip, Core, CoreAccessOptions, RiscvCoreAccessOptions};
+pub use chip_family::{ChipFamily, CoreType, DebugSequenceKind, TargetDescriptionSource};
+pub use configuration::{ConfigurationField, ConfigurationKind, ConfigurationRegion};
 pub use flash_algorithm::RawFlashAlgorithm;
 pub use flash_properties::FlashProperties;
 pub use memory::{
     MemoryRange, MemoryRegion, NvmRegion, PageInfo, RamRegion, SectorDescription, SectorInfo,
 };
+pub use target_extension::TargetExtension;
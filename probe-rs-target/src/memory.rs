@@ -8,6 +8,15 @@ pub struct NvmRegion {
     pub range: Range<u32>,
     /// True if the chip boots from this memory
     pub is_boot_memory: bool,
+    /// Name of the flash algorithm used to program this region.
+    ///
+    /// If this is not set, the flash algorithm is selected based on its address range.
+    #[serde(default)]
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub flash_algorithm: Option<String>,
 }
 
 impl NvmRegion {
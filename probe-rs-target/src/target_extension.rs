@@ -0,0 +1,32 @@
+use super::flash_algorithm::RawFlashAlgorithm;
+use super::memory::MemoryRegion;
+
+use serde::{Deserialize, Serialize};
+
+/// A board-level extension of a chip description.
+///
+/// Boards often add memory to a chip, for example external QSPI flash which
+/// is mapped into the address space of the chip. A target extension describes
+/// the memory regions of such memory, and the flash algorithms to program it.
+/// It is applied on top of the target of the chip it extends.
+///
+/// Extensions can not describe any setup of the target, like the configuration
+/// of a QSPI controller which boards often do in an init script. If the memory
+/// needs such a setup, it has to be done by the `Init` function of the flash algorithm.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetExtension {
+    /// The name of the board.
+    pub name: String,
+    /// The name of the chip which is extended.
+    pub chip: String,
+    /// The memory regions which are added to the memory map of the chip.
+    ///
+    /// An NVM region can specify the flash algorithm used to program it.
+    #[serde(default)]
+    pub memory_map: Vec<MemoryRegion>,
+    /// The flash algorithms which are added to the algorithms of the chip.
+    #[serde(default)]
+    #[serde(deserialize_with = "super::chip_family::deserialize")]
+    #[serde(serialize_with = "super::chip_family::serialize")]
+    pub flash_algorithms: Vec<RawFlashAlgorithm>,
+}
@@ -134,6 +134,22 @@ pub trait ArmProbeInterface: SwoAccess + Debug + Send {
 
     fn read_from_rom_table(&mut self) -> Result<Option<ArmChipInfo>, ProbeRsError>;
 
+    /// Read a register of an access port by its address.
+    ///
+    /// This is used to access vendor specific access ports,
+    /// e.g. the CTRL-AP of the Nordic nRF52 series.
+    fn read_raw_ap_register(&mut self, port: GenericAp, address: u8) -> Result<u32, ProbeRsError>;
+
+    /// Write a register of an access port by its address.
+    ///
+    /// See also [`ArmProbeInterface::read_raw_ap_register`].
+    fn write_raw_ap_register(
+        &mut self,
+        port: GenericAp,
+        address: u8,
+        value: u32,
+    ) -> Result<(), ProbeRsError>;
+
     /// Deassert the target reset line
     ///
     /// When connecting under reset,
@@ -221,6 +237,55 @@ pub struct ArmCommunicationInterface {
 /// struct itself.
 pub trait DapProbe: DapAccess + DebugProbe {}
 
+/// Access to the registers of an access port by their address, which is shared
+/// by the implementations of [`ArmProbeInterface::read_raw_ap_register`] and
+/// [`ArmProbeInterface::write_raw_ap_register`].
+pub(crate) trait RawApAccess {
+    /// The probe through which the registers are accessed.
+    type Probe: DapAccess + ?Sized;
+
+    fn dap_probe(&mut self) -> &mut Self::Probe;
+
+    /// Select the access port `port`, and the register bank `ap_bank` in it.
+    fn select_ap_and_ap_bank(&mut self, port: u8, ap_bank: u8) -> Result<(), DebugProbeError>;
+
+    fn read_raw_register(&mut self, port: GenericAp, address: u8) -> Result<u32, DebugProbeError> {
+        self.select_ap_and_ap_bank(port.port_number(), address >> 4)?;
+
+        self.dap_probe().read_register(
+            PortType::AccessPort(u16::from(port.port_number())),
+            u16::from(address),
+        )
+    }
+
+    fn write_raw_register(
+        &mut self,
+        port: GenericAp,
+        address: u8,
+        value: u32,
+    ) -> Result<(), DebugProbeError> {
+        self.select_ap_and_ap_bank(port.port_number(), address >> 4)?;
+
+        self.dap_probe().write_register(
+            PortType::AccessPort(u16::from(port.port_number())),
+            u16::from(address),
+            value,
+        )
+    }
+}
+
+impl RawApAccess for ArmCommunicationInterface {
+    type Probe = dyn DapProbe;
+
+    fn dap_probe(&mut self) -> &mut Self::Probe {
+        self.probe.as_mut()
+    }
+
+    fn select_ap_and_ap_bank(&mut self, port: u8, ap_bank: u8) -> Result<(), DebugProbeError> {
+        ArmCommunicationInterface::select_ap_and_ap_bank(self, port, ap_bank)
+    }
+}
+
 impl ArmProbeInterface for ArmCommunicationInterface {
     fn memory_interface(&mut self, access_port: MemoryAp) -> Result<Memory<'_>, ProbeRsError> {
         ArmCommunicationInterface::memory_interface(self, access_port)
@@ -234,6 +299,19 @@ impl ArmProbeInterface for ArmCommunicationInterface {
         ArmCommunicationInterface::read_from_rom_table(self)
     }
 
+    fn read_raw_ap_register(&mut self, port: GenericAp, address: u8) -> Result<u32, ProbeRsError> {
+        Ok(RawApAccess::read_raw_register(self, port, address)?)
+    }
+
+    fn write_raw_ap_register(
+        &mut self,
+        port: GenericAp,
+        address: u8,
+        value: u32,
+    ) -> Result<(), ProbeRsError> {
+        Ok(RawApAccess::write_raw_register(self, port, address, value)?)
+    }
+
     fn num_access_ports(&self) -> usize {
         self.state.ap_information.len()
     }
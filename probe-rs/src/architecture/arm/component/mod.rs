@@ -1,4 +1,4 @@
-mod dwt;
+pub(crate) mod dwt;
 mod itm;
 mod tpiu;
 
@@ -113,7 +113,7 @@ fn setup_swv_vendor(
                 // STMicroelectronics:
                 // STM32 parts need TRACE_IOEN set to 1 and TRACE_MODE set to 00.
                 log::debug!("STMicroelectronics part detected, configuring DBGMCU");
-                const DBGMCU: u32 = 0xE004_2004;
+                const DBGMCU: u64 = 0xE004_2004;
                 let mut dbgmcu = core.read_word_32(DBGMCU)?;
                 dbgmcu |= 1 << 5;
                 dbgmcu &= !(0b00 << 6);
@@ -122,7 +122,7 @@ fn setup_swv_vendor(
             Some(id) if id == jep106::JEP106Code::new(0x02, 0x44) => {
                 // Nordic VLSI ASA
                 log::debug!("Nordic part detected, configuring CLOCK TRACECONFIG");
-                const CLOCK_TRACECONFIG: u32 = 0x4000_055C;
+                const CLOCK_TRACECONFIG: u64 = 0x4000_055C;
                 let mut traceconfig: u32 = 0;
                 traceconfig |= match config.tpiu_clk() {
                     4_000_000 => 3,
@@ -169,16 +169,16 @@ pub fn remove_swv_data_trace(
 
 /// Sets TRCENA in DEMCR to begin trace generation.
 pub fn enable_tracing(core: &mut Core) -> Result<(), Error> {
-    let mut demcr = Demcr(core.read_word_32(Demcr::ADDRESS)?);
+    let mut demcr = Demcr(core.read_word_32(Demcr::ADDRESS.into())?);
     demcr.set_dwtena(true);
-    core.write_word_32(Demcr::ADDRESS, demcr.into())?;
+    core.write_word_32(Demcr::ADDRESS.into(), demcr.into())?;
     Ok(())
 }
 
 /// Disables TRCENA in DEMCR to disable trace generation.
 pub fn disable_swv(core: &mut Core) -> Result<(), Error> {
-    let mut demcr = Demcr(core.read_word_32(Demcr::ADDRESS)?);
+    let mut demcr = Demcr(core.read_word_32(Demcr::ADDRESS.into())?);
     demcr.set_dwtena(false);
-    core.write_word_32(Demcr::ADDRESS, demcr.into())?;
+    core.write_word_32(Demcr::ADDRESS.into(), demcr.into())?;
     Ok(())
 }
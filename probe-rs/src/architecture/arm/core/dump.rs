@@ -0,0 +1,645 @@
+//! Core dumps of Cortex-M cores.
+//!
+//! A [`CoreDump`] holds the core registers and the contents of selected memory
+//! regions of a halted core. It is stored as an ELF core file, which contains R0 to
+//! R15 and the xPSR in a `NT_PRSTATUS` note using the layout of ARM Linux core files,
+//! and one loadable segment per memory region. This means the files can also be opened
+//! with GDB. The stack pointers, CONTROL and the floating-point registers are stored
+//! in an additional `probe-rs` note.
+//!
+//! A loaded core dump can be used as a read-only [`Core`], so that the stack can be
+//! unwound and variables can be inspected without the target being attached.
+
+use super::{register, ARM_REGISTER_FILE};
+use crate::{
+    config::{ArmCoreAccessOptions, CoreAccessOptions},
+    core::{
+        Architecture, CoreInformation, CoreInterface, CoreState, RegisterFile,
+        VectorCatchCondition, WatchpointKind,
+    },
+    elf_writer::ElfWriter,
+    memory::valid_32bit_address,
+    Core, CoreRegisterAddress, CoreStatus, Error, HaltReason, MemoryInterface,
+};
+
+use anyhow::anyhow;
+use object::{
+    elf::{FileHeader32, EM_ARM, ET_CORE, NT_PRSTATUS, PF_R, PF_W, PF_X, PT_LOAD, PT_NOTE},
+    endian::LittleEndian,
+    read::elf::{FileHeader, ProgramHeader},
+};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    fs::File,
+    io::{BufWriter, Write},
+    ops::Range,
+    path::Path,
+    time::Duration,
+};
+use thiserror::Error;
+
+/// Number of registers stored in the `NT_PRSTATUS` note: R0 to R15, and the xPSR.
+///
+/// These are also the register selectors 0 to 16 of the DCRSR.
+const PRSTATUS_REGISTER_COUNT: usize = 17;
+
+/// DCRSR register selectors of the MSP, the PSP, and CONTROL combined with FAULTMASK,
+/// BASEPRI and PRIMASK.
+const SPECIAL_REGISTERS: [u16; 3] = [0b1_0001, 0b1_0010, 0b1_0100];
+
+/// DCRSR register selector of the FPSCR.
+const FPSCR_REGSEL: u16 = 0b10_0001;
+
+/// DCRSR register selector of S0, which is followed by S1 to S31.
+const S0_REGSEL: u16 = 0b100_0000;
+
+/// Media and VFP Feature Register 0, which is zero if the core has no FPU.
+const MVFR0: u64 = 0xE000_EF40;
+
+/// Size of the `elf_prstatus` structure of 32 bit ARM Linux.
+const PRSTATUS_SIZE: usize = 148;
+
+/// Offset of the general purpose registers (`pr_reg`) in the `elf_prstatus` structure.
+const PRSTATUS_REGISTERS_OFFSET: usize = 72;
+
+/// Name of the note containing the registers.
+const NOTE_NAME: &str = "CORE";
+
+/// Name of the note containing the registers which are not part of `NT_PRSTATUS`.
+const EXTRA_NOTE_NAME: &str = "probe-rs";
+
+/// Type of the additional register note, which contains pairs of register selector and value.
+const NT_EXTRA_REGISTERS: u32 = 1;
+
+/// An error which occured while storing or loading a core dump.
+#[derive(Debug, Error)]
+pub enum CoreDumpError {
+    /// An I/O error occured while accessing the core dump file.
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+    /// The ELF file could not be parsed.
+    #[error("Could not read ELF file")]
+    Elf(#[from] object::read::Error),
+    /// The file is a valid ELF file, but not a core dump created by probe-rs.
+    #[error("Invalid core dump: {0}")]
+    InvalidDump(&'static str),
+}
+
+/// The contents of a single memory region in a core dump.
+#[derive(Debug, Clone)]
+pub(crate) struct MemoryDump {
+    address: u32,
+    data: Vec<u8>,
+}
+
+impl MemoryDump {
+    fn range(&self) -> Range<u64> {
+        let start = u64::from(self.address);
+
+        start..start + self.data.len() as u64
+    }
+}
+
+/// The registers and memory regions of a halted Cortex-M core.
+#[derive(Debug)]
+pub struct CoreDump {
+    /// Register values by their DCRSR register selector.
+    registers: BTreeMap<u16, u32>,
+    regions: Vec<MemoryDump>,
+    /// State used for the read-only core created by [`CoreDump::core`].
+    state: CoreState,
+}
+
+impl CoreDump {
+    /// Create a dump of `core`, containing the core registers and the memory in `ranges`.
+    ///
+    /// The core has to be halted.
+    pub fn capture(core: &mut Core, ranges: &[Range<u64>]) -> Result<Self, Error> {
+        if core.architecture() != Architecture::Arm {
+            return Err(Error::ArchitectureRequired(&[
+                "ARMv6-M", "ARMv7-M", "ARMv8-M",
+            ]));
+        }
+
+        if !core.core_halted()? {
+            return Err(Error::Other(anyhow!(
+                "The core has to be halted to create a core dump"
+            )));
+        }
+
+        let mut selectors: Vec<u16> = (0..PRSTATUS_REGISTER_COUNT as u16).collect();
+        selectors.extend_from_slice(&SPECIAL_REGISTERS);
+
+        if core.read_word_32(MVFR0)? != 0 {
+            selectors.push(FPSCR_REGSEL);
+            selectors.extend(S0_REGSEL..S0_REGSEL + 32);
+        }
+
+        let mut registers = BTreeMap::new();
+
+        for selector in selectors {
+            let value = core.read_core_reg(CoreRegisterAddress(selector))?;
+            registers.insert(selector, value as u32);
+        }
+
+        let mut regions = Vec::with_capacity(ranges.len());
+
+        for range in ranges {
+            let address = valid_32bit_address(range.start)?;
+            valid_32bit_address(range.end.saturating_sub(1))?;
+
+            let mut data = vec![0u8; range.end.saturating_sub(range.start) as usize];
+            core.read_8(range.start, &mut data)?;
+
+            log::debug!(
+                "Dumped memory region {:#010x}..{:#010x}",
+                range.start,
+                range.end
+            );
+
+            regions.push(MemoryDump { address, data });
+        }
+
+        Ok(Self::new(registers, regions))
+    }
+
+    pub(crate) fn new(registers: BTreeMap<u16, u32>, regions: Vec<MemoryDump>) -> Self {
+        CoreDump {
+            registers,
+            regions,
+            state: CoreState::new(0, CoreAccessOptions::Arm(ArmCoreAccessOptions { ap: 0 })),
+        }
+    }
+
+    /// The address ranges of the memory contained in the dump.
+    pub fn memory_ranges(&self) -> impl Iterator<Item = Range<u64>> + '_ {
+        self.regions.iter().map(MemoryDump::range)
+    }
+
+    /// Get a read-only [`Core`], which reads registers and memory from the dump.
+    ///
+    /// Reading memory which is not contained in the dump, and all operations which would
+    /// change the state of the core, return an error.
+    pub fn core(&mut self) -> Core<'_> {
+        let dumped_core = DumpedCore {
+            registers: &self.registers,
+            regions: &self.regions,
+        };
+
+        Core::new(dumped_core, &mut self.state)
+    }
+
+    /// Store the dump as an ELF core file at `path`.
+    pub fn store(&self, path: &Path) -> Result<(), CoreDumpError> {
+        let mut file = BufWriter::new(File::create(path)?);
+
+        self.write_elf(&mut file)?;
+
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /// Write the dump as an ELF core file.
+    pub fn write_elf(&self, writer: &mut impl Write) -> Result<(), CoreDumpError> {
+        // Only the registers are stored, all other fields of the structure are zero.
+        let mut prstatus = [0u8; PRSTATUS_SIZE];
+
+        for (selector, value) in self.registers.range(..PRSTATUS_REGISTER_COUNT as u16) {
+            let offset = PRSTATUS_REGISTERS_OFFSET + usize::from(*selector) * 4;
+            prstatus[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        let extra_registers: Vec<u8> = self
+            .registers
+            .range(PRSTATUS_REGISTER_COUNT as u16..)
+            .flat_map(|(selector, value)| {
+                let mut entry = [0u8; 8];
+                entry[..4].copy_from_slice(&u32::from(*selector).to_le_bytes());
+                entry[4..].copy_from_slice(&value.to_le_bytes());
+                entry
+            })
+            .collect();
+
+        let mut elf = ElfWriter::new(ET_CORE, EM_ARM);
+
+        elf.add_note(NOTE_NAME, NT_PRSTATUS, &prstatus);
+        elf.add_note(EXTRA_NOTE_NAME, NT_EXTRA_REGISTERS, &extra_registers);
+
+        for region in &self.regions {
+            elf.add_segment(region.address, &region.data, PF_R | PF_W | PF_X);
+        }
+
+        elf.write(writer)?;
+
+        Ok(())
+    }
+
+    /// Load a core dump from the ELF core file at `path`.
+    pub fn load(path: &Path) -> Result<Self, CoreDumpError> {
+        let data = std::fs::read(path)?;
+
+        Self::from_elf(&data)
+    }
+
+    /// Parse a core dump from the contents of an ELF core file.
+    pub fn from_elf(data: &[u8]) -> Result<Self, CoreDumpError> {
+        let header = FileHeader32::<LittleEndian>::parse(data)?;
+        let endian = header.endian()?;
+
+        if header.e_type(endian) != ET_CORE {
+            return Err(CoreDumpError::InvalidDump(
+                "The file is not an ELF core file",
+            ));
+        }
+
+        if header.e_machine(endian) != EM_ARM {
+            return Err(CoreDumpError::InvalidDump(
+                "The dump is not for an ARM core",
+            ));
+        }
+
+        let mut registers = None;
+        let mut extra_registers = Vec::new();
+        let mut regions = Vec::new();
+
+        for segment in header.program_headers(endian, data)? {
+            match segment.p_type(endian) {
+                PT_NOTE => {
+                    let mut notes = match segment.notes(endian, data)? {
+                        Some(notes) => notes,
+                        None => continue,
+                    };
+
+                    while let Some(note) = notes.next()? {
+                        if note.name() == NOTE_NAME.as_bytes() && note.n_type(endian) == NT_PRSTATUS
+                        {
+                            registers = Some(parse_prstatus(note.desc())?);
+                        } else if note.name() == EXTRA_NOTE_NAME.as_bytes()
+                            && note.n_type(endian) == NT_EXTRA_REGISTERS
+                        {
+                            extra_registers = parse_extra_registers(note.desc())?;
+                        }
+                    }
+                }
+                PT_LOAD => {
+                    let segment_data = segment.data(endian, data).map_err(|_| {
+                        CoreDumpError::InvalidDump("Failed to access data for an ELF segment")
+                    })?;
+
+                    regions.push(MemoryDump {
+                        address: segment.p_vaddr(endian),
+                        data: segment_data.to_vec(),
+                    });
+                }
+                _ => (),
+            }
+        }
+
+        let mut registers = registers.ok_or(CoreDumpError::InvalidDump(
+            "The dump does not contain any registers",
+        ))?;
+
+        registers.extend(extra_registers);
+
+        Ok(Self::new(registers, regions))
+    }
+}
+
+/// Extract the registers from the `NT_PRSTATUS` note.
+fn parse_prstatus(desc: &[u8]) -> Result<BTreeMap<u16, u32>, CoreDumpError> {
+    let register_bytes = desc
+        .get(PRSTATUS_REGISTERS_OFFSET..PRSTATUS_REGISTERS_OFFSET + PRSTATUS_REGISTER_COUNT * 4)
+        .ok_or(CoreDumpError::InvalidDump("The register note is too short"))?;
+
+    Ok(register_bytes
+        .chunks_exact(4)
+        .enumerate()
+        .map(|(selector, bytes)| {
+            let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            (selector as u16, value)
+        })
+        .collect())
+}
+
+/// Extract the register selectors and values from the additional register note.
+fn parse_extra_registers(desc: &[u8]) -> Result<Vec<(u16, u32)>, CoreDumpError> {
+    let entries = desc.chunks_exact(8);
+
+    if !entries.remainder().is_empty() {
+        return Err(CoreDumpError::InvalidDump(
+            "The additional register note has an invalid size",
+        ));
+    }
+
+    entries
+        .map(|entry| {
+            let selector = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]);
+            let value = u32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]);
+
+            let selector = u16::try_from(selector).map_err(|_| {
+                CoreDumpError::InvalidDump("The additional register note is invalid")
+            })?;
+
+            Ok((selector, value))
+        })
+        .collect()
+}
+
+/// A read-only core, backed by the contents of a [`CoreDump`].
+struct DumpedCore<'dump> {
+    registers: &'dump BTreeMap<u16, u32>,
+    regions: &'dump [MemoryDump],
+}
+
+impl<'dump> DumpedCore<'dump> {
+    /// Get the dumped memory at `address`, which has to be contained in a single region.
+    fn memory(&self, address: u64, len: usize) -> Result<&'dump [u8], Error> {
+        let end = address.checked_add(len as u64).ok_or_else(|| {
+            Error::Other(anyhow!(
+                "The memory at {:#010x} with length {:#x} is outside of the address space",
+                address,
+                len
+            ))
+        })?;
+
+        self.regions
+            .iter()
+            .find(|region| {
+                let range = region.range();
+                range.start <= address && end <= range.end
+            })
+            .map(|region| {
+                let offset = (address - u64::from(region.address)) as usize;
+                &region.data[offset..offset + len]
+            })
+            .ok_or_else(|| {
+                Error::Other(anyhow!(
+                    "The memory at {:#010x}..{:#010x} is not contained in the core dump",
+                    address,
+                    end
+                ))
+            })
+    }
+}
+
+fn read_only_error() -> Error {
+    Error::Other(anyhow!("A core dump can not be modified"))
+}
+
+impl<'dump> MemoryInterface for DumpedCore<'dump> {
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
+        let mut data = [0u32; 1];
+        self.read_32(address, &mut data)?;
+        Ok(data[0])
+    }
+
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        let mut data = [0u16; 1];
+        self.read_16(address, &mut data)?;
+        Ok(data[0])
+    }
+
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
+        Ok(self.memory(address, 1)?[0])
+    }
+
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
+        let bytes = self.memory(address, data.len() * 4)?;
+
+        for (value, bytes) in data.iter_mut().zip(bytes.chunks_exact(4)) {
+            *value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        Ok(())
+    }
+
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        let bytes = self.memory(address, data.len() * 2)?;
+
+        for (value, bytes) in data.iter_mut().zip(bytes.chunks_exact(2)) {
+            *value = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+
+        Ok(())
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        data.copy_from_slice(self.memory(address, data.len())?);
+        Ok(())
+    }
+
+    fn write_word_32(&mut self, _address: u64, _data: u32) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn write_word_16(&mut self, _address: u64, _data: u16) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn write_word_8(&mut self, _address: u64, _data: u8) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn write_32(&mut self, _address: u64, _data: &[u32]) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn write_16(&mut self, _address: u64, _data: &[u16]) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn write_8(&mut self, _address: u64, _data: &[u8]) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'dump> CoreInterface for DumpedCore<'dump> {
+    fn wait_for_core_halted(&mut self, _timeout: Duration) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn core_halted(&mut self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, Error> {
+        Ok(CoreStatus::Halted(HaltReason::Unknown))
+    }
+
+    fn halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        Ok(CoreInformation {
+            pc: self.read_core_reg(register::PC.address)?,
+        })
+    }
+
+    fn run(&mut self) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn reset_and_halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        Err(read_only_error())
+    }
+
+    fn step(&mut self) -> Result<CoreInformation, Error> {
+        Err(read_only_error())
+    }
+
+    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u64, Error> {
+        self.registers
+            .get(&address.0)
+            .map(|value| u64::from(*value))
+            .ok_or_else(|| {
+                Error::Other(anyhow!(
+                    "The register {:?} is not contained in the core dump",
+                    address
+                ))
+            })
+    }
+
+    fn write_core_reg(&mut self, _address: CoreRegisterAddress, _value: u64) -> anyhow::Result<()> {
+        Err(anyhow!("A core dump can not be modified"))
+    }
+
+    fn get_available_breakpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    fn enable_breakpoints(&mut self, _state: bool) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn set_breakpoint(&mut self, _bp_unit_index: usize, _addr: u64) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn clear_breakpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn get_available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    fn set_watchpoint(
+        &mut self,
+        _unit_index: usize,
+        _address: u64,
+        _size: u32,
+        _kind: WatchpointKind,
+    ) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn clear_watchpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn enable_vector_catch(&mut self, _condition: VectorCatchCondition) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn disable_vector_catch(&mut self, _condition: VectorCatchCondition) -> Result<(), Error> {
+        Err(read_only_error())
+    }
+
+    fn registers(&self) -> &'static RegisterFile {
+        &ARM_REGISTER_FILE
+    }
+
+    fn hw_breakpoints_enabled(&self) -> bool {
+        false
+    }
+
+    fn architecture(&self) -> Architecture {
+        Architecture::Arm
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_dump() -> CoreDump {
+        let mut registers: BTreeMap<u16, u32> = (0..PRSTATUS_REGISTER_COUNT as u16)
+            .map(|selector| (selector, 0x1000 + u32::from(selector)))
+            .collect();
+
+        registers.insert(SPECIAL_REGISTERS[0], 0x2000_0FF0);
+        registers.insert(SPECIAL_REGISTERS[1], 0x2000_1FF0);
+        registers.insert(S0_REGSEL + 31, 0x3F80_0000);
+
+        CoreDump::new(
+            registers,
+            vec![
+                MemoryDump {
+                    address: 0x2000_0000,
+                    data: (0..16).collect(),
+                },
+                MemoryDump {
+                    address: 0x2000_1000,
+                    data: vec![0xAA; 8],
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn write_and_parse_core_dump() {
+        let dump = test_dump();
+
+        let mut elf = Vec::new();
+        dump.write_elf(&mut elf).unwrap();
+
+        let parsed = CoreDump::from_elf(&elf).unwrap();
+
+        assert_eq!(parsed.registers, dump.registers);
+        assert_eq!(
+            parsed.memory_ranges().collect::<Vec<_>>(),
+            vec![0x2000_0000..0x2000_0010, 0x2000_1000..0x2000_1008]
+        );
+        assert_eq!(parsed.regions[0].data, dump.regions[0].data);
+    }
+
+    #[test]
+    fn read_from_dumped_core() {
+        let mut dump = test_dump();
+        let mut core = dump.core();
+
+        assert_eq!(core.read_core_reg(register::PC.address).unwrap(), 0x100F);
+        assert_eq!(core.read_core_reg(register::XPSR.address).unwrap(), 0x1010);
+        assert_eq!(
+            core.read_core_reg(CoreRegisterAddress(0b1_0010)).unwrap(),
+            0x2000_1FF0
+        );
+        assert_eq!(
+            core.read_core_reg(CoreRegisterAddress(S0_REGSEL + 31))
+                .unwrap(),
+            0x3F80_0000
+        );
+        // The FPSCR was not dumped.
+        assert!(core
+            .read_core_reg(CoreRegisterAddress(FPSCR_REGSEL))
+            .is_err());
+
+        assert_eq!(core.read_word_32(0x2000_0004).unwrap(), 0x0706_0504);
+        assert_eq!(core.read_word_16(0x2000_1002).unwrap(), 0xAAAA);
+
+        // Not contained in the dump
+        assert!(core.read_word_32(0x2000_000E).is_err());
+        assert!(core.read_word_8(0x2000_2000).is_err());
+        assert!(core.read_word_8(u64::MAX).is_err());
+
+        assert!(core.write_word_32(0x2000_0000, 0).is_err());
+        assert!(core.run().is_err());
+    }
+}
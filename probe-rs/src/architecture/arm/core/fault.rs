@@ -0,0 +1,766 @@
+//! Decoding of Cortex-M fault exceptions.
+//!
+//! When a Cortex-M core ends up in one of its fault handlers, the cause of the fault
+//! can be determined from the fault status registers in the System Control Block, and
+//! the state of the core before the fault from the exception frame on the stack.
+
+use super::register;
+use crate::{
+    core::{Architecture, CoreRegister},
+    debug::DebugInfo,
+    Core, CoreRegisterAddress, Error, MemoryInterface,
+};
+
+use bitfield::bitfield;
+use std::fmt;
+
+bitfield! {
+    /// CPUID Base Register, CPUID
+    #[derive(Copy, Clone)]
+    pub struct Cpuid(u32);
+    impl Debug;
+    pub implementer, _: 31, 24;
+    pub variant, _: 23, 20;
+    pub architecture, _: 19, 16;
+    pub partno, _: 15, 4;
+    pub revision, _: 3, 0;
+}
+
+impl Cpuid {
+    /// The core implements ARMv6-M, which has no configurable fault status registers.
+    fn is_armv6m(&self) -> bool {
+        self.architecture() == 0xC
+    }
+}
+
+impl From<u32> for Cpuid {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Cpuid> for u32 {
+    fn from(value: Cpuid) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for Cpuid {
+    const ADDRESS: u32 = 0xE000_ED00;
+    const NAME: &'static str = "CPUID";
+}
+
+bitfield! {
+    /// Configurable Fault Status Register, CFSR
+    ///
+    /// Combination of the MemManage (MMFSR), BusFault (BFSR) and UsageFault (UFSR)
+    /// status registers.
+    #[derive(Copy, Clone)]
+    pub struct Cfsr(u32);
+    impl Debug;
+    pub divbyzero, _: 25;
+    pub unaligned, _: 24;
+    pub stkof, _: 20;
+    pub nocp, _: 19;
+    pub invpc, _: 18;
+    pub invstate, _: 17;
+    pub undefinstr, _: 16;
+    pub bfarvalid, _: 15;
+    pub lsperr, _: 13;
+    pub stkerr, _: 12;
+    pub unstkerr, _: 11;
+    pub impreciserr, _: 10;
+    pub preciserr, _: 9;
+    pub ibuserr, _: 8;
+    pub mmarvalid, _: 7;
+    pub mlsperr, _: 5;
+    pub mstkerr, _: 4;
+    pub munstkerr, _: 3;
+    pub daccviol, _: 1;
+    pub iaccviol, _: 0;
+}
+
+impl From<u32> for Cfsr {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Cfsr> for u32 {
+    fn from(value: Cfsr) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for Cfsr {
+    const ADDRESS: u32 = 0xE000_ED28;
+    const NAME: &'static str = "CFSR";
+}
+
+bitfield! {
+    /// HardFault Status Register, HFSR
+    #[derive(Copy, Clone)]
+    pub struct Hfsr(u32);
+    impl Debug;
+    pub debugevt, _: 31;
+    pub forced, _: 30;
+    pub vecttbl, _: 1;
+}
+
+impl From<u32> for Hfsr {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Hfsr> for u32 {
+    fn from(value: Hfsr) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for Hfsr {
+    const ADDRESS: u32 = 0xE000_ED2C;
+    const NAME: &'static str = "HFSR";
+}
+
+/// MemManage Fault Address Register, MMFAR
+#[derive(Debug, Copy, Clone)]
+pub struct Mmfar(pub u32);
+
+impl From<u32> for Mmfar {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Mmfar> for u32 {
+    fn from(value: Mmfar) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for Mmfar {
+    const ADDRESS: u32 = 0xE000_ED34;
+    const NAME: &'static str = "MMFAR";
+}
+
+/// BusFault Address Register, BFAR
+#[derive(Debug, Copy, Clone)]
+pub struct Bfar(pub u32);
+
+impl From<u32> for Bfar {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Bfar> for u32 {
+    fn from(value: Bfar) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for Bfar {
+    const ADDRESS: u32 = 0xE000_ED38;
+    const NAME: &'static str = "BFAR";
+}
+
+bitfield! {
+    /// Secure Fault Status Register, SFSR
+    ///
+    /// Only present on ARMv8-M cores with the Security Extension.
+    #[derive(Copy, Clone)]
+    pub struct Sfsr(u32);
+    impl Debug;
+    pub lserr, _: 7;
+    pub sfarvalid, _: 6;
+    pub lsperr, _: 5;
+    pub invtran, _: 4;
+    pub auviol, _: 3;
+    pub inver, _: 2;
+    pub invis, _: 1;
+    pub invep, _: 0;
+}
+
+impl From<u32> for Sfsr {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Sfsr> for u32 {
+    fn from(value: Sfsr) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for Sfsr {
+    const ADDRESS: u32 = 0xE000_EDE4;
+    const NAME: &'static str = "SFSR";
+}
+
+/// Secure Fault Address Register, SFAR
+#[derive(Debug, Copy, Clone)]
+pub struct Sfar(pub u32);
+
+impl From<u32> for Sfar {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Sfar> for u32 {
+    fn from(value: Sfar) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for Sfar {
+    const ADDRESS: u32 = 0xE000_EDE8;
+    const NAME: &'static str = "SFAR";
+}
+
+/// The fault exception a core is currently handling.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FaultException {
+    HardFault,
+    MemManage,
+    BusFault,
+    UsageFault,
+    SecureFault,
+}
+
+impl FaultException {
+    /// Get the fault exception for an exception number, as found in the IPSR.
+    pub fn from_exception_number(number: u32) -> Option<Self> {
+        match number {
+            3 => Some(FaultException::HardFault),
+            4 => Some(FaultException::MemManage),
+            5 => Some(FaultException::BusFault),
+            6 => Some(FaultException::UsageFault),
+            7 => Some(FaultException::SecureFault),
+            _ => None,
+        }
+    }
+}
+
+/// A cause of a fault, as indicated by the fault status registers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FaultKind {
+    /// Instruction fetch from a location which does not permit execution (IACCVIOL).
+    InstructionAccessViolation,
+    /// Data access to a location which does not permit the access (DACCVIOL).
+    DataAccessViolation,
+    /// MemManage fault on unstacking for a return from exception (MUNSTKERR).
+    MemManageUnstacking,
+    /// MemManage fault on stacking for exception entry (MSTKERR).
+    MemManageStacking,
+    /// MemManage fault during lazy floating-point state preservation (MLSPERR).
+    MemManageLazyFpPreservation,
+    /// Bus error on an instruction prefetch (IBUSERR).
+    InstructionBusError,
+    /// Precise data bus error (PRECISERR).
+    PreciseDataBusError,
+    /// Imprecise data bus error (IMPRECISERR).
+    ImpreciseDataBusError,
+    /// BusFault on unstacking for a return from exception (UNSTKERR).
+    BusFaultUnstacking,
+    /// BusFault on stacking for exception entry (STKERR).
+    BusFaultStacking,
+    /// BusFault during lazy floating-point state preservation (LSPERR).
+    BusFaultLazyFpPreservation,
+    /// Execution of an undefined instruction (UNDEFINSTR).
+    UndefinedInstruction,
+    /// Instruction executed with an invalid EPSR value, e.g. in ARM state (INVSTATE).
+    InvalidState,
+    /// Invalid EXC_RETURN value loaded into the PC (INVPC).
+    InvalidPc,
+    /// Access to a coprocessor which is not present or disabled (NOCP).
+    NoCoprocessor,
+    /// Stack pointer limit violation, only on ARMv8-M (STKOF).
+    StackOverflow,
+    /// Unaligned memory access (UNALIGNED).
+    UnalignedAccess,
+    /// Integer division by zero (DIVBYZERO).
+    DivideByZero,
+    /// BusFault on a vector table read during exception processing (VECTTBL).
+    VectorTableRead,
+    /// A configurable fault was escalated to a HardFault (FORCED).
+    Forced,
+    /// A debug event occurred while halting debug was disabled (DEBUGEVT).
+    DebugEvent,
+    /// Branch from Non-secure state to a Secure address which is not a valid entry point (INVEP).
+    InvalidEntryPoint,
+    /// Invalid integrity signature in the exception frame on unstacking (INVIS).
+    InvalidIntegritySignature,
+    /// Invalid EXC_RETURN value on a return from a Secure exception (INVER).
+    InvalidExceptionReturn,
+    /// Non-secure access to a Secure address (AUVIOL).
+    AttributionUnitViolation,
+    /// Branch to Non-secure code without the required state transition (INVTRAN).
+    InvalidTransition,
+    /// SecureFault during lazy floating-point state preservation (LSPERR).
+    SecureFaultLazyFpPreservation,
+    /// Error in the lazy floating-point state preservation on exception return (LSERR).
+    LazyStateError,
+}
+
+impl fmt::Display for FaultKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            FaultKind::InstructionAccessViolation => "instruction access violation",
+            FaultKind::DataAccessViolation => "data access violation",
+            FaultKind::MemManageUnstacking => "MemManage fault on unstacking",
+            FaultKind::MemManageStacking => "MemManage fault on stacking",
+            FaultKind::MemManageLazyFpPreservation => {
+                "MemManage fault during lazy floating-point state preservation"
+            }
+            FaultKind::InstructionBusError => "instruction bus error",
+            FaultKind::PreciseDataBusError => "precise data bus error",
+            FaultKind::ImpreciseDataBusError => "imprecise data bus error",
+            FaultKind::BusFaultUnstacking => "BusFault on unstacking",
+            FaultKind::BusFaultStacking => "BusFault on stacking",
+            FaultKind::BusFaultLazyFpPreservation => {
+                "BusFault during lazy floating-point state preservation"
+            }
+            FaultKind::UndefinedInstruction => "undefined instruction",
+            FaultKind::InvalidState => "invalid execution state",
+            FaultKind::InvalidPc => "invalid PC load on exception return",
+            FaultKind::NoCoprocessor => "coprocessor not available",
+            FaultKind::StackOverflow => "stack overflow",
+            FaultKind::UnalignedAccess => "unaligned access",
+            FaultKind::DivideByZero => "divide by zero",
+            FaultKind::VectorTableRead => "BusFault on vector table read",
+            FaultKind::Forced => "escalated to HardFault",
+            FaultKind::DebugEvent => "debug event",
+            FaultKind::InvalidEntryPoint => "invalid Secure entry point",
+            FaultKind::InvalidIntegritySignature => "invalid integrity signature",
+            FaultKind::InvalidExceptionReturn => "invalid exception return",
+            FaultKind::AttributionUnitViolation => "attribution unit violation",
+            FaultKind::InvalidTransition => "invalid transition to Non-secure state",
+            FaultKind::SecureFaultLazyFpPreservation => {
+                "SecureFault during lazy floating-point state preservation"
+            }
+            FaultKind::LazyStateError => "lazy floating-point state error",
+        };
+
+        f.write_str(description)
+    }
+}
+
+/// Decode the fault causes indicated by the CFSR and HFSR registers.
+pub fn decode_fault_status(cfsr: Cfsr, hfsr: Hfsr) -> Vec<FaultKind> {
+    let flags = [
+        (cfsr.iaccviol(), FaultKind::InstructionAccessViolation),
+        (cfsr.daccviol(), FaultKind::DataAccessViolation),
+        (cfsr.munstkerr(), FaultKind::MemManageUnstacking),
+        (cfsr.mstkerr(), FaultKind::MemManageStacking),
+        (cfsr.mlsperr(), FaultKind::MemManageLazyFpPreservation),
+        (cfsr.ibuserr(), FaultKind::InstructionBusError),
+        (cfsr.preciserr(), FaultKind::PreciseDataBusError),
+        (cfsr.impreciserr(), FaultKind::ImpreciseDataBusError),
+        (cfsr.unstkerr(), FaultKind::BusFaultUnstacking),
+        (cfsr.stkerr(), FaultKind::BusFaultStacking),
+        (cfsr.lsperr(), FaultKind::BusFaultLazyFpPreservation),
+        (cfsr.undefinstr(), FaultKind::UndefinedInstruction),
+        (cfsr.invstate(), FaultKind::InvalidState),
+        (cfsr.invpc(), FaultKind::InvalidPc),
+        (cfsr.nocp(), FaultKind::NoCoprocessor),
+        (cfsr.stkof(), FaultKind::StackOverflow),
+        (cfsr.unaligned(), FaultKind::UnalignedAccess),
+        (cfsr.divbyzero(), FaultKind::DivideByZero),
+        (hfsr.vecttbl(), FaultKind::VectorTableRead),
+        (hfsr.forced(), FaultKind::Forced),
+        (hfsr.debugevt(), FaultKind::DebugEvent),
+    ];
+
+    flags
+        .iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, kind)| *kind)
+        .collect()
+}
+
+/// Decode the fault causes indicated by the SFSR register.
+pub fn decode_secure_fault_status(sfsr: Sfsr) -> Vec<FaultKind> {
+    let flags = [
+        (sfsr.invep(), FaultKind::InvalidEntryPoint),
+        (sfsr.invis(), FaultKind::InvalidIntegritySignature),
+        (sfsr.inver(), FaultKind::InvalidExceptionReturn),
+        (sfsr.auviol(), FaultKind::AttributionUnitViolation),
+        (sfsr.invtran(), FaultKind::InvalidTransition),
+        (sfsr.lsperr(), FaultKind::SecureFaultLazyFpPreservation),
+        (sfsr.lserr(), FaultKind::LazyStateError),
+    ];
+
+    flags
+        .iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, kind)| *kind)
+        .collect()
+}
+
+/// The registers which were stacked by the core on exception entry.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ExceptionFrame {
+    /// Address of the exception frame on the stack.
+    ///
+    /// On ARMv8-M cores, this is the address of the basic frame, which is above
+    /// the additional state context, if it was pushed as well.
+    pub address: u32,
+    pub r0: u32,
+    pub r1: u32,
+    pub r2: u32,
+    pub r3: u32,
+    pub r12: u32,
+    pub lr: u32,
+    pub pc: u32,
+    pub xpsr: u32,
+    /// The frame also contains the floating-point context.
+    pub fp_context: bool,
+}
+
+impl ExceptionFrame {
+    /// Decode the basic exception frame, which consists of eight words.
+    fn from_words(address: u32, words: &[u32; 8], fp_context: bool) -> Self {
+        ExceptionFrame {
+            address,
+            r0: words[0],
+            r1: words[1],
+            r2: words[2],
+            r3: words[3],
+            r12: words[4],
+            lr: words[5],
+            pc: words[6],
+            xpsr: words[7],
+            fp_context,
+        }
+    }
+
+    /// The value of the stack pointer before the frame was pushed.
+    ///
+    /// This takes the floating-point context and the alignment padding,
+    /// which is indicated by bit 9 of the stacked xPSR, into account.
+    pub fn stack_pointer_before_exception(&self) -> u32 {
+        let size = if self.fp_context { 0x68 } else { 0x20 };
+        let padding = if self.xpsr & (1 << 9) != 0 { 4 } else { 0 };
+
+        self.address + size + padding
+    }
+}
+
+/// `EXC_RETURN` value, which is loaded into the LR on exception entry.
+#[derive(Debug, Copy, Clone)]
+struct ExcReturn(u32);
+
+impl ExcReturn {
+    fn from_lr(lr: u32) -> Option<Self> {
+        if lr >> 24 == 0xFF {
+            Some(ExcReturn(lr))
+        } else {
+            None
+        }
+    }
+
+    /// The exception frame was pushed onto the process stack.
+    fn uses_psp(&self) -> bool {
+        self.0 & (1 << 2) != 0
+    }
+
+    /// The exception frame contains the floating-point context.
+    fn fp_context(&self) -> bool {
+        self.0 & (1 << 4) == 0
+    }
+
+    /// The offset of the basic exception frame from the stack pointer.
+    ///
+    /// On ARMv8-M cores with the Security Extension, the additional state context is
+    /// pushed below the basic frame, when the callee saved registers were stacked (DCRS is 0).
+    /// It consists of the integrity signature, a reserved word and R4 to R11.
+    fn frame_offset(&self) -> u32 {
+        if self.0 & (1 << 5) == 0 {
+            0x28
+        } else {
+            0
+        }
+    }
+}
+
+/// Report of a fault the core is currently handling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaultReport {
+    /// The fault exception the core is in.
+    pub exception: FaultException,
+    /// The fault causes indicated by the fault status registers.
+    ///
+    /// This is always empty on ARMv6-M cores, which don't have fault status registers.
+    pub causes: Vec<FaultKind>,
+    /// The address which caused the fault, if it is known.
+    pub fault_address: Option<u32>,
+    /// The registers stacked on exception entry, if they could be located.
+    pub frame: Option<ExceptionFrame>,
+}
+
+impl FaultReport {
+    /// Display the report with the symbols containing the faulting address and the stacked PC and LR.
+    pub fn with_symbols<'a>(&'a self, debug_info: &'a DebugInfo) -> impl fmt::Display + 'a {
+        SymbolizedFaultReport {
+            report: self,
+            debug_info: Some(debug_info),
+        }
+    }
+}
+
+impl fmt::Display for FaultReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        SymbolizedFaultReport {
+            report: self,
+            debug_info: None,
+        }
+        .fmt(f)
+    }
+}
+
+struct SymbolizedFaultReport<'a> {
+    report: &'a FaultReport,
+    debug_info: Option<&'a DebugInfo>,
+}
+
+impl SymbolizedFaultReport<'_> {
+    /// Write the symbol containing `address`, if it is known.
+    fn write_symbol(&self, f: &mut fmt::Formatter<'_>, address: u32) -> fmt::Result {
+        let symbol = self
+            .debug_info
+            .and_then(|debug_info| debug_info.get_symbol_at(u64::from(address)));
+
+        match symbol {
+            Some((symbol, 0)) => write!(f, " <{}>", symbol.name),
+            Some((symbol, offset)) => write!(f, " <{}+{:#x}>", symbol.name, offset),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for SymbolizedFaultReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = self.report;
+
+        write!(f, "{:?}", report.exception)?;
+
+        if !report.causes.is_empty() {
+            let causes: Vec<String> = report.causes.iter().map(|c| c.to_string()).collect();
+            write!(f, ": {}", causes.join(", "))?;
+        }
+
+        if let Some(address) = report.fault_address {
+            write!(f, "\nFaulting address: {:#010x}", address)?;
+            self.write_symbol(f, address)?;
+        }
+
+        if let Some(frame) = &report.frame {
+            write!(f, "\nStacked PC: {:#010x}", frame.pc)?;
+            self.write_symbol(f, frame.pc)?;
+            write!(f, ", LR: {:#010x}", frame.lr)?;
+            // The return address of Thumb code has the lowest bit set.
+            self.write_symbol(f, frame.lr & !1)?;
+            write!(f, ", xPSR: {:#010x}", frame.xpsr)?;
+
+            if frame.fp_context {
+                write!(f, " (with floating-point context)")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Read the fault status of a halted Cortex-M core.
+///
+/// Returns `None` if the core is not currently executing a fault handler.
+pub fn read_fault_report(core: &mut Core) -> Result<Option<FaultReport>, Error> {
+    if core.architecture() != Architecture::Arm {
+        return Err(Error::ArchitectureRequired(&[
+            "ARMv6-M", "ARMv7-M", "ARMv8-M",
+        ]));
+    }
+
+    let xpsr = core.read_core_reg(register::XPSR.address)? as u32;
+
+    // The exception number is stored in the IPSR, bits [8:0] of the xPSR.
+    let exception = match FaultException::from_exception_number(xpsr & 0x1FF) {
+        Some(exception) => exception,
+        None => return Ok(None),
+    };
+
+    let cpuid = Cpuid(core.read_word_32(Cpuid::ADDRESS.into())?);
+
+    let (causes, fault_address) = if cpuid.is_armv6m() {
+        (vec![], None)
+    } else if exception == FaultException::SecureFault {
+        // The SFSR is only present on cores with the Security Extension,
+        // which are the only ones which can take a SecureFault.
+        let sfsr = Sfsr(core.read_word_32(Sfsr::ADDRESS.into())?);
+
+        let fault_address = if sfsr.sfarvalid() {
+            Some(core.read_word_32(Sfar::ADDRESS.into())?)
+        } else {
+            None
+        };
+
+        (decode_secure_fault_status(sfsr), fault_address)
+    } else {
+        let cfsr = Cfsr(core.read_word_32(Cfsr::ADDRESS.into())?);
+        let hfsr = Hfsr(core.read_word_32(Hfsr::ADDRESS.into())?);
+
+        let fault_address = if cfsr.mmarvalid() {
+            Some(core.read_word_32(Mmfar::ADDRESS.into())?)
+        } else if cfsr.bfarvalid() {
+            Some(core.read_word_32(Bfar::ADDRESS.into())?)
+        } else {
+            None
+        };
+
+        (decode_fault_status(cfsr, hfsr), fault_address)
+    };
+
+    let lr = core.read_core_reg(register::LR.address)? as u32;
+    let msp = core.read_core_reg(MSP_REGSEL)? as u32;
+
+    // The LR only contains the EXC_RETURN value until the fault handler
+    // calls another function, afterwards the exception frame can't be located.
+    let frame = read_exception_frame(core, lr, msp)?;
+
+    Ok(Some(FaultReport {
+        exception,
+        causes,
+        fault_address,
+        frame,
+    }))
+}
+
+/// Read the exception frame of an exception handler, if `lr` contains an `EXC_RETURN` value.
+///
+/// `msp` is the value of the main stack pointer on entry to the handler, which is where
+/// the frame is located if it was pushed onto the main stack. Frames on the process stack
+/// are read at the current PSP, because exception handlers never use the process stack.
+pub(crate) fn read_exception_frame(
+    core: &mut Core,
+    lr: u32,
+    msp: u32,
+) -> Result<Option<ExceptionFrame>, Error> {
+    let exc_return = match ExcReturn::from_lr(lr) {
+        Some(exc_return) => exc_return,
+        None => return Ok(None),
+    };
+
+    let stack_pointer = if exc_return.uses_psp() {
+        core.read_core_reg(PSP_REGSEL)? as u32
+    } else {
+        msp
+    };
+    let address = stack_pointer + exc_return.frame_offset();
+
+    let mut words = [0u32; 8];
+    core.read_32(address.into(), &mut words)?;
+
+    Ok(Some(ExceptionFrame::from_words(
+        address,
+        &words,
+        exc_return.fp_context(),
+    )))
+}
+
+/// Register selector of the main stack pointer in the DCRSR.
+const MSP_REGSEL: CoreRegisterAddress = CoreRegisterAddress(0b1_0001);
+
+/// Register selector of the process stack pointer in the DCRSR.
+const PSP_REGSEL: CoreRegisterAddress = CoreRegisterAddress(0b1_0010);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_forced_hard_fault() {
+        // UNDEFINSTR escalated to a HardFault.
+        let causes = decode_fault_status(Cfsr(1 << 16), Hfsr(1 << 30));
+
+        assert_eq!(
+            causes,
+            vec![FaultKind::UndefinedInstruction, FaultKind::Forced]
+        );
+    }
+
+    #[test]
+    fn decode_precise_bus_fault() {
+        // PRECISERR with a valid BFAR.
+        let cfsr = Cfsr((1 << 9) | (1 << 15));
+
+        assert!(cfsr.bfarvalid());
+        assert_eq!(
+            decode_fault_status(cfsr, Hfsr(0)),
+            vec![FaultKind::PreciseDataBusError]
+        );
+    }
+
+    #[test]
+    fn decode_secure_fault() {
+        // AUVIOL with a valid SFAR.
+        let sfsr = Sfsr((1 << 3) | (1 << 6));
+
+        assert!(sfsr.sfarvalid());
+        assert_eq!(
+            decode_secure_fault_status(sfsr),
+            vec![FaultKind::AttributionUnitViolation]
+        );
+
+        assert_eq!(
+            decode_secure_fault_status(Sfsr(0b1001_0001)),
+            vec![
+                FaultKind::InvalidEntryPoint,
+                FaultKind::InvalidTransition,
+                FaultKind::LazyStateError
+            ]
+        );
+    }
+
+    #[test]
+    fn exc_return_stack_selection() {
+        // Return to thread mode, using the PSP, without FP context.
+        let exc_return = ExcReturn::from_lr(0xFFFF_FFFD).unwrap();
+        assert!(exc_return.uses_psp());
+        assert!(!exc_return.fp_context());
+
+        // Return to handler mode, using the MSP, with FP context.
+        let exc_return = ExcReturn::from_lr(0xFFFF_FFE1).unwrap();
+        assert!(!exc_return.uses_psp());
+        assert!(exc_return.fp_context());
+
+        assert!(ExcReturn::from_lr(0x0800_1235).is_none());
+    }
+
+    #[test]
+    fn exc_return_additional_state_context() {
+        let exc_return = ExcReturn::from_lr(0xFFFF_FFFD).unwrap();
+        assert_eq!(exc_return.frame_offset(), 0);
+
+        // A secure thread interrupted by a non-secure exception, DCRS is cleared.
+        let exc_return = ExcReturn::from_lr(0xFFFF_FFDC).unwrap();
+        assert!(exc_return.uses_psp());
+        assert_eq!(exc_return.frame_offset(), 0x28);
+    }
+
+    #[test]
+    fn stack_pointer_before_exception() {
+        let mut words = [0u32; 8];
+
+        let frame = ExceptionFrame::from_words(0x2000_1000, &words, false);
+        assert_eq!(frame.stack_pointer_before_exception(), 0x2000_1020);
+
+        // The stack was realigned to 8 bytes on exception entry.
+        words[7] = 1 << 9;
+        let frame = ExceptionFrame::from_words(0x2000_1000, &words, true);
+        assert_eq!(frame.stack_pointer_before_exception(), 0x2000_106C);
+    }
+}
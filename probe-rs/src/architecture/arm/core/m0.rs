@@ -1,10 +1,14 @@
-use super::{reset_catch_clear, reset_catch_set, CortexState, Dfsr, ARM_REGISTER_FILE};
+use super::{
+    dwt_available_watchpoint_units, dwt_clear_watchpoint, dwt_set_watchpoint, reset_catch_clear,
+    reset_catch_set, vector_catch_update, CortexState, Dfsr, ARM_REGISTER_FILE,
+};
 use crate::core::{
-    Architecture, CoreInformation, CoreInterface, CoreRegister, CoreRegisterAddress,
-    RegisterDescription, RegisterFile, RegisterKind,
+    valid_32bit_register_value, Architecture, CoreInformation, CoreInterface, CoreRegister,
+    CoreRegisterAddress, RegisterDescription, RegisterFile, RegisterKind, VectorCatchCondition,
+    WatchpointKind,
 };
 use crate::error::Error;
-use crate::memory::Memory;
+use crate::memory::{valid_32bit_address, Memory};
 use crate::{CoreStatus, DebugProbeError, HaltReason, MemoryInterface};
 use anyhow::Result;
 use bitfield::bitfield;
@@ -278,7 +282,7 @@ impl<'probe> M0<'probe> {
             } else if dhcsr.s_halt() {
                 let dfsr = Dfsr(memory.read_word_32(Dfsr::ADDRESS)?);
 
-                let reason = dfsr.halt_reason();
+                let reason = dfsr.read_halt_reason(&mut memory)?;
 
                 log::debug!("Core was halted when connecting, reason: {:?}", reason);
 
@@ -301,6 +305,15 @@ impl<'probe> M0<'probe> {
     }
 }
 
+/// ARMv6-M only implements the vector catch for HardFault, the only fault exception.
+fn armv6m_vector_catch_supported(condition: VectorCatchCondition) -> Result<(), Error> {
+    match condition {
+        VectorCatchCondition::HardFault => Ok(()),
+        VectorCatchCondition::SecureFault => Err(Error::ArchitectureRequired(&["ARMv8-M"])),
+        _ => Err(Error::ArchitectureRequired(&["ARMv7-M", "ARMv8-M"])),
+    }
+}
+
 impl<'probe> CoreInterface for M0<'probe> {
     fn wait_for_core_halted(&mut self, timeout: Duration) -> Result<(), Error> {
         // Wait until halted state is active again.
@@ -425,7 +438,7 @@ impl<'probe> CoreInterface for M0<'probe> {
         // Update core status
         let _ = self.status()?;
 
-        const XPSR_THUMB: u32 = 1 << 24;
+        const XPSR_THUMB: u64 = 1 << 24;
         let xpsr_value = self.read_core_reg(XPSR.address)?;
         if xpsr_value & XPSR_THUMB == 0 {
             self.write_core_reg(XPSR.address, xpsr_value | XPSR_THUMB)?;
@@ -462,7 +475,9 @@ impl<'probe> CoreInterface for M0<'probe> {
         Ok(())
     }
 
-    fn set_breakpoint(&mut self, bp_register_index: usize, addr: u32) -> Result<(), Error> {
+    fn set_breakpoint(&mut self, bp_register_index: usize, addr: u64) -> Result<(), Error> {
+        let addr = valid_32bit_address(addr)?;
+
         debug!("Setting breakpoint on address 0x{:08x}", addr);
 
         // The highest 3 bits of the address have to be zero, otherwise the breakpoint cannot
@@ -504,6 +519,34 @@ impl<'probe> CoreInterface for M0<'probe> {
         Ok(())
     }
 
+    fn get_available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        dwt_available_watchpoint_units(self)
+    }
+
+    fn set_watchpoint(
+        &mut self,
+        unit_index: usize,
+        address: u64,
+        size: u32,
+        kind: WatchpointKind,
+    ) -> Result<(), Error> {
+        dwt_set_watchpoint(self, unit_index, address, size, kind)
+    }
+
+    fn clear_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        dwt_clear_watchpoint(self, unit_index)
+    }
+
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        armv6m_vector_catch_supported(condition)?;
+        vector_catch_update(self, condition, true)
+    }
+
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        armv6m_vector_catch_supported(condition)?;
+        vector_catch_update(self, condition, false)
+    }
+
     fn hw_breakpoints_enabled(&self) -> bool {
         self.state.hw_breakpoints_enabled
     }
@@ -531,7 +574,7 @@ impl<'probe> CoreInterface for M0<'probe> {
         if dhcsr.s_halt() {
             let dfsr = Dfsr(self.memory.read_word_32(Dfsr::ADDRESS)?);
 
-            let reason = dfsr.halt_reason();
+            let reason = dfsr.read_halt_reason(&mut self.memory)?;
 
             // Clear bits from Dfsr register
             self.memory
@@ -570,40 +613,57 @@ impl<'probe> CoreInterface for M0<'probe> {
         Ok(CoreStatus::Running)
     }
 
-    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u32, Error> {
-        self.memory.read_core_reg(address)
+    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u64, Error> {
+        self.memory.read_core_reg(address).map(u64::from)
     }
 
-    fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u32) -> Result<()> {
+    fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u64) -> Result<()> {
+        let value = valid_32bit_register_value(value)?;
+
         self.memory.write_core_reg(address, value)?;
         Ok(())
     }
 }
 
 impl<'probe> MemoryInterface for M0<'probe> {
-    fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
-        self.memory.read_word_32(address)
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
+        self.memory.read_word_32(valid_32bit_address(address)?)
+    }
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        self.memory.read_word_16(valid_32bit_address(address)?)
+    }
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
+        self.memory.read_word_8(valid_32bit_address(address)?)
+    }
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
+        self.memory.read_32(valid_32bit_address(address)?, data)
+    }
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        self.memory.read_16(valid_32bit_address(address)?, data)
     }
-    fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
-        self.memory.read_word_8(address)
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        self.memory.read_8(valid_32bit_address(address)?, data)
     }
-    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
-        self.memory.read_32(address, data)
+    fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), Error> {
+        self.memory
+            .write_word_32(valid_32bit_address(address)?, data)
     }
-    fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
-        self.memory.read_8(address, data)
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), Error> {
+        self.memory
+            .write_word_16(valid_32bit_address(address)?, data)
     }
-    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
-        self.memory.write_word_32(address, data)
+    fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
+        self.memory
+            .write_word_8(valid_32bit_address(address)?, data)
     }
-    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
-        self.memory.write_word_8(address, data)
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), Error> {
+        self.memory.write_32(valid_32bit_address(address)?, data)
     }
-    fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
-        self.memory.write_32(address, data)
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), Error> {
+        self.memory.write_16(valid_32bit_address(address)?, data)
     }
-    fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
-        self.memory.write_8(address, data)
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
+        self.memory.write_8(valid_32bit_address(address)?, data)
     }
     fn flush(&mut self) -> Result<(), Error> {
         self.memory.flush()
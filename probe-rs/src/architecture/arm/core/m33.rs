@@ -2,21 +2,28 @@
 //!
 
 use crate::error::Error;
-use crate::memory::Memory;
+use crate::memory::{valid_32bit_address, Memory};
 use crate::{
     core::{
-        Architecture, CoreInformation, CoreInterface, CoreRegister, CoreRegisterAddress,
-        RegisterFile,
+        valid_32bit_register_value, Architecture, CoreInformation, CoreInterface, CoreRegister,
+        CoreRegisterAddress, RegisterFile, VectorCatchCondition, WatchpointKind,
     },
     CoreStatus, DebugProbeError, HaltReason,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
-use crate::{architecture::arm::core::register, MemoryInterface};
+use crate::{
+    architecture::arm::component::{dwt::Comp, DebugRegister},
+    architecture::arm::core::register,
+    MemoryInterface,
+};
 
 use bitfield::bitfield;
 
-use super::{reset_catch_clear, reset_catch_set, CortexState, Dfsr, ARM_REGISTER_FILE};
+use super::{
+    dwt_available_watchpoint_units, dwt_clear_watchpoint, dwt_unit_register, reset_catch_clear,
+    reset_catch_set, vector_catch_update, CortexState, Dfsr, ARM_REGISTER_FILE,
+};
 use std::{
     mem::size_of,
     time::{Duration, Instant},
@@ -42,7 +49,7 @@ impl<'probe> M33<'probe> {
             } else if dhcsr.s_halt() {
                 let dfsr = Dfsr(memory.read_word_32(Dfsr::ADDRESS)?);
 
-                let reason = dfsr.halt_reason();
+                let reason = dfsr.read_halt_reason(&mut memory)?;
 
                 log::debug!("Core was halted when connecting, reason: {:?}", reason);
 
@@ -153,7 +160,7 @@ impl<'probe> CoreInterface for M33<'probe> {
         // Update core status
         let _ = self.status()?;
 
-        const XPSR_THUMB: u32 = 1 << 24;
+        const XPSR_THUMB: u64 = 1 << 24;
         let xpsr_value = self.read_core_reg(register::XPSR.address)?;
         if xpsr_value & XPSR_THUMB == 0 {
             self.write_core_reg(register::XPSR.address, xpsr_value | XPSR_THUMB)?;
@@ -205,11 +212,13 @@ impl<'probe> CoreInterface for M33<'probe> {
         Ok(CoreInformation { pc: pc_value })
     }
 
-    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u32, Error> {
-        self.memory.read_core_reg(address)
+    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u64, Error> {
+        self.memory.read_core_reg(address).map(u64::from)
     }
 
-    fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u32) -> Result<()> {
+    fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u64) -> Result<()> {
+        let value = valid_32bit_register_value(value)?;
+
         self.memory.write_core_reg(address, value)?;
         Ok(())
     }
@@ -235,7 +244,9 @@ impl<'probe> CoreInterface for M33<'probe> {
         Ok(())
     }
 
-    fn set_breakpoint(&mut self, bp_unit_index: usize, addr: u32) -> Result<(), Error> {
+    fn set_breakpoint(&mut self, bp_unit_index: usize, addr: u64) -> Result<(), Error> {
+        let addr = valid_32bit_address(addr)?;
+
         let mut val = FpCompX::from(0);
 
         // clear bits which cannot be set and shift into position
@@ -267,6 +278,74 @@ impl<'probe> CoreInterface for M33<'probe> {
         Ok(())
     }
 
+    fn get_available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        dwt_available_watchpoint_units(self)
+    }
+
+    fn set_watchpoint(
+        &mut self,
+        unit_index: usize,
+        address: u64,
+        size: u32,
+        kind: WatchpointKind,
+    ) -> Result<(), Error> {
+        let address = valid_32bit_address(address)?;
+
+        // A single ARMv8-M comparator matches accesses of one data size. Larger
+        // ranges would require linking two comparators.
+        let datavsize = match size {
+            1 => 0b00,
+            2 => 0b01,
+            4 => 0b10,
+            _ => {
+                return Err(anyhow!(
+                    "Watchpoints with a size of {} bytes are not supported on ARMv8-M.",
+                    size
+                )
+                .into())
+            }
+        };
+
+        let mut demcr = Demcr(self.memory.read_word_32(Demcr::ADDRESS)?);
+        if !demcr.trcena() {
+            demcr.set_trcena(true);
+            self.memory.write_word_32(Demcr::ADDRESS, demcr.into())?;
+        }
+
+        let mut comp = Comp::from(0);
+        comp.set_comp(address);
+        self.memory
+            .write_word_32(dwt_unit_register::<Comp>(unit_index), comp.into())?;
+
+        let mut function = DwtFunction(0);
+        function.set_datavsize(datavsize);
+        // Generate a debug event on a match
+        function.set_action(0b01);
+        function.set_match(match kind {
+            WatchpointKind::Access => 0b0100,
+            WatchpointKind::Write => 0b0101,
+            WatchpointKind::Read => 0b0110,
+        });
+        self.memory.write_word_32(
+            dwt_unit_register::<DwtFunction>(unit_index),
+            function.into(),
+        )?;
+
+        Ok(())
+    }
+
+    fn clear_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        dwt_clear_watchpoint(self, unit_index)
+    }
+
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        vector_catch_update(self, condition, true)
+    }
+
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        vector_catch_update(self, condition, false)
+    }
+
     fn hw_breakpoints_enabled(&self) -> bool {
         self.state.hw_breakpoints_enabled
     }
@@ -294,7 +373,7 @@ impl<'probe> CoreInterface for M33<'probe> {
         if dhcsr.s_halt() {
             let dfsr = Dfsr(self.memory.read_word_32(Dfsr::ADDRESS)?);
 
-            let reason = dfsr.halt_reason();
+            let reason = dfsr.read_halt_reason(&mut self.memory)?;
 
             // Clear bits from Dfsr register
             self.memory
@@ -335,29 +414,44 @@ impl<'probe> CoreInterface for M33<'probe> {
 }
 
 impl<'probe> MemoryInterface for M33<'probe> {
-    fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
-        self.memory.read_word_32(address)
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
+        self.memory.read_word_32(valid_32bit_address(address)?)
     }
-    fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
-        self.memory.read_word_8(address)
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        self.memory.read_word_16(valid_32bit_address(address)?)
     }
-    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
-        self.memory.read_32(address, data)
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
+        self.memory.read_word_8(valid_32bit_address(address)?)
     }
-    fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
-        self.memory.read_8(address, data)
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
+        self.memory.read_32(valid_32bit_address(address)?, data)
     }
-    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
-        self.memory.write_word_32(address, data)
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        self.memory.read_16(valid_32bit_address(address)?, data)
     }
-    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
-        self.memory.write_word_8(address, data)
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        self.memory.read_8(valid_32bit_address(address)?, data)
     }
-    fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
-        self.memory.write_32(address, data)
+    fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), Error> {
+        self.memory
+            .write_word_32(valid_32bit_address(address)?, data)
     }
-    fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
-        self.memory.write_8(address, data)
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), Error> {
+        self.memory
+            .write_word_16(valid_32bit_address(address)?, data)
+    }
+    fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
+        self.memory
+            .write_word_8(valid_32bit_address(address)?, data)
+    }
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), Error> {
+        self.memory.write_32(valid_32bit_address(address)?, data)
+    }
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), Error> {
+        self.memory.write_16(valid_32bit_address(address)?, data)
+    }
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
+        self.memory.write_8(valid_32bit_address(address)?, data)
     }
     fn flush(&mut self) -> Result<(), Error> {
         self.memory.flush()
@@ -611,3 +705,38 @@ impl From<FpCompX> for u32 {
         value.0
     }
 }
+
+bitfield! {
+    /// DWT_FUNCTIONn register of an ARMv8-M DWT unit. The layout differs from
+    /// the one used for ARMv6-M and ARMv7-M.
+    #[derive(Copy, Clone)]
+    pub struct DwtFunction(u32);
+    impl Debug;
+    /// Identifies the capabilities of the comparator
+    pub u8, id, _: 31, 27;
+    /// Set when the comparator matched since the register was last read
+    pub matched, _: 24;
+    /// Size of the data access to match
+    pub u8, datavsize, set_datavsize: 11, 10;
+    /// Action to take on a match
+    pub u8, action, set_action: 5, 4;
+    /// Type of match generated by this comparator
+    pub u8, match_, set_match: 3, 0;
+}
+
+impl From<u32> for DwtFunction {
+    fn from(value: u32) -> Self {
+        DwtFunction(value)
+    }
+}
+
+impl From<DwtFunction> for u32 {
+    fn from(value: DwtFunction) -> Self {
+        value.0
+    }
+}
+
+impl DebugRegister for DwtFunction {
+    const ADDRESS: u32 = 0x28;
+    const NAME: &'static str = "DWT/FUNCTION";
+}
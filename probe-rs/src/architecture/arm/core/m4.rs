@@ -1,11 +1,15 @@
 use crate::core::{
-    CoreInformation, CoreInterface, CoreRegister, CoreRegisterAddress, RegisterFile,
+    valid_32bit_register_value, CoreInformation, CoreInterface, CoreRegister, CoreRegisterAddress,
+    RegisterFile, VectorCatchCondition, WatchpointKind,
 };
 use crate::error::Error;
-use crate::memory::Memory;
+use crate::memory::{valid_32bit_address, Memory};
 use crate::DebugProbeError;
 
-use super::{register, reset_catch_clear, reset_catch_set, CortexState, Dfsr, ARM_REGISTER_FILE};
+use super::{
+    dwt_available_watchpoint_units, dwt_clear_watchpoint, dwt_set_watchpoint, register,
+    reset_catch_clear, reset_catch_set, vector_catch_update, CortexState, Dfsr, ARM_REGISTER_FILE,
+};
 use crate::{
     core::{Architecture, CoreStatus, HaltReason},
     MemoryInterface,
@@ -122,6 +126,27 @@ impl CoreRegister for Aircr {
     const NAME: &'static str = "AIRCR";
 }
 
+/// Vector Table Offset Register
+#[derive(Debug, Copy, Clone)]
+pub struct Vtor(pub u32);
+
+impl From<u32> for Vtor {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Vtor> for u32 {
+    fn from(value: Vtor) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for Vtor {
+    const ADDRESS: u32 = 0xE000_ED08;
+    const NAME: &'static str = "VTOR";
+}
+
 bitfield! {
     #[derive(Copy, Clone)]
     pub struct Demcr(u32);
@@ -330,7 +355,7 @@ impl<'probe> M4<'probe> {
             } else if dhcsr.s_halt() {
                 let dfsr = Dfsr(memory.read_word_32(Dfsr::ADDRESS)?);
 
-                let reason = dfsr.halt_reason();
+                let reason = dfsr.read_halt_reason(&mut memory)?;
 
                 log::debug!("Core was halted when connecting, reason: {:?}", reason);
 
@@ -353,6 +378,14 @@ impl<'probe> M4<'probe> {
     }
 }
 
+/// The SecureFault exception only exists on ARMv8-M.
+fn armv7m_vector_catch_supported(condition: VectorCatchCondition) -> Result<(), Error> {
+    match condition {
+        VectorCatchCondition::SecureFault => Err(Error::ArchitectureRequired(&["ARMv8-M"])),
+        _ => Ok(()),
+    }
+}
+
 impl<'probe> CoreInterface for M4<'probe> {
     fn wait_for_core_halted(&mut self, timeout: Duration) -> Result<(), Error> {
         // Wait until halted state is active again.
@@ -401,7 +434,7 @@ impl<'probe> CoreInterface for M4<'probe> {
         if dhcsr.s_halt() {
             let dfsr = Dfsr(self.memory.read_word_32(Dfsr::ADDRESS)?);
 
-            let reason = dfsr.halt_reason();
+            let reason = dfsr.read_halt_reason(&mut self.memory)?;
 
             // Clear bits from Dfsr register
             self.memory
@@ -440,11 +473,13 @@ impl<'probe> CoreInterface for M4<'probe> {
         Ok(CoreStatus::Running)
     }
 
-    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u32, Error> {
-        self.memory.read_core_reg(address)
+    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u64, Error> {
+        self.memory.read_core_reg(address).map(u64::from)
     }
 
-    fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u32) -> Result<()> {
+    fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u64) -> Result<()> {
+        let value = valid_32bit_register_value(value)?;
+
         self.memory.write_core_reg(address, value)?;
 
         Ok(())
@@ -548,7 +583,7 @@ impl<'probe> CoreInterface for M4<'probe> {
         // Update core status
         let _ = self.status()?;
 
-        const XPSR_THUMB: u32 = 1 << 24;
+        const XPSR_THUMB: u64 = 1 << 24;
         let xpsr_value = self.read_core_reg(register::XPSR.address)?;
         if xpsr_value & XPSR_THUMB == 0 {
             self.write_core_reg(register::XPSR.address, xpsr_value | XPSR_THUMB)?;
@@ -589,7 +624,9 @@ impl<'probe> CoreInterface for M4<'probe> {
         Ok(())
     }
 
-    fn set_breakpoint(&mut self, bp_unit_index: usize, addr: u32) -> Result<(), Error> {
+    fn set_breakpoint(&mut self, bp_unit_index: usize, addr: u64) -> Result<(), Error> {
+        let addr = valid_32bit_address(addr)?;
+
         let raw_val = self.memory.read_word_32(FpCtrl::ADDRESS)?;
         let ctrl_reg = FpCtrl::from(raw_val);
 
@@ -628,6 +665,34 @@ impl<'probe> CoreInterface for M4<'probe> {
         Ok(())
     }
 
+    fn get_available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        dwt_available_watchpoint_units(self)
+    }
+
+    fn set_watchpoint(
+        &mut self,
+        unit_index: usize,
+        address: u64,
+        size: u32,
+        kind: WatchpointKind,
+    ) -> Result<(), Error> {
+        dwt_set_watchpoint(self, unit_index, address, size, kind)
+    }
+
+    fn clear_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        dwt_clear_watchpoint(self, unit_index)
+    }
+
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        armv7m_vector_catch_supported(condition)?;
+        vector_catch_update(self, condition, true)
+    }
+
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        armv7m_vector_catch_supported(condition)?;
+        vector_catch_update(self, condition, false)
+    }
+
     fn hw_breakpoints_enabled(&self) -> bool {
         self.state.hw_breakpoints_enabled
     }
@@ -638,29 +703,44 @@ impl<'probe> CoreInterface for M4<'probe> {
 }
 
 impl<'probe> MemoryInterface for M4<'probe> {
-    fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
-        self.memory.read_word_32(address)
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
+        self.memory.read_word_32(valid_32bit_address(address)?)
+    }
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        self.memory.read_word_16(valid_32bit_address(address)?)
+    }
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
+        self.memory.read_word_8(valid_32bit_address(address)?)
+    }
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
+        self.memory.read_32(valid_32bit_address(address)?, data)
+    }
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        self.memory.read_16(valid_32bit_address(address)?, data)
     }
-    fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
-        self.memory.read_word_8(address)
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        self.memory.read_8(valid_32bit_address(address)?, data)
     }
-    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
-        self.memory.read_32(address, data)
+    fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), Error> {
+        self.memory
+            .write_word_32(valid_32bit_address(address)?, data)
     }
-    fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
-        self.memory.read_8(address, data)
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), Error> {
+        self.memory
+            .write_word_16(valid_32bit_address(address)?, data)
     }
-    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
-        self.memory.write_word_32(address, data)
+    fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
+        self.memory
+            .write_word_8(valid_32bit_address(address)?, data)
     }
-    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
-        self.memory.write_word_8(address, data)
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), Error> {
+        self.memory.write_32(valid_32bit_address(address)?, data)
     }
-    fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
-        self.memory.write_32(address, data)
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), Error> {
+        self.memory.write_16(valid_32bit_address(address)?, data)
     }
-    fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
-        self.memory.write_8(address, data)
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
+        self.memory.write_8(valid_32bit_address(address)?, data)
     }
     fn flush(&mut self) -> Result<(), Error> {
         self.memory.flush()
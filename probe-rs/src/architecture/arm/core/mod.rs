@@ -1,10 +1,20 @@
 use crate::{
-    core::{CoreRegister, CoreRegisterAddress, RegisterDescription, RegisterFile, RegisterKind},
-    CoreStatus, Error, HaltReason, MemoryInterface,
+    architecture::arm::component::{
+        dwt::{Comp, Ctrl, Function, Mask},
+        DebugRegister,
+    },
+    core::{
+        CoreRegister, CoreRegisterAddress, RegisterDescription, RegisterFile, RegisterKind,
+        VectorCatchCondition, WatchpointKind,
+    },
+    memory::valid_32bit_address,
+    CoreStatus, Error, HaltReason, Memory, MemoryInterface,
 };
 
 use bitfield::bitfield;
 
+pub mod dump;
+pub mod fault;
 pub mod m0;
 pub mod m33;
 pub mod m4;
@@ -20,7 +30,7 @@ pub(crate) fn debug_core_start(core: &mut impl MemoryInterface) -> Result<(), Er
     dhcsr.set_c_debugen(true);
     dhcsr.enable_write();
 
-    core.write_word_32(Dhcsr::ADDRESS, dhcsr.into())?;
+    core.write_word_32(Dhcsr::ADDRESS.into(), dhcsr.into())?;
 
     Ok(())
 }
@@ -34,13 +44,13 @@ pub(crate) fn reset_catch_set(core: &mut impl MemoryInterface) -> Result<(), Err
     use crate::architecture::arm::core::m4::{Demcr, Dhcsr};
 
     // Request halt after reset
-    let mut demcr = Demcr(core.read_word_32(Demcr::ADDRESS)?);
+    let mut demcr = Demcr(core.read_word_32(Demcr::ADDRESS.into())?);
     demcr.set_vc_corereset(true);
 
-    core.write_word_32(Demcr::ADDRESS, demcr.into())?;
+    core.write_word_32(Demcr::ADDRESS.into(), demcr.into())?;
 
     // Clear the status bits by reading from DHCSR
-    let _ = core.read_word_32(Dhcsr::ADDRESS)?;
+    let _ = core.read_word_32(Dhcsr::ADDRESS.into())?;
 
     Ok(())
 }
@@ -54,10 +64,152 @@ pub(crate) fn reset_catch_clear(core: &mut impl MemoryInterface) -> Result<(), E
     use crate::architecture::arm::core::m4::Demcr;
 
     // Clear reset catch bit
-    let mut demcr = Demcr(core.read_word_32(Demcr::ADDRESS)?);
+    let mut demcr = Demcr(core.read_word_32(Demcr::ADDRESS.into())?);
     demcr.set_vc_corereset(false);
 
-    core.write_word_32(Demcr::ADDRESS, demcr.into())?;
+    core.write_word_32(Demcr::ADDRESS.into(), demcr.into())?;
+    Ok(())
+}
+
+/// Set or clear the vector catch bits in DEMCR which cover `condition`.
+///
+/// The caller has to ensure that the core implements the vector catch for `condition`.
+/// Only the SecureFault vector catch is checked here, because it depends on the
+/// Security Extension instead of the architecture of the core.
+pub(crate) fn vector_catch_update(
+    core: &mut impl MemoryInterface,
+    condition: VectorCatchCondition,
+    enabled: bool,
+) -> Result<(), Error> {
+    use crate::architecture::arm::core::m33::Demcr;
+
+    if condition == VectorCatchCondition::SecureFault {
+        let id_pfr1 = core.read_word_32(ID_PFR1.into())?;
+
+        // The Security field of ID_PFR1 is zero if the Security Extension is not implemented.
+        if (id_pfr1 >> 4) & 0xF == 0 {
+            return Err(Error::ArchitectureRequired(&[
+                "ARMv8-M with Security Extension",
+            ]));
+        }
+    }
+
+    let mut demcr = Demcr(core.read_word_32(Demcr::ADDRESS.into())?);
+
+    match condition {
+        VectorCatchCondition::HardFault => demcr.set_vc_harderr(enabled),
+        VectorCatchCondition::MemManage => demcr.set_vc_mmerr(enabled),
+        VectorCatchCondition::BusFault => demcr.set_vc_buserr(enabled),
+        VectorCatchCondition::UsageFault => {
+            demcr.set_vc_staterr(enabled);
+            demcr.set_vc_chkerr(enabled);
+            demcr.set_vc_nocperr(enabled);
+        }
+        VectorCatchCondition::SecureFault => demcr.set_vc_sferr(enabled),
+        VectorCatchCondition::ExceptionEntry => demcr.set_vc_interr(enabled),
+    }
+
+    core.write_word_32(Demcr::ADDRESS.into(), demcr.into())?;
+
+    Ok(())
+}
+
+/// Address of the Processor Feature Register 1, ID_PFR1.
+const ID_PFR1: u32 = 0xE000_ED44;
+
+/// Determine the vector catch condition which halted the core, based on the
+/// exception number in the IPSR.
+///
+/// A fault during exception entry or return is reported as the fault exception
+/// it caused, because the two can't be distinguished after the halt.
+fn caught_vector(xpsr: u32) -> Option<VectorCatchCondition> {
+    use fault::FaultException;
+
+    let condition = match FaultException::from_exception_number(xpsr & 0x1FF)? {
+        FaultException::HardFault => VectorCatchCondition::HardFault,
+        FaultException::MemManage => VectorCatchCondition::MemManage,
+        FaultException::BusFault => VectorCatchCondition::BusFault,
+        FaultException::UsageFault => VectorCatchCondition::UsageFault,
+        FaultException::SecureFault => VectorCatchCondition::SecureFault,
+    };
+
+    Some(condition)
+}
+
+/// Base address of the DWT unit. It is the same on all ARMv6-M, ARMv7-M and ARMv8-M cores.
+const DWT_BASE: u32 = 0xE000_1000;
+
+/// Address of a register of DWT comparator `unit`.
+pub(crate) fn dwt_unit_register<R: DebugRegister>(unit: usize) -> u32 {
+    DWT_BASE + R::ADDRESS + 16 * unit as u32
+}
+
+/// Read the number of comparators of the DWT unit.
+pub(crate) fn dwt_available_watchpoint_units(
+    core: &mut impl MemoryInterface,
+) -> Result<u32, Error> {
+    let ctrl = Ctrl::from(core.read_word_32((DWT_BASE + Ctrl::ADDRESS).into())?);
+
+    Ok(ctrl.numcomp() as u32)
+}
+
+/// Enable the DWT unit in DEMCR, which is required for comparator matches.
+fn dwt_enable(core: &mut impl MemoryInterface) -> Result<(), Error> {
+    use crate::architecture::arm::core::m0::Demcr;
+
+    let mut demcr = Demcr(core.read_word_32(Demcr::ADDRESS.into())?);
+
+    if !demcr.dwtena() {
+        demcr.set_dwtena(true);
+        core.write_word_32(Demcr::ADDRESS.into(), demcr.into())?;
+    }
+
+    Ok(())
+}
+
+/// Configure a DWT comparator as a watchpoint, for ARMv6-M and ARMv7-M cores.
+///
+/// The watched range is selected using the MASK register, which ignores
+/// the lower address bits during the comparison.
+pub(crate) fn dwt_set_watchpoint(
+    core: &mut impl MemoryInterface,
+    unit: usize,
+    address: u64,
+    size: u32,
+    kind: WatchpointKind,
+) -> Result<(), Error> {
+    let address = valid_32bit_address(address)?;
+
+    dwt_enable(core)?;
+
+    let mut comp = Comp::from(0);
+    comp.set_comp(address);
+    core.write_word_32(dwt_unit_register::<Comp>(unit).into(), comp.into())?;
+
+    let mut mask = Mask::from(0);
+    mask.set_mask(size.trailing_zeros());
+    core.write_word_32(dwt_unit_register::<Mask>(unit).into(), mask.into())?;
+
+    // Watchpoint function values from the ARMv7-M ARM, table C1-14.
+    // They are identical for ARMv6-M.
+    let mut function = Function::from(0);
+    function.set_function(match kind {
+        WatchpointKind::Read => 0b0101,
+        WatchpointKind::Write => 0b0110,
+        WatchpointKind::Access => 0b0111,
+    });
+    core.write_word_32(dwt_unit_register::<Function>(unit).into(), function.into())?;
+
+    Ok(())
+}
+
+/// Disable a DWT comparator.
+pub(crate) fn dwt_clear_watchpoint(
+    core: &mut impl MemoryInterface,
+    unit: usize,
+) -> Result<(), Error> {
+    core.write_word_32(dwt_unit_register::<Function>(unit).into(), 0)?;
+
     Ok(())
 }
 
@@ -109,7 +261,7 @@ pub(crate) mod register {
     };
 }
 
-static ARM_REGISTER_FILE: RegisterFile = RegisterFile {
+pub(crate) static ARM_REGISTER_FILE: RegisterFile = RegisterFile {
     platform_registers: &[
         RegisterDescription {
             name: "R0",
@@ -261,11 +413,13 @@ impl Dfsr {
             // it could be for multiple reasons.
 
             // For debuggers, it's important to know if
-            // the core halted because of a breakpoint.
-            // Because of this, we still return breakpoint
+            // the core halted because of a breakpoint or watchpoint.
+            // Because of this, we still return breakpoint or watchpoint
             // even if other reasons are possible as well.
             if self.bkpt() {
                 HaltReason::Breakpoint
+            } else if self.dwttrap() {
+                HaltReason::Watchpoint
             } else {
                 HaltReason::Multiple
             }
@@ -284,6 +438,22 @@ impl Dfsr {
             panic!("This should not happen. Please open a bug report.")
         }
     }
+
+    /// Determine the halt reason like [`Dfsr::halt_reason`], but report the caught
+    /// exception for a vector catch. The core has to be halted.
+    fn read_halt_reason(&self, memory: &mut Memory) -> Result<HaltReason, Error> {
+        let reason = self.halt_reason();
+
+        if reason != HaltReason::Exception {
+            return Ok(reason);
+        }
+
+        let xpsr = memory.read_core_reg(register::XPSR.address)?;
+
+        Ok(caught_vector(xpsr)
+            .map(HaltReason::VectorCatch)
+            .unwrap_or(reason))
+    }
 }
 
 impl From<u32> for Dfsr {
@@ -332,3 +502,72 @@ impl CortexState {
         self.initialized
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::memory::mock::MockMemory;
+
+    #[test]
+    fn caught_vector_from_ipsr() {
+        assert_eq!(
+            caught_vector(0x6100_0003),
+            Some(VectorCatchCondition::HardFault)
+        );
+        assert_eq!(
+            caught_vector(0x0000_0006),
+            Some(VectorCatchCondition::UsageFault)
+        );
+
+        // The reset vector catch halts the core in thread mode.
+        assert_eq!(caught_vector(0x0100_0000), None);
+
+        // SysTick is not covered by a vector catch.
+        assert_eq!(caught_vector(0x0100_000F), None);
+    }
+
+    /// The System Control Space, with all registers reset to zero.
+    fn test_scs() -> MockMemory {
+        MockMemory::new().with_region(0xE000_E000, 0x1000)
+    }
+
+    #[test]
+    fn vector_catch_bits() {
+        use crate::architecture::arm::core::m33::Demcr;
+
+        let mut scs = test_scs();
+
+        vector_catch_update(&mut scs, VectorCatchCondition::UsageFault, true).unwrap();
+        vector_catch_update(&mut scs, VectorCatchCondition::HardFault, true).unwrap();
+
+        let demcr = Demcr(scs.read_word_32(Demcr::ADDRESS.into()).unwrap());
+        assert!(demcr.vc_staterr() && demcr.vc_chkerr() && demcr.vc_nocperr());
+        assert!(demcr.vc_harderr());
+        assert!(!demcr.vc_buserr());
+
+        vector_catch_update(&mut scs, VectorCatchCondition::UsageFault, false).unwrap();
+
+        let demcr = Demcr(scs.read_word_32(Demcr::ADDRESS.into()).unwrap());
+        assert!(!demcr.vc_staterr() && !demcr.vc_chkerr() && !demcr.vc_nocperr());
+        assert!(demcr.vc_harderr());
+    }
+
+    #[test]
+    fn secure_fault_vector_catch() {
+        use crate::architecture::arm::core::m33::Demcr;
+
+        // Without the Security Extension, DEMCR is left unchanged.
+        let mut scs = test_scs();
+        assert!(matches!(
+            vector_catch_update(&mut scs, VectorCatchCondition::SecureFault, true),
+            Err(Error::ArchitectureRequired(_))
+        ));
+        assert_eq!(scs.read_word_32(Demcr::ADDRESS.into()).unwrap(), 0);
+
+        scs.write_word_32(ID_PFR1.into(), 0x0000_0030).unwrap();
+        vector_catch_update(&mut scs, VectorCatchCondition::SecureFault, true).unwrap();
+
+        let demcr = Demcr(scs.read_word_32(Demcr::ADDRESS.into()).unwrap());
+        assert!(demcr.vc_sferr());
+    }
+}
@@ -22,9 +22,11 @@ pub trait ArmProbe {
     ) -> Result<(), Error>;
 
     fn read_8(&mut self, ap: MemoryAp, address: u32, data: &mut [u8]) -> Result<(), Error>;
+    fn read_16(&mut self, ap: MemoryAp, address: u32, data: &mut [u16]) -> Result<(), Error>;
     fn read_32(&mut self, ap: MemoryAp, address: u32, data: &mut [u32]) -> Result<(), Error>;
 
     fn write_8(&mut self, ap: MemoryAp, address: u32, data: &[u8]) -> Result<(), Error>;
+    fn write_16(&mut self, ap: MemoryAp, address: u32, data: &[u16]) -> Result<(), Error>;
     fn write_32(&mut self, ap: MemoryAp, address: u32, data: &[u32]) -> Result<(), Error>;
 
     fn flush(&mut self) -> Result<(), Error>;
@@ -241,6 +243,42 @@ where
         Ok(result.data)
     }
 
+    /// Read a 16bit word at `addr`.
+    ///
+    /// The address where the read should be performed at has to be halfword aligned.
+    /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
+    pub fn read_word_16(
+        &mut self,
+        access_port: MemoryAp,
+        address: u32,
+    ) -> Result<u16, AccessPortError> {
+        if (address & 0x1) != 0 {
+            return Err(AccessPortError::alignment_error(address, 2));
+        }
+
+        let aligned = aligned_range(address, 2)?;
+
+        // Offset of halfword in word (little endian)
+        let bit_offset = (address - aligned.start) * 8;
+
+        let result = if self.only_32bit_data_size {
+            // Read 32-bit word and extract the correct halfword
+            ((self.read_word_32(access_port, aligned.start)? >> bit_offset) & 0xFFFF) as u16
+        } else {
+            let csw = self.build_csw_register(DataSize::U16);
+            let tar = TAR { address };
+            self.write_csw_register(access_port, csw)?;
+            self.write_ap_register(access_port, tar)?;
+            let result = self.read_ap_register(access_port, DRW::default())?;
+
+            // Extract the correct halfword
+            // See "Arm Debug Interface Architecture Specification ADIv5.0 to ADIv5.2", C2.2.6
+            ((result.data >> bit_offset) & 0xFFFF) as u16
+        };
+
+        Ok(result)
+    }
+
     /// Read an 8bit word at `addr`.
     pub fn read_word_8(
         &mut self,
@@ -362,6 +400,30 @@ where
         Ok(())
     }
 
+    /// Read a block of 16bit words at `addr`.
+    ///
+    /// The number of words read is `data.len()`.
+    /// The address where the read should be performed at has to be halfword aligned.
+    /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
+    pub fn read_16(
+        &mut self,
+        access_port: MemoryAp,
+        start_address: u32,
+        data: &mut [u16],
+    ) -> Result<(), AccessPortError> {
+        if (start_address & 0x1) != 0 {
+            return Err(AccessPortError::alignment_error(start_address, 2));
+        }
+
+        // Each halfword is read with a separate access, so that
+        // peripheral registers only see halfword accesses.
+        for (index, halfword) in data.iter_mut().enumerate() {
+            *halfword = self.read_word_16(access_port, start_address + 2 * index as u32)?;
+        }
+
+        Ok(())
+    }
+
     pub fn read_8(
         &mut self,
         access_port: MemoryAp,
@@ -416,6 +478,47 @@ where
         Ok(())
     }
 
+    /// Write a 16bit word at `addr`.
+    ///
+    /// The address where the write should be performed at has to be halfword aligned.
+    /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
+    pub fn write_word_16(
+        &mut self,
+        access_port: MemoryAp,
+        address: u32,
+        data: u16,
+    ) -> Result<(), AccessPortError> {
+        if (address & 0x1) != 0 {
+            return Err(AccessPortError::alignment_error(address, 2));
+        }
+
+        let aligned = aligned_range(address, 2)?;
+
+        // Offset of halfword in word (little endian)
+        let bit_offset = (address - aligned.start) * 8;
+
+        if self.only_32bit_data_size {
+            // Read the existing 32-bit word and insert the halfword at the correct bit offset
+            // See "Arm Debug Interface Architecture Specification ADIv5.0 to ADIv5.2", C2.2.6
+            let word = self.read_word_32(access_port, aligned.start)?;
+            let word = word & !(0xFFFF << bit_offset) | (u32::from(data) << bit_offset);
+
+            self.write_word_32(access_port, aligned.start, word)?;
+        } else {
+            let csw = self.build_csw_register(DataSize::U16);
+            let drw = DRW {
+                data: u32::from(data) << bit_offset,
+            };
+            let tar = TAR { address };
+            self.write_csw_register(access_port, csw)?;
+
+            self.write_ap_register(access_port, tar)?;
+            self.write_ap_register(access_port, drw)?;
+        }
+
+        Ok(())
+    }
+
     /// Write an 8bit word at `addr`.
     pub fn write_word_8(
         &mut self,
@@ -549,6 +652,30 @@ where
         Ok(())
     }
 
+    /// Write a block of 16bit words at `addr`.
+    ///
+    /// The number of words written is `data.len()`.
+    /// The address where the write should be performed at has to be halfword aligned.
+    /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
+    pub fn write_16(
+        &mut self,
+        access_port: MemoryAp,
+        start_address: u32,
+        data: &[u16],
+    ) -> Result<(), AccessPortError> {
+        if (start_address & 0x1) != 0 {
+            return Err(AccessPortError::alignment_error(start_address, 2));
+        }
+
+        // Each halfword is written with a separate access, so that
+        // peripheral registers only see halfword accesses.
+        for (index, halfword) in data.iter().enumerate() {
+            self.write_word_16(access_port, start_address + 2 * index as u32, *halfword)?;
+        }
+
+        Ok(())
+    }
+
     /// Write a block of 8bit words at `addr`.
     ///
     /// The number of words written is `data.len()`.
@@ -653,6 +780,16 @@ where
         Ok(())
     }
 
+    fn read_16(&mut self, ap: MemoryAp, address: u32, data: &mut [u16]) -> Result<(), Error> {
+        if data.len() == 1 {
+            data[0] = self.read_word_16(ap, address)?;
+        } else {
+            self.read_16(ap, address, data)?;
+        }
+
+        Ok(())
+    }
+
     fn read_32(&mut self, ap: MemoryAp, address: u32, data: &mut [u32]) -> Result<(), Error> {
         if data.len() == 1 {
             data[0] = self.read_word_32(ap, address)?;
@@ -673,6 +810,16 @@ where
         Ok(())
     }
 
+    fn write_16(&mut self, ap: MemoryAp, address: u32, data: &[u16]) -> Result<(), Error> {
+        if data.len() == 1 {
+            self.write_word_16(ap, address, data[0])?;
+        } else {
+            self.write_16(ap, address, data)?;
+        }
+
+        Ok(())
+    }
+
     fn write_32(&mut self, ap: MemoryAp, address: u32, data: &[u32]) -> Result<(), Error> {
         if data.len() == 1 {
             self.write_word_32(ap, address, data[0])?;
@@ -831,6 +978,11 @@ mod tests {
         128, 129, 130, 131, 132, 133, 134, 135, 136, 137, 138, 139, 140, 141, 142, 143,
     ];
 
+    // DATA8 interpreted as little endian 16-bit words
+    const DATA16: &[u16] = &[
+        0x8180, 0x8382, 0x8584, 0x8786, 0x8988, 0x8b8a, 0x8d8c, 0x8f8e,
+    ];
+
     // DATA8 interpreted as little endian 32-bit words
     const DATA32: &[u32] = &[0x83828180, 0x87868584, 0x8b8a8988, 0x8f8e8d8c];
 
@@ -848,6 +1000,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn read_word_16() {
+        let mut mock = MockMemoryAp::with_pattern();
+        mock.memory[..8].copy_from_slice(&DATA8[..8]);
+        let mut mi = ADIMemoryInterface::new_mock(&mut mock);
+
+        for &address in &[0, 2, 4, 6] {
+            let value = mi
+                .read_word_16(0.into(), address)
+                .unwrap_or_else(|_| panic!("read_word_16 failed, address = {}", address));
+            assert_eq!(value, DATA16[address as usize / 2], "address = {}", address);
+        }
+    }
+
+    #[test]
+    fn read_word_16_unaligned_should_error() {
+        let mut mock = MockMemoryAp::with_pattern();
+        let mut mi = ADIMemoryInterface::new_mock(&mut mock);
+
+        for &address in &[1, 3, 127] {
+            assert!(mi.read_word_16(0.into(), address).is_err());
+        }
+    }
+
     #[test]
     fn read_word_8() {
         let mut mock = MockMemoryAp::with_pattern();
@@ -882,6 +1058,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn write_word_16() {
+        for &address in &[0, 2, 4, 6] {
+            let mut mock = MockMemoryAp::with_pattern();
+            let mut mi = ADIMemoryInterface::new_mock(&mut mock);
+
+            let mut expected = Vec::from(mi.mock_memory());
+            expected[(address as usize)..(address as usize) + 2].copy_from_slice(&DATA8[..2]);
+
+            mi.write_word_16(0.into(), address, DATA16[0])
+                .unwrap_or_else(|_| panic!("write_word_16 failed, address = {}", address));
+            assert_eq!(
+                mi.mock_memory(),
+                expected.as_slice(),
+                "address = {}",
+                address
+            );
+        }
+    }
+
     #[test]
     fn write_word_8() {
         for address in 0..8 {
@@ -937,6 +1133,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn read_16() {
+        let mut mock = MockMemoryAp::with_pattern();
+        mock.memory[..DATA8.len()].copy_from_slice(DATA8);
+        let mut mi = ADIMemoryInterface::new_mock(&mut mock);
+
+        for &address in &[0, 2] {
+            for len in 0..4 {
+                let mut data = vec![0u16; len];
+                mi.read_16(0.into(), address, &mut data)
+                    .unwrap_or_else(|_| {
+                        panic!("read_16 failed, address = {}, len = {}", address, len)
+                    });
+
+                assert_eq!(
+                    data.as_slice(),
+                    &DATA16[(address / 2) as usize..(address / 2) as usize + len],
+                    "address = {}, len = {}",
+                    address,
+                    len
+                );
+            }
+        }
+    }
+
     #[test]
     fn read_8() {
         let mut mock = MockMemoryAp::with_pattern();
@@ -1000,6 +1221,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn write_16() {
+        for &address in &[0, 2] {
+            for len in 0..4 {
+                let mut mock = MockMemoryAp::with_pattern();
+                let mut mi = ADIMemoryInterface::new_mock(&mut mock);
+
+                let mut expected = Vec::from(mi.mock_memory());
+                expected[address as usize..(address as usize) + len * 2]
+                    .copy_from_slice(&DATA8[..len * 2]);
+
+                let data = &DATA16[..len];
+                mi.write_16(0.into(), address, data).unwrap_or_else(|_| {
+                    panic!("write_16 failed, address = {}, len = {}", address, len)
+                });
+
+                assert_eq!(
+                    mi.mock_memory(),
+                    expected.as_slice(),
+                    "address = {}, len = {}",
+                    address,
+                    len
+                );
+            }
+        }
+    }
+
     #[test]
     fn write_8() {
         for address in 0..4 {
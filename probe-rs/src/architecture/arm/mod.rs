@@ -4,6 +4,8 @@ pub mod component;
 pub(crate) mod core;
 pub mod dp;
 pub mod memory;
+pub mod semihosting;
+pub mod sequences;
 pub mod swo;
 
 pub use communication_interface::{
@@ -12,6 +14,8 @@ pub use communication_interface::{
 pub use communication_interface::{PortType, Register};
 pub use swo::{SwoAccess, SwoConfig, SwoMode};
 
+pub use self::core::dump;
+pub use self::core::fault;
 pub use self::core::m0;
 pub use self::core::m33;
 pub use self::core::m4;
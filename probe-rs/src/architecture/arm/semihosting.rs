@@ -0,0 +1,516 @@
+//! Host side of the ARM semihosting interface.
+//!
+//! A target using semihosting executes a `BKPT 0xAB` instruction, with the
+//! requested operation in `r0` and a pointer to its parameter block in `r1`.
+//! With a debugger attached, this halts the core with [`HaltReason::Breakpoint`].
+//!
+//! [`Semihosting::handle`] checks if the core halted because of such a trap,
+//! services the request on the host, writes the result back to `r0`, moves the
+//! program counter past the `BKPT` instruction and resumes the core.
+//!
+//! The operations are described in the [Semihosting for AArch32 and AArch64]
+//! specification.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use probe_rs::architecture::arm::semihosting::{Semihosting, SemihostingOutcome};
+//! use probe_rs::Session;
+//! use std::time::Duration;
+//!
+//! let mut session = Session::auto_attach("nrf52")?;
+//! let mut core = session.core(0)?;
+//!
+//! let mut semihosting = Semihosting::new();
+//!
+//! core.run()?;
+//!
+//! let exit = loop {
+//!     core.wait_for_core_halted(Duration::from_secs(60))?;
+//!
+//!     match semihosting.handle(&mut core)? {
+//!         SemihostingOutcome::Resumed(_) => continue,
+//!         SemihostingOutcome::Exit(exit) => break exit,
+//!         SemihostingOutcome::NotSemihosting => panic!("Core halted unexpectedly"),
+//!     }
+//! };
+//!
+//! std::process::exit(exit.code());
+//! # Ok::<(), probe_rs::Error>(())
+//! ```
+//!
+//! [Semihosting for AArch32 and AArch64]: https://github.com/ARM-software/abi-aa/blob/main/semihosting/semihosting.rst
+
+use crate::core::{Core, CoreRegisterAddress, CoreStatus, HaltReason};
+use crate::{Error, MemoryInterface};
+
+use anyhow::anyhow;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::time::Instant;
+
+/// Encoding of the `BKPT 0xAB` instruction in Thumb state.
+const BKPT_SEMIHOSTING: u16 = 0xBEAB;
+
+/// Exit reason `ADP_Stopped_ApplicationExit`, used to signal a successful exit.
+pub const ADP_STOPPED_APPLICATION_EXIT: u32 = 0x20026;
+
+/// Exit reason `ADP_Stopped_RunTimeErrorUnknown`.
+pub const ADP_STOPPED_RUN_TIME_ERROR_UNKNOWN: u32 = 0x20023;
+
+/// Handles below this value are reserved for the standard streams.
+const FIRST_FILE_HANDLE: u32 = 3;
+
+const STDIN: u32 = 0;
+const STDOUT: u32 = 1;
+const STDERR: u32 = 2;
+
+/// Semihosting operations supported by [`Semihosting`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// `SYS_OPEN` (0x01): Open a file on the host.
+    Open,
+    /// `SYS_CLOSE` (0x02): Close a file on the host.
+    Close,
+    /// `SYS_WRITEC` (0x03): Write a single character to the debug console.
+    WriteC,
+    /// `SYS_WRITE0` (0x04): Write a null-terminated string to the debug console.
+    Write0,
+    /// `SYS_WRITE` (0x05): Write to a file.
+    Write,
+    /// `SYS_READ` (0x06): Read from a file.
+    Read,
+    /// `SYS_CLOCK` (0x10): Centiseconds since the semihosting session started.
+    Clock,
+    /// `SYS_GET_CMDLINE` (0x15): Get the command line of the application.
+    GetCmdline,
+    /// `SYS_EXIT` (0x18): Report that the application has exited.
+    Exit,
+    /// `SYS_EXIT_EXTENDED` (0x20): Report that the application has exited, with a subcode.
+    ExitExtended,
+    /// An operation which is not supported. The request is answered with `-1`.
+    Unsupported(u32),
+}
+
+impl From<u32> for Operation {
+    fn from(value: u32) -> Self {
+        match value {
+            0x01 => Operation::Open,
+            0x02 => Operation::Close,
+            0x03 => Operation::WriteC,
+            0x04 => Operation::Write0,
+            0x05 => Operation::Write,
+            0x06 => Operation::Read,
+            0x10 => Operation::Clock,
+            0x15 => Operation::GetCmdline,
+            0x18 => Operation::Exit,
+            0x20 => Operation::ExitExtended,
+            other => Operation::Unsupported(other),
+        }
+    }
+}
+
+/// Exit status reported by the target with `SYS_EXIT` or `SYS_EXIT_EXTENDED`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ExitStatus {
+    /// The reason for the exit, e.g. [`ADP_STOPPED_APPLICATION_EXIT`].
+    pub reason: u32,
+    /// The subcode of the exit. For `SYS_EXIT` this is always 0.
+    pub subcode: u32,
+}
+
+impl ExitStatus {
+    /// Returns true if the application reported a successful exit.
+    pub fn success(&self) -> bool {
+        self.code() == 0
+    }
+
+    /// The exit code as it would be returned from a host process.
+    ///
+    /// For an `ADP_Stopped_ApplicationExit` reason this is the subcode, all
+    /// other reasons are reported as `1`.
+    pub fn code(&self) -> i32 {
+        if self.reason == ADP_STOPPED_APPLICATION_EXIT {
+            self.subcode as i32
+        } else {
+            1
+        }
+    }
+}
+
+/// Result of [`Semihosting::handle`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SemihostingOutcome {
+    /// The core is not halted on a semihosting trap, and was left untouched.
+    NotSemihosting,
+    /// The request was serviced, and the core is running again.
+    Resumed(Operation),
+    /// The application requested to exit. The core is left halted.
+    Exit(ExitStatus),
+}
+
+/// Host side state of a semihosting session.
+///
+/// Console output is written to the standard output and error streams of the
+/// host process, and console input is read from its standard input.
+pub struct Semihosting {
+    files: HashMap<u32, File>,
+    next_handle: u32,
+    cmdline: String,
+    start: Instant,
+}
+
+impl std::fmt::Debug for Semihosting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Semihosting")
+            .field("open_files", &self.files.len())
+            .field("cmdline", &self.cmdline)
+            .finish()
+    }
+}
+
+impl Default for Semihosting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Semihosting {
+    /// Create a new semihosting session. The clock reported by `SYS_CLOCK`
+    /// starts counting now.
+    pub fn new() -> Self {
+        Self {
+            files: HashMap::new(),
+            next_handle: FIRST_FILE_HANDLE,
+            cmdline: String::new(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Set the command line returned to the target by `SYS_GET_CMDLINE`.
+    pub fn set_cmdline(&mut self, cmdline: impl Into<String>) {
+        self.cmdline = cmdline.into();
+    }
+
+    /// Check if the core is halted on a semihosting trap.
+    ///
+    /// Returns the requested operation and its parameter pointer if that is the case.
+    pub fn check_trap(core: &mut Core) -> Result<Option<(Operation, u32)>, Error> {
+        if core.status()? != CoreStatus::Halted(HaltReason::Breakpoint) {
+            return Ok(None);
+        }
+
+        let pc = core.read_core_reg(core.registers().program_counter())?;
+
+        if read_halfword(core, pc)? != BKPT_SEMIHOSTING {
+            return Ok(None);
+        }
+
+        // Semihosting on ARMv6-M to ARMv8-M only uses 32 bit values.
+        let operation = core.read_core_reg(CoreRegisterAddress(0))? as u32;
+        let parameter = core.read_core_reg(CoreRegisterAddress(1))? as u32;
+
+        Ok(Some((Operation::from(operation), parameter)))
+    }
+
+    /// Service a semihosting request, if the core is halted on one.
+    ///
+    /// After the request is serviced, the core is resumed, except for the
+    /// exit operations, which leave the core halted on the trap.
+    pub fn handle(&mut self, core: &mut Core) -> Result<SemihostingOutcome, Error> {
+        let (operation, parameter) = match Self::check_trap(core)? {
+            Some(trap) => trap,
+            None => return Ok(SemihostingOutcome::NotSemihosting),
+        };
+
+        log::debug!(
+            "Semihosting request {:?}, parameter {:#010x}",
+            operation,
+            parameter
+        );
+
+        let result = match operation {
+            Operation::Exit => {
+                return Ok(SemihostingOutcome::Exit(ExitStatus {
+                    reason: parameter,
+                    subcode: 0,
+                }))
+            }
+            Operation::ExitExtended => {
+                let mut block = [0u32; 2];
+                core.read_32(parameter.into(), &mut block)?;
+
+                return Ok(SemihostingOutcome::Exit(ExitStatus {
+                    reason: block[0],
+                    subcode: block[1],
+                }));
+            }
+            Operation::Open => self.open(core, parameter)?,
+            Operation::Close => self.close(core, parameter)?,
+            Operation::WriteC => {
+                let c = core.read_word_8(parameter.into())?;
+                write_console(STDOUT, &[c]);
+                0
+            }
+            Operation::Write0 => {
+                let string = read_c_string(core, parameter)?;
+                write_console(STDOUT, &string);
+                0
+            }
+            Operation::Write => self.write(core, parameter)?,
+            Operation::Read => self.read(core, parameter)?,
+            Operation::Clock => (self.start.elapsed().as_millis() / 10) as u32,
+            Operation::GetCmdline => self.get_cmdline(core, parameter)?,
+            Operation::Unsupported(number) => {
+                log::warn!("Unsupported semihosting operation {:#04x}", number);
+                -1i32 as u32
+            }
+        };
+
+        core.write_core_reg(CoreRegisterAddress(0), result.into())?;
+
+        // Continue after the BKPT instruction
+        let pc_register: CoreRegisterAddress = core.registers().program_counter().into();
+        let pc = core.read_core_reg(pc_register)?;
+        core.write_core_reg(pc_register, pc + 2)?;
+
+        core.run()?;
+
+        Ok(SemihostingOutcome::Resumed(operation))
+    }
+
+    /// `SYS_OPEN`, parameter block: name pointer, mode, name length.
+    fn open(&mut self, core: &mut Core, parameter: u32) -> Result<u32, Error> {
+        let mut block = [0u32; 3];
+        core.read_32(parameter.into(), &mut block)?;
+
+        let mut name = vec![0u8; block[2] as usize];
+        core.read_8(block[0].into(), &mut name)?;
+        let name = String::from_utf8_lossy(&name).into_owned();
+        let mode = block[1];
+
+        if name == ":tt" {
+            // The console is opened with mode "r" for stdin, "w" for stdout and "a" for stderr.
+            return Ok(match mode {
+                0..=3 => STDIN,
+                4..=7 => STDOUT,
+                _ => STDERR,
+            });
+        }
+
+        let options = match open_options(mode) {
+            Some(options) => options,
+            None => return Ok(-1i32 as u32),
+        };
+
+        match options.open(&name) {
+            Ok(file) => {
+                let handle = self.next_handle;
+                self.next_handle += 1;
+                self.files.insert(handle, file);
+
+                Ok(handle)
+            }
+            Err(e) => {
+                log::warn!("Semihosting: Failed to open {}: {}", name, e);
+                Ok(-1i32 as u32)
+            }
+        }
+    }
+
+    /// `SYS_CLOSE`, parameter block: handle.
+    fn close(&mut self, core: &mut Core, parameter: u32) -> Result<u32, Error> {
+        let handle = core.read_word_32(parameter.into())?;
+
+        if handle < FIRST_FILE_HANDLE || self.files.remove(&handle).is_some() {
+            Ok(0)
+        } else {
+            Ok(-1i32 as u32)
+        }
+    }
+
+    /// `SYS_WRITE`, parameter block: handle, buffer pointer, length.
+    ///
+    /// Returns the number of bytes which were *not* written.
+    fn write(&mut self, core: &mut Core, parameter: u32) -> Result<u32, Error> {
+        let mut block = [0u32; 3];
+        core.read_32(parameter.into(), &mut block)?;
+        let [handle, buffer, length] = block;
+
+        let mut data = vec![0u8; length as usize];
+        core.read_8(buffer.into(), &mut data)?;
+
+        if handle < FIRST_FILE_HANDLE {
+            write_console(handle, &data);
+            return Ok(0);
+        }
+
+        match self.files.get_mut(&handle) {
+            Some(file) => match file.write_all(&data) {
+                Ok(()) => Ok(0),
+                Err(e) => {
+                    log::warn!("Semihosting: Failed to write to file: {}", e);
+                    Ok(length)
+                }
+            },
+            None => Ok(length),
+        }
+    }
+
+    /// `SYS_READ`, parameter block: handle, buffer pointer, length.
+    ///
+    /// Returns the number of bytes which were *not* read.
+    fn read(&mut self, core: &mut Core, parameter: u32) -> Result<u32, Error> {
+        let mut block = [0u32; 3];
+        core.read_32(parameter.into(), &mut block)?;
+        let [handle, buffer, length] = block;
+
+        let mut data = vec![0u8; length as usize];
+
+        let result = if handle == STDIN {
+            std::io::stdin().read(&mut data)
+        } else {
+            match self.files.get_mut(&handle) {
+                Some(file) => file.read(&mut data),
+                None => return Ok(-1i32 as u32),
+            }
+        };
+
+        match result {
+            Ok(count) => {
+                core.write_8(buffer.into(), &data[..count])?;
+                Ok(length - count as u32)
+            }
+            Err(e) => {
+                log::warn!("Semihosting: Failed to read from file: {}", e);
+                Ok(-1i32 as u32)
+            }
+        }
+    }
+
+    /// `SYS_GET_CMDLINE`, parameter block: buffer pointer, buffer length.
+    ///
+    /// The length in the parameter block is updated with the length of the command line.
+    fn get_cmdline(&mut self, core: &mut Core, parameter: u32) -> Result<u32, Error> {
+        let mut block = [0u32; 2];
+        core.read_32(parameter.into(), &mut block)?;
+
+        let mut cmdline = self.cmdline.as_bytes().to_vec();
+        cmdline.push(0);
+
+        if cmdline.len() > block[1] as usize {
+            return Ok(-1i32 as u32);
+        }
+
+        core.write_8(block[0].into(), &cmdline)?;
+        core.write_word_32((parameter + 4).into(), cmdline.len() as u32 - 1)?;
+
+        Ok(0)
+    }
+}
+
+/// Map the ISO C `fopen` mode used by `SYS_OPEN` to the corresponding [`OpenOptions`].
+fn open_options(mode: u32) -> Option<OpenOptions> {
+    let mut options = OpenOptions::new();
+
+    // Modes come in groups of four: "r", "rb", "r+", "r+b" and so on.
+    let update = mode & 0b10 != 0;
+
+    match mode >> 2 {
+        // r
+        0 => options.read(true).write(update),
+        // w
+        1 => options.write(true).read(update).create(true).truncate(true),
+        // a
+        2 => options.append(true).read(update).create(true),
+        _ => return None,
+    };
+
+    Some(options)
+}
+
+fn write_console(handle: u32, data: &[u8]) {
+    let result = if handle == STDERR {
+        std::io::stderr().write_all(data)
+    } else {
+        let mut stdout = std::io::stdout();
+        stdout.write_all(data).and_then(|_| stdout.flush())
+    };
+
+    if let Err(e) = result {
+        log::warn!("Semihosting: Failed to write to console: {}", e);
+    }
+}
+
+fn read_halfword(core: &mut Core, address: u64) -> Result<u16, Error> {
+    let mut data = [0u8; 2];
+    core.read_8(address, &mut data)?;
+
+    Ok(u16::from_le_bytes(data))
+}
+
+/// Read a null-terminated string, without the terminating null.
+fn read_c_string(core: &mut Core, mut address: u32) -> Result<Vec<u8>, Error> {
+    const MAX_LENGTH: usize = 64 * 1024;
+
+    let mut string = Vec::new();
+    let mut chunk = [0u8; 32];
+
+    while string.len() < MAX_LENGTH {
+        // Don't read across a 32 byte boundary, the string could end
+        // right before inaccessible memory.
+        let length = 32 - (address % 32) as usize;
+        core.read_8(address.into(), &mut chunk[..length])?;
+
+        if let Some(end) = chunk[..length].iter().position(|&c| c == 0) {
+            string.extend_from_slice(&chunk[..end]);
+            return Ok(string);
+        }
+
+        string.extend_from_slice(&chunk[..length]);
+        address += length as u32;
+    }
+
+    Err(anyhow!("Semihosting string at {:#010x} is not terminated", address).into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn exit_status_code() {
+        let success = ExitStatus {
+            reason: ADP_STOPPED_APPLICATION_EXIT,
+            subcode: 0,
+        };
+        assert!(success.success());
+
+        let failure = ExitStatus {
+            reason: ADP_STOPPED_APPLICATION_EXIT,
+            subcode: 3,
+        };
+        assert_eq!(failure.code(), 3);
+
+        let error = ExitStatus {
+            reason: ADP_STOPPED_RUN_TIME_ERROR_UNKNOWN,
+            subcode: 0,
+        };
+        assert!(!error.success());
+        assert_eq!(error.code(), 1);
+    }
+
+    #[test]
+    fn operation_numbers() {
+        assert_eq!(Operation::from(0x04), Operation::Write0);
+        assert_eq!(Operation::from(0x20), Operation::ExitExtended);
+        assert_eq!(Operation::from(0x0c), Operation::Unsupported(0x0c));
+    }
+
+    #[test]
+    fn invalid_open_mode() {
+        assert!(open_options(11).is_some());
+        assert!(open_options(12).is_none());
+    }
+}
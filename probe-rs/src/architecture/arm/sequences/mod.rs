@@ -0,0 +1,129 @@
+//! Debug sequences for ARM chips.
+//!
+//! Some chips need special handling to attach to them, or to recover them
+//! when they are locked. This is done in debug sequences, which are selected
+//! based on the `debug_sequence` entry of the target description.
+//! The steps are modelled after the debug sequences of CMSIS-Pack
+//! target descriptions.
+
+pub mod nrf52;
+pub mod stm32;
+
+use std::{fmt::Debug, sync::Arc};
+
+use anyhow::anyhow;
+
+use super::{
+    core::{debug_core_start, reset_catch_clear, reset_catch_set},
+    ArmProbeInterface,
+};
+use crate::{config::DebugSequenceKind, Core, Error};
+
+/// A chip specific debug sequence.
+///
+/// All steps have a default implementation, so only the
+/// steps which differ from the default sequence have to be implemented.
+pub trait ArmDebugSequence: Send + Sync + Debug {
+    /// Called before the cores of the target are attached.
+    ///
+    /// This can be used to check if the chip is locked, and to
+    /// return [`Error::TargetLocked`] instead of failing later
+    /// with a less helpful error.
+    fn pre_attach(&self, interface: &mut dyn ArmProbeInterface) -> Result<(), Error> {
+        let _ = interface;
+
+        Ok(())
+    }
+
+    /// Called for each core after it has been attached, to enable debugging.
+    fn post_attach(&self, core: &mut Core) -> Result<(), Error> {
+        debug_core_start(core)
+    }
+
+    /// Configure the core to halt after the next reset.
+    fn reset_catch_set(&self, core: &mut Core) -> Result<(), Error> {
+        reset_catch_set(core)
+    }
+
+    /// Undo the configuration of [`ArmDebugSequence::reset_catch_set`].
+    fn reset_catch_clear(&self, core: &mut Core) -> Result<(), Error> {
+        reset_catch_clear(core)
+    }
+
+    /// Unlock a locked chip by erasing it.
+    ///
+    /// This erases all non-volatile memory of the chip.
+    fn recover(&self, interface: &mut dyn ArmProbeInterface) -> Result<(), Error> {
+        let _ = interface;
+
+        Err(anyhow!("Recovering is not supported for this target").into())
+    }
+}
+
+/// The debug sequence used for chips without a chip specific sequence.
+#[derive(Debug)]
+pub struct DefaultArmSequence;
+
+impl ArmDebugSequence for DefaultArmSequence {}
+
+/// Get the debug sequence for a chip family.
+pub(crate) fn debug_sequence(kind: Option<DebugSequenceKind>) -> Arc<dyn ArmDebugSequence> {
+    match kind {
+        Some(DebugSequenceKind::Nrf52) => Arc::new(nrf52::Nrf52),
+        Some(DebugSequenceKind::Stm32f4) => Arc::new(stm32::Stm32f4),
+        None => Arc::new(DefaultArmSequence),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::ChipFamily;
+
+    #[test]
+    fn deserialize_debug_sequence() {
+        let family = |debug_sequence: &str| -> ChipFamily {
+            serde_yaml::from_str(&format!(
+                "
+                name: Test family
+                variants: []
+                flash_algorithms: {{}}
+                core: M4
+                {}
+                ",
+                debug_sequence
+            ))
+            .unwrap()
+        };
+
+        assert_eq!(
+            family("debug_sequence: Nrf52").debug_sequence,
+            Some(DebugSequenceKind::Nrf52)
+        );
+        assert_eq!(
+            family("debug_sequence: Stm32f4").debug_sequence,
+            Some(DebugSequenceKind::Stm32f4)
+        );
+        assert_eq!(family("").debug_sequence, None);
+
+        let unknown = serde_yaml::from_str::<ChipFamily>(
+            "
+            name: Test family
+            variants: []
+            flash_algorithms: {}
+            core: M4
+            debug_sequence: Lpc55
+            ",
+        );
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn select_debug_sequence() {
+        let name = |kind| format!("{:?}", debug_sequence(kind));
+
+        assert_eq!(name(Some(DebugSequenceKind::Nrf52)), "Nrf52");
+        assert_eq!(name(Some(DebugSequenceKind::Stm32f4)), "Stm32f4");
+        assert_eq!(name(None), "DefaultArmSequence");
+    }
+}
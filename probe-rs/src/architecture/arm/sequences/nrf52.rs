@@ -0,0 +1,86 @@
+//! Debug sequence for the Nordic nRF52 series.
+
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+
+use super::ArmDebugSequence;
+use crate::architecture::arm::{ap::GenericAp, ArmProbeInterface};
+use crate::Error;
+
+/// The CTRL-AP is always the second access port.
+const CTRL_AP: u8 = 1;
+/// Value of the IDR register of the CTRL-AP.
+const CTRL_AP_IDR: u32 = 0x0288_0000;
+
+/// Soft reset of the chip, held until the register is cleared again.
+const RESET: u8 = 0x000;
+/// Start erasing all flash and RAM, which also disables the access port protection.
+const ERASEALL: u8 = 0x004;
+/// Set while the erase started with [`ERASEALL`] is ongoing.
+const ERASEALLSTATUS: u8 = 0x008;
+/// Cleared if the access port protection is enabled.
+const APPROTECTSTATUS: u8 = 0x00C;
+const IDR: u8 = 0x0FC;
+
+const ERASE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Debug sequence for the nRF52 series, which can be locked with the APPROTECT
+/// register in the UICR. A locked chip is unlocked by erasing it through the CTRL-AP.
+#[derive(Debug)]
+pub struct Nrf52;
+
+impl Nrf52 {
+    fn ctrl_ap(interface: &mut dyn ArmProbeInterface) -> Result<GenericAp, Error> {
+        let ctrl_ap = GenericAp::new(CTRL_AP);
+
+        let idr = interface.read_raw_ap_register(ctrl_ap, IDR)?;
+
+        if idr != CTRL_AP_IDR {
+            return Err(anyhow!(
+                "Access port {} is not a CTRL-AP, IDR is {:#010x}",
+                CTRL_AP,
+                idr
+            )
+            .into());
+        }
+
+        Ok(ctrl_ap)
+    }
+}
+
+impl ArmDebugSequence for Nrf52 {
+    fn pre_attach(&self, interface: &mut dyn ArmProbeInterface) -> Result<(), Error> {
+        let ctrl_ap = Self::ctrl_ap(interface)?;
+
+        if interface.read_raw_ap_register(ctrl_ap, APPROTECTSTATUS)? & 1 == 0 {
+            return Err(Error::TargetLocked);
+        }
+
+        Ok(())
+    }
+
+    fn recover(&self, interface: &mut dyn ArmProbeInterface) -> Result<(), Error> {
+        let ctrl_ap = Self::ctrl_ap(interface)?;
+
+        log::info!("Erasing all memory of the nRF52");
+
+        interface.write_raw_ap_register(ctrl_ap, ERASEALL, 1)?;
+
+        let start = Instant::now();
+        while interface.read_raw_ap_register(ctrl_ap, ERASEALLSTATUS)? != 0 {
+            if start.elapsed() > ERASE_TIMEOUT {
+                return Err(anyhow!("Timeout while erasing the nRF52").into());
+            }
+
+            std::thread::sleep(Duration::from_millis(100));
+        }
+
+        // Reset the chip, so that the access port protection is reloaded from the erased UICR.
+        interface.write_raw_ap_register(ctrl_ap, RESET, 1)?;
+        interface.write_raw_ap_register(ctrl_ap, RESET, 0)?;
+        interface.write_raw_ap_register(ctrl_ap, ERASEALL, 0)?;
+
+        Ok(())
+    }
+}
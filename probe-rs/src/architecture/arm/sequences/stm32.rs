@@ -0,0 +1,54 @@
+//! Debug sequences for STM32 chips.
+
+use super::ArmDebugSequence;
+use crate::architecture::arm::{ap::MemoryAp, ArmProbeInterface};
+use crate::flashing::configuration::stm32f4::{
+    read_optcr, read_protection, with_read_protection, write_optcr, RDP_LEVEL_0,
+};
+use crate::flashing::ConfigurationError;
+use crate::{Error, Memory};
+
+/// Debug sequence for the STM32F2, STM32F4 and STM32F7 series, which can be locked
+/// with the RDP option byte. Regressing the RDP level to 0 unlocks the chip
+/// and erases the flash.
+#[derive(Debug)]
+pub struct Stm32f4;
+
+impl Stm32f4 {
+    fn memory(interface: &mut dyn ArmProbeInterface) -> Result<Memory<'_>, Error> {
+        interface.memory_interface(MemoryAp::new(0))
+    }
+}
+
+impl ArmDebugSequence for Stm32f4 {
+    fn pre_attach(&self, interface: &mut dyn ArmProbeInterface) -> Result<(), Error> {
+        let mut memory = Self::memory(interface)?;
+
+        let rdp = read_protection(read_optcr(&mut memory)?);
+
+        if rdp != RDP_LEVEL_0 {
+            log::debug!("Read protection is enabled, RDP = {:#04x}", rdp);
+
+            return Err(Error::TargetLocked);
+        }
+
+        Ok(())
+    }
+
+    fn recover(&self, interface: &mut dyn ArmProbeInterface) -> Result<(), Error> {
+        let mut memory = Self::memory(interface)?;
+
+        log::info!("Setting the read protection to level 0, this erases the flash");
+
+        let optcr = with_read_protection(read_optcr(&mut memory)?, RDP_LEVEL_0);
+
+        write_optcr(&mut memory, optcr).map_err(|error| match error {
+            ConfigurationError::Core(error) => error,
+            error => Error::Other(error.into()),
+        })?;
+
+        log::info!("The read protection is removed after the next power cycle");
+
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@ use super::{
     register, Dmcontrol, Dmstatus,
 };
 use crate::architecture::riscv::*;
+use crate::memory::valid_32bit_address;
 use crate::DebugProbeError;
 use crate::{MemoryInterface, Probe};
 
@@ -47,6 +48,8 @@ pub enum RiscvError {
     SystemBusAccess,
     #[error("Unexpected trigger type {0} for address breakpoint.")]
     UnexpectedTriggerType(u32),
+    #[error("Hart {0} does not exist, the debug module only has {1} hart(s).")]
+    HartNotFound(u32, u32),
 }
 
 impl From<RiscvError> for ProbeRsError {
@@ -166,6 +169,9 @@ pub struct RiscvCommunicationInterfaceState {
     /// Number of harts
     num_harts: u32,
 
+    /// The hart which is accessed by the debug module
+    selected_hart: u32,
+
     memory_access_info: HashMap<RiscvBusAccess, MemoryAccessMethod>,
 
     /// describes, if the given register can be read / written with an
@@ -203,6 +209,8 @@ impl RiscvCommunicationInterfaceState {
             // We assume only a singe hart exisits initially
             num_harts: 1,
 
+            selected_hart: 0,
+
             memory_access_info: HashMap::new(),
 
             abstract_cmd_register_info: HashMap::new(),
@@ -261,6 +269,41 @@ impl<'probe> RiscvCommunicationInterface {
         self.dtm.read_idcode()
     }
 
+    /// Number of harts which are available in the debug module.
+    pub fn num_harts(&self) -> u32 {
+        self.state.num_harts
+    }
+
+    /// Select the hart which is accessed by all following debug module operations.
+    pub fn select_hart(&mut self, hart: u32) -> Result<(), RiscvError> {
+        if hart >= self.state.num_harts {
+            return Err(RiscvError::HartNotFound(hart, self.state.num_harts));
+        }
+
+        if hart == self.state.selected_hart {
+            return Ok(());
+        }
+
+        log::debug!("Selecting hart {}", hart);
+
+        self.state.selected_hart = hart;
+
+        self.write_dm_register(self.dmcontrol())
+    }
+
+    /// Create a value for the `dmcontrol` register, with the debug module
+    /// active and the `hartsel` field set to the selected hart.
+    ///
+    /// All writes to `dmcontrol` should start from this value, otherwise
+    /// they would switch back to hart 0.
+    pub(super) fn dmcontrol(&self) -> Dmcontrol {
+        let mut dmcontrol = Dmcontrol(0);
+        dmcontrol.set_dmactive(true);
+        dmcontrol.set_hartsel(self.state.selected_hart);
+
+        dmcontrol
+    }
+
     fn enter_debug_mode(&mut self) -> Result<(), RiscvError> {
         // We need a jtag interface
 
@@ -778,7 +821,7 @@ impl<'probe> RiscvCommunicationInterface {
         let s0 = self.abstract_cmd_register_read(&register::S0)?;
         let s1 = self.abstract_cmd_register_read(&register::S1)?;
 
-        let sw_command = assembly::sw(0, 8, V::WIDTH.byte_width() as u32, 9);
+        let sw_command = store_instruction::<V>(8, 9);
 
         self.setup_program_buffer(&[sw_command])?;
 
@@ -838,7 +881,7 @@ impl<'probe> RiscvCommunicationInterface {
         // Setup program buffer for multiple writes
         // Store value from register s9 into memory,
         // then increase the address for next write.
-        let sw_command = assembly::sw(0, 8, V::WIDTH as u32, 9);
+        let sw_command = store_instruction::<V>(8, 9);
 
         self.setup_program_buffer(&[
             sw_command,
@@ -900,7 +943,7 @@ impl<'probe> RiscvCommunicationInterface {
         // resumereq    = 0
         // ackhavereset = 0
 
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.dmcontrol();
         dmcontrol.set_haltreq(false);
         dmcontrol.set_resumereq(false);
         dmcontrol.set_ackhavereset(true);
@@ -1144,9 +1187,9 @@ impl<'probe> RiscvCommunicationInterface {
         address: u32,
         data: &mut [V],
     ) -> Result<(), crate::Error> {
-        log::debug!("read_32 from {:#08x}", address);
+        log::debug!("read_multiple from {:#08x}", address);
 
-        match self.state.memory_access_method(RiscvBusAccess::A32) {
+        match self.state.memory_access_method(V::WIDTH) {
             MemoryAccessMethod::ProgramBuffer => {
                 self.perform_memory_read_multiple_progbuf(address, data)?;
             }
@@ -1227,6 +1270,14 @@ pub(crate) trait RiscvValue32: RiscvValue + Into<u32> {
     fn from_register_value(value: u32) -> Self;
 }
 
+/// Assemble the store instruction for a value of type `V`, which stores the
+/// register `source` at the address in the register `base`.
+///
+/// The width of the access selects the `sb`, `sh` or `sw` instruction.
+fn store_instruction<V: RiscvValue32>(base: u32, source: u32) -> u32 {
+    assembly::sw(0, base, V::WIDTH as u32, source)
+}
+
 impl RiscvValue32 for u8 {
     fn from_register_value(value: u32) -> Self {
         value as u8
@@ -1413,42 +1464,86 @@ impl RiscvValue for u128 {
 }
 
 impl MemoryInterface for RiscvCommunicationInterface {
-    fn read_word_32(&mut self, address: u32) -> Result<u32, crate::Error> {
+    fn read_word_32(&mut self, address: u64) -> Result<u32, crate::Error> {
+        let address = valid_32bit_address(address)?;
+
+        self.read_word(address)
+    }
+
+    fn read_word_16(&mut self, address: u64) -> Result<u16, crate::Error> {
+        let address = valid_32bit_address(address)?;
+
+        log::debug!("read_word_16 from {:#08x}", address);
         self.read_word(address)
     }
 
-    fn read_word_8(&mut self, address: u32) -> Result<u8, crate::Error> {
+    fn read_word_8(&mut self, address: u64) -> Result<u8, crate::Error> {
+        let address = valid_32bit_address(address)?;
+
         log::debug!("read_word_8 from {:#08x}", address);
         self.read_word(address)
     }
 
-    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), crate::Error> {
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+
         log::debug!("read_32 from {:#08x}", address);
         self.read_multiple(address, data)
     }
 
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+
+        log::debug!("read_16 from {:#08x}", address);
+        self.read_multiple(address, data)
+    }
+
     /// Read 8-bit values from target memory.
-    fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), crate::Error> {
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+
         log::debug!("read_8 from {:#08x}", address);
 
         self.read_multiple(address, data)
     }
 
-    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), crate::Error> {
+    fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+
         self.write_word(address, data)
     }
 
-    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), crate::Error> {
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+
         self.write_word(address, data)
     }
 
-    fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), crate::Error> {
+    fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+
+        self.write_word(address, data)
+    }
+
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+
         log::debug!("write_32 to {:#08x}", address);
 
         self.write_multiple(address, data)
     }
 
-    fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), crate::Error> {
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+
+        log::debug!("write_16 to {:#08x}", address);
+
+        self.write_multiple(address, data)
+    }
+
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+
         log::debug!("write_8 to {:#08x}", address);
 
         self.write_multiple(address, data)
@@ -1653,3 +1748,16 @@ data_register! { Confstrptr0, 0x19, "confstrptr0" }
 data_register! { Confstrptr1, 0x1a, "confstrptr1" }
 data_register! { Confstrptr2, 0x1b, "confstrptr2" }
 data_register! { Confstrptr3, 0x1c, "confstrptr3" }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn store_instruction_width() {
+        // Assembly output of 'sb s1, 0(s0)', 'sh s1, 0(s0)' and 'sw s1, 0(s0)'
+        assert_eq!(store_instruction::<u8>(8, 9), 0x0094_0023);
+        assert_eq!(store_instruction::<u16>(8, 9), 0x0094_1023);
+        assert_eq!(store_instruction::<u32>(8, 9), 0x0094_2023);
+    }
+}
@@ -9,14 +9,17 @@ use communication_interface::{
     AbstractCommandErrorKind, DebugRegister, RiscvCommunicationInterface, RiscvError,
 };
 
-use crate::core::{CoreInformation, RegisterFile};
+use crate::core::{
+    valid_32bit_register_value, CoreInformation, RegisterFile, VectorCatchCondition, WatchpointKind,
+};
+use crate::memory::valid_32bit_address;
 use crate::{CoreRegisterAddress, CoreStatus, Error, HaltReason, MemoryInterface};
 use bitfield::bitfield;
 use register::RISCV_REGISTERS;
 use std::time::{Duration, Instant};
 
 #[macro_use]
-mod register;
+pub(crate) mod register;
 pub(crate) mod assembly;
 mod dtm;
 
@@ -95,7 +98,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         let current_dmcontrol: Dmcontrol = self.interface.read_dm_register()?;
         log::debug!("{:?}", current_dmcontrol);
 
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
 
         dmcontrol.set_haltreq(true);
         dmcontrol.set_dmactive(true);
@@ -105,7 +108,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         self.wait_for_core_halted(timeout)?;
 
         // clear the halt request
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
 
         dmcontrol.set_dmactive(true);
 
@@ -120,7 +123,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         // TODO: test if core halted?
 
         // set resume request
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_dmactive(true);
         dmcontrol.set_resumereq(true);
 
@@ -134,7 +137,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         };
 
         // clear resume request
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_dmactive(true);
 
         self.interface.write_dm_register(dmcontrol)?;
@@ -145,7 +148,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     fn reset(&mut self) -> Result<(), crate::Error> {
         log::debug!("Resetting core, setting hartreset bit");
 
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_dmactive(true);
         dmcontrol.set_hartreset(true);
 
@@ -157,7 +160,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         if readback.hartreset() {
             log::debug!("Clearing hartreset bit");
             // Reset is performed by setting the bit high, and then low again
-            let mut dmcontrol = Dmcontrol(0);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_dmactive(true);
             dmcontrol.set_hartreset(false);
 
@@ -167,14 +170,14 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             //
             // TODO: Cache this
             log::debug!("Hartreset bit not supported, using ndmreset");
-            let mut dmcontrol = Dmcontrol(0);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_dmactive(true);
             dmcontrol.set_ndmreset(true);
 
             self.interface.write_dm_register(dmcontrol)?;
 
             log::debug!("Clearing ndmreset bit");
-            let mut dmcontrol = Dmcontrol(0);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_dmactive(true);
             dmcontrol.set_ndmreset(false);
 
@@ -191,7 +194,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         }
 
         // acknowledge the reset
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_dmactive(true);
         dmcontrol.set_ackhavereset(true);
 
@@ -206,7 +209,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     ) -> Result<crate::core::CoreInformation, crate::Error> {
         log::debug!("Resetting core, setting hartreset bit");
 
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_dmactive(true);
         dmcontrol.set_hartreset(true);
         dmcontrol.set_haltreq(true);
@@ -219,7 +222,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         if readback.hartreset() {
             log::debug!("Clearing hartreset bit");
             // Reset is performed by setting the bit high, and then low again
-            let mut dmcontrol = Dmcontrol(0);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_dmactive(true);
             dmcontrol.set_haltreq(true);
             dmcontrol.set_hartreset(false);
@@ -230,7 +233,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             //
             // TODO: Cache this
             log::debug!("Hartreset bit not supported, using ndmreset");
-            let mut dmcontrol = Dmcontrol(0);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_dmactive(true);
             dmcontrol.set_ndmreset(true);
             dmcontrol.set_haltreq(true);
@@ -238,7 +241,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             self.interface.write_dm_register(dmcontrol)?;
 
             log::debug!("Clearing ndmreset bit");
-            let mut dmcontrol = Dmcontrol(0);
+            let mut dmcontrol = self.interface.dmcontrol();
             dmcontrol.set_dmactive(true);
             dmcontrol.set_ndmreset(false);
             dmcontrol.set_haltreq(true);
@@ -254,7 +257,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         }
 
         // acknowledge the reset, clear the halt request
-        let mut dmcontrol = Dmcontrol(0);
+        let mut dmcontrol = self.interface.dmcontrol();
         dmcontrol.set_dmactive(true);
         dmcontrol.set_ackhavereset(true);
 
@@ -266,7 +269,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     }
 
     fn step(&mut self) -> Result<crate::core::CoreInformation, crate::Error> {
-        let mut dcsr = Dcsr(self.read_core_reg(CoreRegisterAddress(0x7b0))?);
+        let mut dcsr = Dcsr(self.read_core_reg(CoreRegisterAddress(0x7b0))? as u32);
 
         dcsr.set_step(true);
 
@@ -279,7 +282,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         let pc = self.read_core_reg(CoreRegisterAddress(0x7b1))?;
 
         // clear step request
-        let mut dcsr = Dcsr(self.read_core_reg(CoreRegisterAddress(0x7b0))?);
+        let mut dcsr = Dcsr(self.read_core_reg(CoreRegisterAddress(0x7b0))? as u32);
 
         dcsr.set_step(false);
 
@@ -288,11 +291,15 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         Ok(CoreInformation { pc })
     }
 
-    fn read_core_reg(&mut self, address: crate::CoreRegisterAddress) -> Result<u32, crate::Error> {
-        self.read_csr(address.0).map_err(|e| e.into())
+    fn read_core_reg(&mut self, address: crate::CoreRegisterAddress) -> Result<u64, crate::Error> {
+        self.read_csr(address.0)
+            .map(u64::from)
+            .map_err(|e| e.into())
     }
 
-    fn write_core_reg(&mut self, address: crate::CoreRegisterAddress, value: u32) -> Result<()> {
+    fn write_core_reg(&mut self, address: crate::CoreRegisterAddress, value: u64) -> Result<()> {
+        let value = valid_32bit_register_value(value)?;
+
         self.write_csr(address.0, value).map_err(|e| e.into())
     }
 
@@ -371,7 +378,9 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         Ok(())
     }
 
-    fn set_breakpoint(&mut self, bp_unit_index: usize, addr: u32) -> Result<(), crate::Error> {
+    fn set_breakpoint(&mut self, bp_unit_index: usize, addr: u64) -> Result<(), crate::Error> {
+        let addr = valid_32bit_address(addr)?;
+
         // select requested trigger
         let tselect = 0x7a0;
         let tdata1 = 0x7a1;
@@ -421,6 +430,48 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         Ok(())
     }
 
+    fn get_available_watchpoint_units(&mut self) -> Result<u32, crate::Error> {
+        Err(Error::ArchitectureRequired(&[
+            "ARMv6-M", "ARMv7-M", "ARMv8-M",
+        ]))
+    }
+
+    fn set_watchpoint(
+        &mut self,
+        _unit_index: usize,
+        _address: u64,
+        _size: u32,
+        _kind: WatchpointKind,
+    ) -> Result<(), crate::Error> {
+        Err(Error::ArchitectureRequired(&[
+            "ARMv6-M", "ARMv7-M", "ARMv8-M",
+        ]))
+    }
+
+    fn clear_watchpoint(&mut self, _unit_index: usize) -> Result<(), crate::Error> {
+        Err(Error::ArchitectureRequired(&[
+            "ARMv6-M", "ARMv7-M", "ARMv8-M",
+        ]))
+    }
+
+    fn enable_vector_catch(
+        &mut self,
+        _condition: VectorCatchCondition,
+    ) -> Result<(), crate::Error> {
+        Err(Error::ArchitectureRequired(&[
+            "ARMv6-M", "ARMv7-M", "ARMv8-M",
+        ]))
+    }
+
+    fn disable_vector_catch(
+        &mut self,
+        _condition: VectorCatchCondition,
+    ) -> Result<(), crate::Error> {
+        Err(Error::ArchitectureRequired(&[
+            "ARMv6-M", "ARMv7-M", "ARMv8-M",
+        ]))
+    }
+
     fn registers(&self) -> &'static RegisterFile {
         &RISCV_REGISTERS
     }
@@ -443,7 +494,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
 
         if status.allhalted() {
             // determine reason for halt
-            let dcsr = Dcsr(self.read_core_reg(CoreRegisterAddress::from(0x7b0))?);
+            let dcsr = Dcsr(self.read_core_reg(CoreRegisterAddress::from(0x7b0))? as u32);
 
             let reason = match dcsr.cause() {
                 // An ebreak instruction was hit
@@ -473,28 +524,40 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
 }
 
 impl<'probe> MemoryInterface for Riscv32<'probe> {
-    fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
         self.interface.read_word_32(address)
     }
-    fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        self.interface.read_word_16(address)
+    }
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
         self.interface.read_word_8(address)
     }
-    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
         self.interface.read_32(address, data)
     }
-    fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        self.interface.read_16(address, data)
+    }
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
         self.interface.read_8(address, data)
     }
-    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
+    fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), Error> {
         self.interface.write_word_32(address, data)
     }
-    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), Error> {
+        self.interface.write_word_16(address, data)
+    }
+    fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
         self.interface.write_word_8(address, data)
     }
-    fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), Error> {
         self.interface.write_32(address, data)
     }
-    fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), Error> {
+        self.interface.write_16(address, data)
+    }
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
         self.interface.write_8(address, data)
     }
     fn flush(&mut self) -> Result<(), Error> {
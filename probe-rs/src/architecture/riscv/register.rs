@@ -85,7 +85,7 @@ pub static S1: RegisterDescription = RegisterDescription {
     address: CoreRegisterAddress(0x1009),
 };
 
-pub(super) static RISCV_REGISTERS: RegisterFile = RegisterFile {
+pub(crate) static RISCV_REGISTERS: RegisterFile = RegisterFile {
     platform_registers: &[
         RegisterDescription {
             name: "x0",
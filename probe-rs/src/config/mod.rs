@@ -21,18 +21,26 @@
 //! To add a target at runtime, the [add_target_from_yaml] file can
 //! be used to read targets from a YAML file.
 //!
+//! Memory of a board which is not part of the chip, like external QSPI flash,
+//! can be described in a [TargetExtension]. Use [get_target_with_extension]
+//! to read an extension from a YAML file, and apply it to the chip it extends.
+//!
 
 mod chip_info;
 mod registry;
 mod target;
 
 pub use probe_rs_target::{
-    Chip, ChipFamily, CoreType, FlashProperties, MemoryRange, MemoryRegion, NvmRegion, PageInfo,
-    RamRegion, RawFlashAlgorithm, SectorDescription, SectorInfo, TargetDescriptionSource,
+    ArmCoreAccessOptions, Chip, ChipFamily, ConfigurationField, ConfigurationKind,
+    ConfigurationRegion, Core, CoreAccessOptions, CoreType, DebugSequenceKind, FlashProperties,
+    MemoryRange, MemoryRegion, NvmRegion, PageInfo, RamRegion, RawFlashAlgorithm,
+    RiscvCoreAccessOptions, SectorDescription, SectorInfo, TargetDescriptionSource,
+    TargetExtension,
 };
 
 pub use registry::{
-    add_target_from_yaml, families, get_target_by_name, search_chips, RegistryError,
+    add_target_from_yaml, families, get_target_by_name, get_target_with_extension, search_chips,
+    RegistryError,
 };
 pub use target::{Target, TargetParseError, TargetSelector};
 
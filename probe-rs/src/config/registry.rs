@@ -1,6 +1,7 @@
 //! Internal target registry
 
-use super::{Chip, ChipFamily, ChipInfo, Target, TargetDescriptionSource};
+use super::{Chip, ChipFamily, ChipInfo, Target, TargetDescriptionSource, TargetExtension};
+use crate::architecture::arm::sequences::debug_sequence;
 use crate::config::CoreType;
 use lazy_static::lazy_static;
 use std::fs::File;
@@ -34,6 +35,14 @@ pub enum RegistryError {
     /// An error occured while deserializing a YAML target description file.
     #[error("Deserializing the yaml encountered an error")]
     Yaml(#[from] serde_yaml::Error),
+    /// A target extension cannot be applied to the chip it extends.
+    #[error("The target extension '{extension}' is invalid: {reason}")]
+    InvalidTargetExtension {
+        /// The name of the target extension.
+        extension: String,
+        /// Why the extension is invalid.
+        reason: String,
+    },
     /// Unable to lock the registry.
     #[error("Unable to lock registry")]
     LockUnavailable,
@@ -55,9 +64,12 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 part: None,
                 memory_map: vec![],
                 flash_algorithms: vec![],
+                cores: vec![],
             }],
             flash_algorithms: vec![],
             core: CoreType::M0,
+            debug_sequence: None,
+            configuration: None,
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -68,9 +80,12 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 part: None,
                 memory_map: vec![],
                 flash_algorithms: vec![],
+                cores: vec![],
             }],
             flash_algorithms: vec![],
             core: CoreType::M4,
+            debug_sequence: None,
+            configuration: None,
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -81,9 +96,12 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 part: None,
                 memory_map: vec![],
                 flash_algorithms: vec![],
+                cores: vec![],
             }],
             flash_algorithms: vec![],
             core: CoreType::M3,
+            debug_sequence: None,
+            configuration: None,
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -94,9 +112,12 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 part: None,
                 memory_map: vec![],
                 flash_algorithms: vec![],
+                cores: vec![],
             }],
             flash_algorithms: vec![],
             core: CoreType::M33,
+            debug_sequence: None,
+            configuration: None,
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -107,9 +128,12 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 part: None,
                 memory_map: vec![],
                 flash_algorithms: vec![],
+                cores: vec![],
             }],
             flash_algorithms: vec![],
             core: CoreType::M7,
+            debug_sequence: None,
+            configuration: None,
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -120,9 +144,12 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 part: None,
                 memory_map: vec![],
                 flash_algorithms: vec![],
+                cores: vec![],
             }],
             flash_algorithms: vec![],
             core: CoreType::Riscv,
+            debug_sequence: None,
+            configuration: None,
             source: TargetDescriptionSource::Generic,
         },
     ]);
@@ -263,12 +290,11 @@ impl Registry {
             .cloned()
             .collect();
 
-        Ok(Target::new(
-            chip,
-            chip_algorithms,
-            family.core,
-            family.source.clone(),
-        ))
+        let mut target = Target::new(chip, chip_algorithms, family.core, family.source.clone());
+        target.debug_sequence = debug_sequence(family.debug_sequence);
+        target.configuration = family.configuration.clone();
+
+        Ok(target)
     }
 
     fn add_target_from_yaml(&mut self, path_to_yaml: &Path) -> Result<(), RegistryError> {
@@ -309,6 +335,20 @@ pub fn add_target_from_yaml(path_to_yaml: &Path) -> Result<(), RegistryError> {
     REGISTRY.try_lock()?.add_target_from_yaml(path_to_yaml)
 }
 
+/// Parse a target extension file, and apply it to the chip it extends.
+///
+/// The chip is searched in the internal target registry, see [TargetExtension]
+/// for a description of target extensions.
+pub fn get_target_with_extension(path_to_yaml: &Path) -> Result<Target, RegistryError> {
+    let file = File::open(path_to_yaml)?;
+    let extension: TargetExtension = serde_yaml::from_reader(file)?;
+
+    let mut target = get_target_by_name(&extension.chip)?;
+    target.apply_extension(extension)?;
+
+    Ok(target)
+}
+
 /// Get a list of all families which are contained in the internal
 /// registry.
 pub fn families() -> Result<Vec<ChipFamily>, RegistryError> {
@@ -318,6 +358,10 @@ pub fn families() -> Result<Vec<ChipFamily>, RegistryError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{
+        ArmCoreAccessOptions, Core, CoreAccessOptions, MemoryRegion, NvmRegion, RawFlashAlgorithm,
+        RiscvCoreAccessOptions,
+    };
 
     #[test]
     fn try_fetch1() {
@@ -342,4 +386,167 @@ mod tests {
         let registry = Registry::from_builtin_families();
         assert!(registry.get_target_by_name("nrf51822_Xxaa").is_ok());
     }
+
+    #[test]
+    fn apply_target_extension() {
+        let registry = Registry::from_builtin_families();
+        let mut target = registry.get_target_by_name("nrf52840_xxAA").unwrap();
+
+        let extension: TargetExtension = serde_yaml::from_str(
+            "
+            name: Test board
+            chip: nRF52840_xxAA
+            memory_map:
+              - Nvm:
+                  range:
+                    start: 0x12000000
+                    end: 0x12800000
+                  is_boot_memory: false
+                  flash_algorithm: qspi
+            ",
+        )
+        .unwrap();
+
+        // The flash algorithm of the region is missing.
+        assert!(target.clone().apply_extension(extension.clone()).is_err());
+
+        let mut overlapping = extension.clone();
+        overlapping.memory_map = target.memory_map.clone();
+        overlapping.flash_algorithms = target.flash_algorithms.clone();
+        assert!(target.clone().apply_extension(overlapping).is_err());
+
+        let mut extension = extension;
+        extension.flash_algorithms = vec![RawFlashAlgorithm {
+            name: "qspi".to_owned(),
+            ..Default::default()
+        }];
+
+        let regions = target.memory_map.len();
+        target.apply_extension(extension).unwrap();
+
+        assert_eq!(target.memory_map.len(), regions + 1);
+        assert!(target.flash_algorithms.iter().any(|a| a.name == "qspi"));
+    }
+
+    #[test]
+    fn target_extension_with_chip_algorithm() {
+        let registry = Registry::from_builtin_families();
+        let mut target = registry.get_target_by_name("nrf52840_xxAA").unwrap();
+
+        // The region uses a flash algorithm of the chip, and a RAM region is added as well.
+        let extension: TargetExtension = serde_yaml::from_str(
+            "
+            name: Test board
+            chip: nRF52840_xxAA
+            memory_map:
+              - Nvm:
+                  range:
+                    start: 0x12000000
+                    end: 0x12800000
+                  is_boot_memory: false
+                  flash_algorithm: nrf52
+              - Ram:
+                  range:
+                    start: 0x60000000
+                    end: 0x60100000
+                  is_boot_memory: false
+            ",
+        )
+        .unwrap();
+
+        let algorithms = target.flash_algorithms.len();
+        target.apply_extension(extension).unwrap();
+
+        assert_eq!(target.flash_algorithms.len(), algorithms);
+        assert!(target.memory_map.iter().any(|region| matches!(
+            region,
+            MemoryRegion::Nvm(NvmRegion {
+                range,
+                flash_algorithm: Some(name),
+                ..
+            }) if range.start == 0x1200_0000 && name == "nrf52"
+        )));
+        assert!(target.memory_map.iter().any(|region| matches!(
+            region,
+            MemoryRegion::Ram(ram) if ram.range == (0x6000_0000..0x6010_0000)
+        )));
+    }
+
+    #[test]
+    fn debug_sequence_of_targets() {
+        let registry = Registry::from_builtin_families();
+
+        for (name, sequence) in &[
+            ("nrf52840_xxAA", "Nrf52"),
+            ("STM32F407VGTx", "Stm32f4"),
+            ("nrf51822_xxAA", "DefaultArmSequence"),
+        ] {
+            let target = registry.get_target_by_name(name).unwrap();
+            assert_eq!(&format!("{:?}", target.debug_sequence), sequence);
+        }
+    }
+
+    #[test]
+    fn single_core_target() {
+        let registry = Registry::from_builtin_families();
+        let target = registry.get_target_by_name("nrf52840_xxAA").unwrap();
+
+        assert_eq!(target.cores.len(), 1);
+        assert_eq!(target.cores[0].core_type, CoreType::M4);
+        assert!(matches!(
+            target.cores[0].core_access_options,
+            CoreAccessOptions::Arm(ArmCoreAccessOptions { ap: 0 })
+        ));
+
+        let target = registry.get_target_by_name("riscv").unwrap();
+        assert!(matches!(
+            target.cores[0].core_access_options,
+            CoreAccessOptions::Riscv(RiscvCoreAccessOptions { hart_id: 0 })
+        ));
+    }
+
+    #[test]
+    fn multi_core_targets() {
+        let registry = Registry::from_builtin_families();
+
+        for (name, core_types) in &[
+            ("STM32H745ZITx", [CoreType::M7, CoreType::M4]),
+            ("LPC55S69JBD100", [CoreType::M33, CoreType::M33]),
+            ("ATSAM4C16C", [CoreType::M4, CoreType::M4]),
+        ] {
+            let target = registry.get_target_by_name(name).unwrap();
+
+            let types: Vec<_> = target.cores.iter().map(|core| core.core_type).collect();
+            assert_eq!(&types, core_types, "{}", name);
+            assert_eq!(target.core_type, core_types[0]);
+        }
+    }
+
+    #[test]
+    fn chip_cores_take_precedence() {
+        let chip = Chip {
+            name: "dual".to_owned(),
+            part: None,
+            memory_map: vec![],
+            flash_algorithms: vec![],
+            cores: vec![
+                Core::default_for(CoreType::M7),
+                Core {
+                    name: "second".to_owned(),
+                    core_type: CoreType::M4,
+                    core_access_options: CoreAccessOptions::Arm(ArmCoreAccessOptions { ap: 3 }),
+                },
+            ],
+        };
+
+        let target = Target::new(
+            &chip,
+            vec![],
+            CoreType::M0,
+            TargetDescriptionSource::External,
+        );
+
+        assert_eq!(target.core_type, CoreType::M7);
+        assert_eq!(target.cores.len(), 2);
+    }
 }
@@ -1,5 +1,11 @@
-use super::{Chip, CoreType, MemoryRegion, RawFlashAlgorithm, TargetDescriptionSource};
+use super::{
+    Chip, ConfigurationRegion, Core, CoreType, MemoryRange, MemoryRegion, NvmRegion,
+    RawFlashAlgorithm, RegistryError, TargetDescriptionSource, TargetExtension,
+};
+use crate::architecture::arm::sequences::{ArmDebugSequence, DefaultArmSequence};
 use crate::{core::Architecture, flashing::FlashLoader};
+use std::ops::Range;
+use std::sync::Arc;
 
 /// This describes a complete target with a fixed chip model and variant.
 #[derive(Clone)]
@@ -8,10 +14,16 @@ pub struct Target {
     pub name: String,
     /// The name of the flash algorithm.
     pub flash_algorithms: Vec<RawFlashAlgorithm>,
-    /// The core type.
+    /// The core type of the first core.
     pub core_type: CoreType,
+    /// The cores of the target.
+    pub cores: Vec<Core>,
     /// The memory map of the target.
     pub memory_map: Vec<MemoryRegion>,
+    /// The debug sequence of the target, used for ARM targets.
+    pub debug_sequence: Arc<dyn ArmDebugSequence>,
+    /// The configuration region of the target, like the option bytes of STM32 chips.
+    pub configuration: Option<ConfigurationRegion>,
 
     /// Source of the target description. Used for diagnostics.
     pub(crate) source: TargetDescriptionSource,
@@ -24,9 +36,10 @@ impl std::fmt::Debug for Target {
             "Target {{
             identifier: {:?},
             flash_algorithms: {:?},
+            cores: {:?},
             memory_map: {:?},
         }}",
-            self.name, self.flash_algorithms, self.memory_map
+            self.name, self.flash_algorithms, self.cores, self.memory_map
         )
     }
 }
@@ -36,17 +49,38 @@ pub type TargetParseError = serde_yaml::Error;
 
 impl Target {
     /// Create a new target
+    ///
+    /// If the chip lists its cores, they take precedence over `core_type`, which
+    /// should then be the type of the first core.
     pub fn new(
         chip: &Chip,
         flash_algorithms: Vec<RawFlashAlgorithm>,
         core_type: CoreType,
         source: TargetDescriptionSource,
     ) -> Target {
+        let cores = if chip.cores.is_empty() {
+            vec![Core::default_for(core_type)]
+        } else {
+            if chip.cores[0].core_type != core_type {
+                log::warn!(
+                    "The first core of {} is a {:?} core, but the family core type is {:?}. Using the core types of the chip.",
+                    chip.name,
+                    chip.cores[0].core_type,
+                    core_type
+                );
+            }
+
+            chip.cores.clone()
+        };
+
         Target {
             name: chip.name.clone(),
             flash_algorithms,
-            core_type,
+            core_type: cores[0].core_type,
+            cores,
             memory_map: chip.memory_map.clone(),
+            debug_sequence: Arc::new(DefaultArmSequence),
+            configuration: None,
             source,
         }
     }
@@ -73,6 +107,102 @@ impl Target {
     pub fn flash_loader(&self) -> FlashLoader {
         FlashLoader::new(self.memory_map.clone(), self.source.clone())
     }
+
+    /// Add a flash algorithm to the target, for example one loaded
+    /// with [`read_flm`](crate::flashing::read_flm).
+    ///
+    /// The algorithm is made the default algorithm for its address range, and the
+    /// NVM regions it covers are set to use it, so it is used instead of the built-in
+    /// algorithms for the same flash. An algorithm of the target with the same name is replaced.
+    pub fn add_flash_algorithm(&mut self, mut algorithm: RawFlashAlgorithm) {
+        let range = &algorithm.flash_properties.address_range;
+
+        self.flash_algorithms
+            .retain(|existing| existing.name != algorithm.name);
+
+        for existing in &mut self.flash_algorithms {
+            if existing
+                .flash_properties
+                .address_range
+                .intersects_range(range)
+            {
+                existing.default = false;
+            }
+        }
+
+        for region in &mut self.memory_map {
+            if let MemoryRegion::Nvm(region) = region {
+                if range.contains_range(&region.range) {
+                    region.flash_algorithm = Some(algorithm.name.clone());
+                }
+            }
+        }
+
+        algorithm.default = true;
+        self.flash_algorithms.push(algorithm);
+    }
+
+    /// Apply a board-level [TargetExtension] to the target.
+    ///
+    /// The memory regions and flash algorithms of the extension are added to the target.
+    /// The added regions must not overlap the memory map of the target, and flash
+    /// algorithms specified for NVM regions have to be part of the extension or the target.
+    ///
+    /// Nothing is done to set up the added memory, see [TargetExtension] for details.
+    pub fn apply_extension(&mut self, extension: TargetExtension) -> Result<(), RegistryError> {
+        let invalid = |reason| RegistryError::InvalidTargetExtension {
+            extension: extension.name.clone(),
+            reason,
+        };
+
+        for region in &extension.memory_map {
+            let range = region_range(region);
+
+            if let Some(existing) = self
+                .memory_map
+                .iter()
+                .find(|existing| region_range(existing).intersects_range(range))
+            {
+                return Err(invalid(format!(
+                    "The region {:#010x?} overlaps the region {:#010x?} of {}",
+                    range,
+                    region_range(existing),
+                    self.name
+                )));
+            }
+
+            if let MemoryRegion::Nvm(NvmRegion {
+                flash_algorithm: Some(name),
+                ..
+            }) = region
+            {
+                if !extension
+                    .flash_algorithms
+                    .iter()
+                    .chain(&self.flash_algorithms)
+                    .any(|algorithm| &algorithm.name == name)
+                {
+                    return Err(invalid(format!(
+                        "The flash algorithm '{}' is unknown",
+                        name
+                    )));
+                }
+            }
+        }
+
+        self.memory_map.extend(extension.memory_map);
+        self.flash_algorithms.extend(extension.flash_algorithms);
+
+        Ok(())
+    }
+}
+
+fn region_range(region: &MemoryRegion) -> &Range<u32> {
+    match region {
+        MemoryRegion::Ram(r) => &r.range,
+        MemoryRegion::Generic(r) => &r.range,
+        MemoryRegion::Nvm(r) => &r.range,
+    }
 }
 
 /// Selector for the debug target.
@@ -5,9 +5,10 @@ pub use communication_interface::CommunicationInterface;
 use crate::architecture::{
     arm::core::CortexState, riscv::communication_interface::RiscvCommunicationInterface,
 };
-use crate::config::CoreType;
+use crate::config::{CoreAccessOptions, CoreType};
 use crate::{error, DebugProbeError, Error, Memory, MemoryInterface};
 use anyhow::{anyhow, Result};
+use std::convert::TryFrom;
 use std::time::Duration;
 
 pub trait CoreRegister: Clone + From<u32> + Into<u32> + Sized + std::fmt::Debug {
@@ -31,7 +32,7 @@ impl From<u16> for CoreRegisterAddress {
 }
 #[derive(Debug, Clone)]
 pub struct CoreInformation {
-    pub pc: u32,
+    pub pc: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -69,6 +70,10 @@ pub(crate) enum RegisterKind {
 
 #[derive(Debug)]
 pub struct RegisterFile {
+    /// The general purpose registers of the core.
+    ///
+    /// The position of a register in this list has to be its DWARF register number,
+    /// which is used to unwind the stack.
     pub(crate) platform_registers: &'static [RegisterDescription],
 
     pub(crate) program_counter: &'static RegisterDescription,
@@ -123,6 +128,11 @@ impl RegisterFile {
     }
 }
 
+/// Checks that `value` fits into a 32 bit register, and returns it as a 32 bit value.
+pub(crate) fn valid_32bit_register_value(value: u64) -> Result<u32, error::Error> {
+    u32::try_from(value).map_err(|_| error::Error::RegisterValueOutOfRange(value))
+}
+
 pub trait CoreInterface: MemoryInterface {
     /// Wait until the core is halted. If the core does not halt on its own,
     /// a [DebugProbeError::Timeout] error will be returned.
@@ -155,18 +165,43 @@ pub trait CoreInterface: MemoryInterface {
     /// Steps one instruction and then enters halted state again.
     fn step(&mut self) -> Result<CoreInformation, error::Error>;
 
-    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u32, error::Error>;
+    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u64, error::Error>;
 
-    fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u32) -> Result<()>;
+    fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u64) -> Result<()>;
 
     fn get_available_breakpoint_units(&mut self) -> Result<u32, error::Error>;
 
     fn enable_breakpoints(&mut self, state: bool) -> Result<(), error::Error>;
 
-    fn set_breakpoint(&mut self, bp_unit_index: usize, addr: u32) -> Result<(), error::Error>;
+    fn set_breakpoint(&mut self, bp_unit_index: usize, addr: u64) -> Result<(), error::Error>;
 
     fn clear_breakpoint(&mut self, unit_index: usize) -> Result<(), error::Error>;
 
+    /// Get the number of data watchpoint units of the core.
+    fn get_available_watchpoint_units(&mut self) -> Result<u32, error::Error>;
+
+    /// Configure the watchpoint unit `unit_index` to halt the core on an access of
+    /// kind `kind` to the `size` bytes starting at `address`.
+    ///
+    /// `size` is a power of two, and `address` is aligned to `size`. Cores may not support
+    /// every size, and return an error for unsupported ones.
+    fn set_watchpoint(
+        &mut self,
+        unit_index: usize,
+        address: u64,
+        size: u32,
+        kind: WatchpointKind,
+    ) -> Result<(), error::Error>;
+
+    fn clear_watchpoint(&mut self, unit_index: usize) -> Result<(), error::Error>;
+
+    /// Halt the core when one of the exceptions covered by `condition` is taken.
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), error::Error>;
+
+    /// Stop halting the core when one of the exceptions covered by `condition` is taken.
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition)
+        -> Result<(), error::Error>;
+
     fn registers(&self) -> &'static RegisterFile;
 
     fn hw_breakpoints_enabled(&self) -> bool;
@@ -176,35 +211,51 @@ pub trait CoreInterface: MemoryInterface {
 }
 
 impl<'probe> MemoryInterface for Core<'probe> {
-    fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
         self.inner.read_word_32(address)
     }
 
-    fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        self.inner.read_word_16(address)
+    }
+
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
         self.inner.read_word_8(address)
     }
 
-    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
         self.inner.read_32(address, data)
     }
 
-    fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        self.inner.read_16(address, data)
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
         self.inner.read_8(address, data)
     }
 
-    fn write_word_32(&mut self, addr: u32, data: u32) -> Result<(), Error> {
+    fn write_word_32(&mut self, addr: u64, data: u32) -> Result<(), Error> {
         self.inner.write_word_32(addr, data)
     }
 
-    fn write_word_8(&mut self, addr: u32, data: u8) -> Result<(), Error> {
+    fn write_word_16(&mut self, addr: u64, data: u16) -> Result<(), Error> {
+        self.inner.write_word_16(addr, data)
+    }
+
+    fn write_word_8(&mut self, addr: u64, data: u8) -> Result<(), Error> {
         self.inner.write_word_8(addr, data)
     }
 
-    fn write_32(&mut self, addr: u32, data: &[u32]) -> Result<(), Error> {
+    fn write_32(&mut self, addr: u64, data: &[u32]) -> Result<(), Error> {
         self.inner.write_32(addr, data)
     }
 
-    fn write_8(&mut self, addr: u32, data: &[u8]) -> Result<(), Error> {
+    fn write_16(&mut self, addr: u64, data: &[u16]) -> Result<(), Error> {
+        self.inner.write_16(addr, data)
+    }
+
+    fn write_8(&mut self, addr: u64, data: &[u8]) -> Result<(), Error> {
         self.inner.write_8(addr, data)
     }
 
@@ -216,16 +267,25 @@ impl<'probe> MemoryInterface for Core<'probe> {
 #[derive(Debug)]
 pub struct CoreState {
     id: usize,
+    core_access_options: CoreAccessOptions,
     breakpoints: Vec<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
 }
 
 impl CoreState {
-    pub fn new(id: usize) -> Self {
+    pub fn new(id: usize, core_access_options: CoreAccessOptions) -> Self {
         Self {
             id,
+            core_access_options,
             breakpoints: vec![],
+            watchpoints: vec![],
         }
     }
+
+    /// The options which are used to access the core, e.g. the AP number for ARM cores.
+    pub fn core_access_options(&self) -> &CoreAccessOptions {
+        &self.core_access_options
+    }
 }
 
 #[derive(Debug)]
@@ -318,8 +378,8 @@ impl<'probe> Core<'probe> {
         }
     }
 
-    pub fn create_state(id: usize) -> CoreState {
-        CoreState::new(id)
+    pub fn create_state(id: usize, core_access_options: CoreAccessOptions) -> CoreState {
+        CoreState::new(id, core_access_options)
     }
 
     pub fn id(&self) -> usize {
@@ -376,14 +436,14 @@ impl<'probe> Core<'probe> {
     pub fn read_core_reg(
         &mut self,
         address: impl Into<CoreRegisterAddress>,
-    ) -> Result<u32, error::Error> {
+    ) -> Result<u64, error::Error> {
         self.inner.read_core_reg(address.into())
     }
 
     pub fn write_core_reg(
         &mut self,
         address: CoreRegisterAddress,
-        value: u32,
+        value: u64,
     ) -> Result<(), error::Error> {
         Ok(self.inner.write_core_reg(address, value)?)
     }
@@ -405,7 +465,7 @@ impl<'probe> Core<'probe> {
     /// This function will try to set a hardware breakpoint. The amount
     /// of hardware breakpoints which are supported is chip specific,
     /// and can be queried using the `get_available_breakpoint_units` function.
-    pub fn set_hw_breakpoint(&mut self, address: u32) -> Result<(), error::Error> {
+    pub fn set_hw_breakpoint(&mut self, address: u64) -> Result<(), error::Error> {
         log::debug!("Trying to set HW breakpoint at address {:#08x}", address);
 
         // Get the number of HW breakpoints available
@@ -440,7 +500,15 @@ impl<'probe> Core<'probe> {
         Ok(())
     }
 
-    pub fn clear_hw_breakpoint(&mut self, address: u32) -> Result<(), error::Error> {
+    /// Returns true if a hardware breakpoint was set at `address`.
+    pub(crate) fn has_hw_breakpoint(&self, address: u64) -> bool {
+        self.state
+            .breakpoints
+            .iter()
+            .any(|bp| bp.address == address)
+    }
+
+    pub fn clear_hw_breakpoint(&mut self, address: u64) -> Result<(), error::Error> {
         let bp_position = self
             .state
             .breakpoints
@@ -484,31 +552,178 @@ impl<'probe> Core<'probe> {
         Ok(())
     }
 
-    pub fn architecture(&self) -> Architecture {
-        self.inner.architecture()
+    pub fn get_available_watchpoint_units(&mut self) -> Result<u32, error::Error> {
+        self.inner.get_available_watchpoint_units()
     }
 
-    fn find_free_breakpoint_unit(&self) -> usize {
-        let mut used_bp: Vec<_> = self
+    /// Set a hardware watchpoint
+    ///
+    /// This function will try to set a data watchpoint, which halts the core when
+    /// the `size` bytes starting at `address` are accessed as specified by `kind`.
+    /// `size` has to be a power of two, and `address` has to be aligned to `size`.
+    /// The supported sizes depend on the core: ARMv8-M cores support watchpoints
+    /// of at most 4 bytes, ARMv6-M and ARMv7-M cores larger ones.
+    ///
+    /// The amount of watchpoints which are supported is chip specific,
+    /// and can be queried using the `get_available_watchpoint_units` function.
+    pub fn set_hw_watchpoint(
+        &mut self,
+        address: u64,
+        size: u32,
+        kind: WatchpointKind,
+    ) -> Result<(), error::Error> {
+        log::debug!(
+            "Trying to set {:?} watchpoint at address {:#08x} with size {}",
+            kind,
+            address,
+            size
+        );
+
+        validate_watchpoint(address, size)?;
+
+        let num_watchpoints = self.get_available_watchpoint_units()? as usize;
+
+        log::debug!("{} watchpoints are supported.", num_watchpoints);
+
+        if num_watchpoints <= self.state.watchpoints.len() {
+            log::warn!(
+                "Maximum number of watchpoints ({}) reached, unable to set additional watchpoint.",
+                num_watchpoints
+            );
+
+            return Err(error::Error::Probe(
+                DebugProbeError::WatchpointUnitsExceeded,
+            ));
+        }
+
+        let unit = self.find_free_watchpoint_unit();
+
+        log::debug!("Using watchpoint unit {}", unit);
+        self.inner.set_watchpoint(unit, address, size, kind)?;
+
+        self.state.watchpoints.push(Watchpoint {
+            address,
+            size,
+            kind,
+            register_hw: unit,
+        });
+
+        Ok(())
+    }
+
+    pub fn clear_hw_watchpoint(&mut self, address: u64) -> Result<(), error::Error> {
+        let wp_position = self
             .state
-            .breakpoints
+            .watchpoints
             .iter()
-            .map(|bp| bp.register_hw)
-            .collect();
-        used_bp.sort_unstable();
+            .position(|wp| wp.address == address);
 
-        let mut free_bp = 0;
+        match wp_position {
+            Some(wp_position) => {
+                let wp = &self.state.watchpoints[wp_position];
+                self.inner.clear_watchpoint(wp.register_hw)?;
 
-        for bp in used_bp {
-            if bp == free_bp {
-                free_bp += 1;
-            } else {
-                return free_bp;
+                // We only remove the watchpoint if we have actually managed to clear it.
+                self.state.watchpoints.swap_remove(wp_position);
+                Ok(())
             }
+            None => Err(error::Error::Other(anyhow!(
+                "No watchpoint found at address {:#010x}",
+                address
+            ))),
         }
+    }
+
+    /// Clear all hardware watchpoints
+    ///
+    /// This function will clear all watchpoints which are configured on the target,
+    /// regardless if they are set by probe-rs or not.
+    pub fn clear_all_hw_watchpoints(&mut self) -> Result<(), error::Error> {
+        let num_watchpoints = self.get_available_watchpoint_units()? as usize;
 
-        free_bp
+        self.state.watchpoints.clear();
+
+        { 0..num_watchpoints }.try_for_each(|unit_index| self.inner.clear_watchpoint(unit_index))
+    }
+
+    /// Clear all watchpoints which were set by probe-rs.
+    ///
+    /// Currently used as a helper function in [`Session::drop`].
+    pub(crate) fn clear_all_set_hw_watchpoints(&mut self) -> Result<(), error::Error> {
+        for wp in self.state.watchpoints.drain(..) {
+            self.inner.clear_watchpoint(wp.register_hw)?;
+        }
+
+        Ok(())
+    }
+
+    /// The watchpoints which are currently set by probe-rs.
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.state.watchpoints
+    }
+
+    /// Enable a vector catch, which halts the core when one of the exceptions
+    /// covered by `condition` is taken.
+    ///
+    /// When the core is halted by a vector catch, its status is
+    /// [`HaltReason::VectorCatch`] with the caught condition.
+    pub fn enable_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+    ) -> Result<(), error::Error> {
+        self.inner.enable_vector_catch(condition)
+    }
+
+    /// Disable a vector catch enabled with [`Core::enable_vector_catch`].
+    pub fn disable_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+    ) -> Result<(), error::Error> {
+        self.inner.disable_vector_catch(condition)
+    }
+
+    pub fn architecture(&self) -> Architecture {
+        self.inner.architecture()
+    }
+
+    fn find_free_watchpoint_unit(&self) -> usize {
+        first_free_unit(self.state.watchpoints.iter().map(|wp| wp.register_hw))
+    }
+
+    fn find_free_breakpoint_unit(&self) -> usize {
+        first_free_unit(self.state.breakpoints.iter().map(|bp| bp.register_hw))
+    }
+}
+
+/// Checks that a watchpoint has a size which is a power of two, and an address aligned to it.
+fn validate_watchpoint(address: u64, size: u32) -> Result<(), error::Error> {
+    if !size.is_power_of_two() || address & u64::from(size - 1) != 0 {
+        return Err(error::Error::Other(anyhow!(
+            "Invalid watchpoint at address {:#010x} with size {}: The size has to be a power of two, and the address aligned to it.",
+            address,
+            size
+        )));
     }
+
+    Ok(())
+}
+
+/// Find the lowest unit index which is not in `used_units`.
+fn first_free_unit(used_units: impl Iterator<Item = usize>) -> usize {
+    let mut used_units: Vec<_> = used_units.collect();
+    used_units.sort_unstable();
+
+    let mut free_unit = 0;
+
+    for unit in used_units {
+        if unit == free_unit {
+            free_unit += 1;
+        } else if unit > free_unit {
+            return free_unit;
+        }
+    }
+
+    free_unit
 }
 
 pub struct CoreList<'probe>(&'probe [CoreType]);
@@ -537,10 +752,63 @@ impl BreakpointId {
 
 #[derive(Clone, Debug)]
 pub struct Breakpoint {
-    address: u32,
+    address: u64,
     register_hw: usize,
 }
 
+/// The kind of memory access which triggers a watchpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchpointKind {
+    /// Halt when the memory is read.
+    Read,
+    /// Halt when the memory is written.
+    Write,
+    /// Halt on any access to the memory.
+    Access,
+}
+
+/// An exception condition which halts the core when vector catch is enabled for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VectorCatchCondition {
+    /// A HardFault exception.
+    HardFault,
+    /// A MemManage exception.
+    MemManage,
+    /// A BusFault exception.
+    BusFault,
+    /// A UsageFault exception, caused by a state, checking or coprocessor access error.
+    UsageFault,
+    /// A SecureFault exception, only on ARMv8-M cores with the Security Extension.
+    SecureFault,
+    /// A fault during exception entry or exception return.
+    ExceptionEntry,
+}
+
+#[derive(Clone, Debug)]
+pub struct Watchpoint {
+    address: u64,
+    size: u32,
+    kind: WatchpointKind,
+    register_hw: usize,
+}
+
+impl Watchpoint {
+    /// Start address of the watched memory.
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Number of watched bytes.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// The kind of access which triggers the watchpoint.
+    pub fn kind(&self) -> WatchpointKind {
+        self.kind
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Architecture {
     Arm,
@@ -577,6 +845,9 @@ pub enum HaltReason {
     Exception,
     /// Core halted due to a data watchpoint
     Watchpoint,
+    /// Core halted due to a vector catch, when it took the exception
+    /// covered by the given condition.
+    VectorCatch(VectorCatchCondition),
     /// Core halted after single step
     Step,
     /// Core halted because of a debugger request
@@ -588,3 +859,50 @@ pub enum HaltReason {
     /// This can happen for example when the core is already halted when we connect.
     Unknown,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn watchpoint_validation() {
+        assert!(validate_watchpoint(0x2000_0000, 1).is_ok());
+        assert!(validate_watchpoint(0x2000_0004, 4).is_ok());
+        assert!(validate_watchpoint(0x2000_0100, 0x100).is_ok());
+
+        // Size not a power of two
+        assert!(validate_watchpoint(0x2000_0000, 0).is_err());
+        assert!(validate_watchpoint(0x2000_0000, 3).is_err());
+        // Address not aligned to the size
+        assert!(validate_watchpoint(0x2000_0002, 4).is_err());
+    }
+
+    #[test]
+    fn free_unit_allocation() {
+        assert_eq!(first_free_unit(std::iter::empty()), 0);
+        assert_eq!(first_free_unit(vec![0, 1, 2].into_iter()), 3);
+        assert_eq!(first_free_unit(vec![2, 0, 3].into_iter()), 1);
+        assert_eq!(first_free_unit(vec![1].into_iter()), 0);
+    }
+
+    #[test]
+    fn out_of_range_values() {
+        assert_eq!(
+            valid_32bit_register_value(0xffff_ffff).unwrap(),
+            0xffff_ffff
+        );
+        assert!(matches!(
+            valid_32bit_register_value(0x1_0000_0000),
+            Err(error::Error::RegisterValueOutOfRange(0x1_0000_0000))
+        ));
+
+        assert_eq!(
+            crate::memory::valid_32bit_address(0x2000_0000).unwrap(),
+            0x2000_0000
+        );
+        assert!(matches!(
+            crate::memory::valid_32bit_address(0x1_2000_0000),
+            Err(error::Error::AddressOutOfRange(0x1_2000_0000))
+        ));
+    }
+}
@@ -3,15 +3,27 @@
 //! The `debug` module contains various debug functionality, which can be
 //! used to implement a debugger based on `probe-rs`.
 
+mod registers;
+mod stepping;
+mod symbols;
 mod variable;
 
-use crate::{core::Core, MemoryInterface};
+use crate::{
+    architecture::arm::core::fault::read_exception_frame,
+    core::{Architecture, Core},
+    MemoryInterface,
+};
+pub use registers::Registers;
+pub use stepping::{SteppingMode, SteppingResult};
+use symbols::SymbolTable;
+pub use symbols::{Symbol, SymbolKind};
 pub use variable::{Variable, VariableKind, VariantRole};
 
 // use std::{borrow, intrinsics::variant_count, io, path::{Path, PathBuf}, rc::Rc, str::{from_utf8, Utf8Error}};
 use std::{
     borrow, io,
     num::NonZeroU64,
+    ops::Range,
     path::{Path, PathBuf},
     rc::Rc,
     str::{from_utf8, Utf8Error},
@@ -38,6 +50,8 @@ pub enum DebugError {
     CharConversion(#[from] std::char::CharTryFromError),
     #[error(transparent)]
     IntConversion(#[from] std::num::TryFromIntError),
+    #[error("Unable to determine the return address of the current function")]
+    NoReturnAddress,
 }
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum ColumnType {
@@ -56,17 +70,37 @@ impl From<gimli::ColumnType> for ColumnType {
 
 #[derive(Debug)]
 pub struct StackFrame {
+    /// The id of the frame, which is unique in the backtrace it is part of.
+    ///
+    /// The frames are numbered from the innermost one, inlined frames included.
     pub id: u64,
     pub function_name: String,
     pub source_location: Option<SourceLocation>,
     pub registers: Registers,
-    pub pc: u32,
+    pub pc: u64,
     pub variables: Vec<Variable>,
+    /// The code of this frame was interrupted by an exception.
+    ///
+    /// The registers of the frame were restored from the exception frame pushed by the core,
+    /// and the frame before it is the exception handler.
+    pub is_exception_boundary: bool,
+    /// The function of this frame is inlined into the function of the next frame.
+    ///
+    /// Inlined frames share the registers and the program counter with the frame
+    /// of the function they are inlined into.
+    pub is_inlined: bool,
 }
 
 impl std::fmt::Display for StackFrame {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        writeln!(f, "{}: {}", self.id, self.function_name)?;
+        if self.is_exception_boundary {
+            writeln!(f, "<exception entry>")?;
+        }
+        if self.is_inlined {
+            writeln!(f, "{}: {} (inlined)", self.id, self.function_name)?;
+        } else {
+            writeln!(f, "{}: {}", self.id, self.function_name)?;
+        }
         if let Some(si) = &self.source_location {
             write!(
                 f,
@@ -116,67 +150,6 @@ fn variable_recurse(
 
     ret
 }
-#[derive(Debug, Clone)]
-pub struct Registers([Option<u32>; 16]);
-
-impl Registers {
-    pub fn from_core(core: &mut Core) -> Self {
-        let mut registers = Registers([None; 16]);
-        for i in 0..16 {
-            registers[i as usize] = core.read_core_reg(i).ok();
-        }
-        registers
-    }
-
-    pub fn get_call_frame_address(&self) -> Option<u32> {
-        self.0[13]
-    }
-
-    pub fn set_call_frame_address(&mut self, value: Option<u32>) {
-        self.0[13] = value;
-    }
-
-    pub fn get_frame_program_counter(&self) -> Option<u32> {
-        self.0[15]
-    }
-}
-
-impl<'a> IntoIterator for &'a Registers {
-    type Item = &'a Option<u32>;
-    type IntoIter = std::slice::Iter<'a, Option<u32>>;
-
-    fn into_iter(self) -> std::slice::Iter<'a, Option<u32>> {
-        self.0.iter()
-    }
-}
-
-impl std::ops::Index<usize> for Registers {
-    type Output = Option<u32>;
-
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.0[index]
-    }
-}
-
-impl std::ops::IndexMut<usize> for Registers {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        &mut self.0[index]
-    }
-}
-
-impl std::ops::Index<std::ops::Range<usize>> for Registers {
-    type Output = [Option<u32>];
-
-    fn index(&self, index: std::ops::Range<usize>) -> &Self::Output {
-        &self.0[index]
-    }
-}
-
-impl std::ops::IndexMut<std::ops::Range<usize>> for Registers {
-    fn index_mut(&mut self, index: std::ops::Range<usize>) -> &mut Self::Output {
-        &mut self.0[index]
-    }
-}
 
 #[derive(Debug, PartialEq)]
 pub struct SourceLocation {
@@ -193,6 +166,14 @@ pub struct StackFrameIterator<'debuginfo, 'probe, 'core> {
     frame_count: u64,
     pc: Option<u64>,
     registers: Registers,
+    /// The next frame was interrupted by an exception.
+    is_exception_boundary: bool,
+    /// Frames of inlined functions which were not returned yet, the next one is last.
+    inlined_frames: Vec<StackFrame>,
+    /// The error which ended the unwinding early.
+    unwind_error: Option<crate::Error>,
+    /// The id of the next frame.
+    frame_id: u64,
 }
 
 impl<'debuginfo, 'probe, 'core> StackFrameIterator<'debuginfo, 'probe, 'core> {
@@ -201,7 +182,8 @@ impl<'debuginfo, 'probe, 'core> StackFrameIterator<'debuginfo, 'probe, 'core> {
         core: &'core mut Core<'probe>,
         address: u64,
     ) -> Self {
-        let registers = Registers::from_core(core);
+        let mut registers = Registers::from_core(core);
+        registers.set_frame_program_counter(Some(address));
         let pc = address;
 
         Self {
@@ -210,6 +192,10 @@ impl<'debuginfo, 'probe, 'core> StackFrameIterator<'debuginfo, 'probe, 'core> {
             frame_count: 0,
             pc: Some(pc),
             registers,
+            is_exception_boundary: false,
+            inlined_frames: Vec::new(),
+            unwind_error: None,
+            frame_id: 0,
         }
     }
 }
@@ -218,6 +204,26 @@ impl<'debuginfo, 'probe, 'core> Iterator for StackFrameIterator<'debuginfo, 'pro
     type Item = StackFrame;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let mut frame = match self.inlined_frames.pop() {
+            Some(frame) => frame,
+            None => self.unwind_frame()?,
+        };
+
+        // MS DAP Specification requires the id to be unique accross all threads. The frame pointer
+        // can't be used, because inlined frames share it with the frame they are inlined into.
+        frame.id = self.frame_id;
+        self.frame_id += 1;
+
+        Some(frame)
+    }
+}
+
+impl<'debuginfo, 'probe, 'core> StackFrameIterator<'debuginfo, 'probe, 'core> {
+    /// Return the frame at the current program counter, and unwind the registers of its caller.
+    ///
+    /// The frames of functions inlined at the program counter are stored, and returned
+    /// before the next frame is unwound.
+    fn unwind_frame(&mut self) -> Option<StackFrame> {
         use gimli::UnwindSection;
         let mut ctx = gimli::UninitializedUnwindContext::new();
         let bases = gimli::BaseAddresses::default();
@@ -250,10 +256,10 @@ impl<'debuginfo, 'probe, 'core> Iterator for StackFrameIterator<'debuginfo, 'pro
 
         let current_cfa = match unwind_info.cfa() {
             gimli::CfaRule::RegisterAndOffset { register, offset } => {
-                let reg_val = self.registers[register.0 as usize];
+                let reg_val = self.registers.get_value(register.0);
 
                 match reg_val {
-                    Some(reg_val) => Some((i64::from(reg_val) + offset) as u32),
+                    Some(reg_val) => Some((reg_val as i64 + offset) as u64),
                     None => {
                         log::warn!(
                             "Unable to calculate CFA: Missing value of register {}",
@@ -270,71 +276,172 @@ impl<'debuginfo, 'probe, 'core> Iterator for StackFrameIterator<'debuginfo, 'pro
             debug!("Current CFA: {:#x}", cfa);
         }
 
+        // The frame shows the registers of the current function, the unwinding below
+        // restores the registers of its caller.
+        //
+        // The program counter of a caller frame is its return address. It is not the
+        // address `pc` of the calling instruction, which is only used for the lookups.
+        let frame_registers = self.registers.clone();
+
+        let return_frame = match self.debug_info.get_stackframe_info(
+            &mut self.core,
+            pc,
+            self.frame_count,
+            frame_registers,
+            current_cfa,
+        ) {
+            Ok(mut frames) => {
+                frames[0].is_exception_boundary = self.is_exception_boundary;
+
+                // Return the innermost frame now, and the other ones on the next calls.
+                frames.reverse();
+                let frame = frames.pop();
+                self.inlined_frames = frames;
+
+                frame
+            }
+            Err(e) => {
+                log::warn!("Unable to get stack frame information: {}", e);
+                None
+            }
+        };
+
+        self.frame_count += 1;
+
+        let stack_pointer = self.registers.stack_pointer_register();
+        let return_address = self.registers.return_address_register();
+        let program_counter = self.registers.program_counter_register();
+
         // generate previous registers
-        for i in 0..16 {
-            if i == 13 {
+        for i in 0..self.registers.len() as u16 {
+            if Some(i) == stack_pointer || Some(i) == program_counter {
                 continue;
             }
             use gimli::read::RegisterRule::*;
 
-            let register_rule = unwind_info.register(gimli::Register(i as u16));
+            let register_rule = unwind_info.register(gimli::Register(i));
 
             log::trace!("Register {}: {:?}", i, &register_rule);
 
-            self.registers[i] = match register_rule {
+            let value = match register_rule {
                 Undefined => {
-                    // If we get undefined for the LR register (register 14) or any callee saved register,
+                    // If we get undefined for the return address register or any callee saved register,
                     // we assume that it is unchanged. Gimli doesn't allow us
                     // to distinguish if  a rule is not present or actually set to Undefined
                     // in the call frame information.
 
-                    match i {
-                        4 | 5 | 6 | 7 | 8 | 10 | 11 | 14 => self.registers[i],
-                        15 => Some(pc as u32),
-                        _ => None,
+                    if Some(i) == return_address || self.registers.is_callee_saved(i) {
+                        self.registers.get_value(i)
+                    } else {
+                        None
                     }
                 }
-                SameValue => self.registers[i],
+                SameValue => self.registers.get_value(i),
                 Offset(o) => {
-                    let addr = i64::from(current_cfa.unwrap()) + o;
+                    let addr = current_cfa.unwrap() as i64 + o;
                     let mut buff = [0u8; 4];
-                    self.core.read_8(addr as u32, &mut buff).unwrap();
+                    self.core.read_8(addr as u64, &mut buff).unwrap();
 
                     let val = u32::from_le_bytes(buff);
 
                     debug!("reg[{: >}]={:#08x}", i, val);
 
-                    Some(val)
+                    Some(u64::from(val))
                 }
                 _ => unimplemented!(),
-            }
+            };
+
+            self.registers.set_value(i, value);
         }
 
         self.registers.set_call_frame_address(current_cfa);
 
-        let return_frame = match self.debug_info.get_stackframe_info(
-            &mut self.core,
-            pc,
-            self.frame_count,
-            self.registers.clone(),
-        ) {
-            Ok(frame) => Some(frame),
+        match self.unwind_exception_frame() {
+            Ok(true) => (),
+            Ok(false) => {
+                // Next function is where our current return register is pointing to.
+                // We just have to remove the lowest bit (indicator for Thumb mode on ARM).
+                //
+                // We also have to subtract one, as we want the calling instruction for
+                // a backtrace, not the next instruction to be executed.
+                let return_address = self.registers.get_return_address().map(|ra| ra & !1);
+
+                self.registers.set_frame_program_counter(return_address);
+                self.pc = return_address.and_then(|ra| ra.checked_sub(1));
+                self.is_exception_boundary = false;
+            }
             Err(e) => {
-                log::warn!("Unable to get stack frame information: {}", e);
-                None
+                log::warn!("Unable to unwind through the exception frame: {}", e);
+                self.pc = None;
+                self.unwind_error = Some(e);
             }
+        }
+
+        return_frame
+    }
+}
+
+impl<'debuginfo, 'probe, 'core> StackFrameIterator<'debuginfo, 'probe, 'core> {
+    /// The error which ended the unwinding before the outermost frame was reached.
+    ///
+    /// This is set when the registers of the code interrupted by an exception can not
+    /// be restored, for example because the exception frame on the stack can not be read.
+    pub fn unwind_error(&self) -> Option<&crate::Error> {
+        self.unwind_error.as_ref()
+    }
+
+    /// Continue unwinding in the code interrupted by an exception, if the current
+    /// frame is an exception handler on a Cortex-M core.
+    ///
+    /// On exception entry, the LR contains an `EXC_RETURN` value, and the registers of the
+    /// interrupted code are pushed onto the stack. They are restored from there.
+    ///
+    /// Returns `true` if the current frame is an exception handler.
+    fn unwind_exception_frame(&mut self) -> Result<bool, crate::Error> {
+        if self.core.architecture() != Architecture::Arm {
+            return Ok(false);
+        }
+
+        let (lr, sp) = match (
+            self.registers.get_return_address(),
+            self.registers.get_call_frame_address(),
+        ) {
+            (Some(lr), Some(sp)) => (lr as u32, sp as u32),
+            _ => return Ok(false),
         };
 
-        self.frame_count += 1;
+        let frame = match read_exception_frame(self.core, lr, sp)? {
+            Some(frame) => frame,
+            None => return Ok(false),
+        };
 
-        // Next function is where our current return register is pointing to.
-        // We just have to remove the lowest bit (indicator for Thumb mode).
-        //
-        // We also have to subtract one, as we want the calling instruction for
-        // a backtrace, not the next instruction to be executed.
-        self.pc = self.registers[14].map(|pc| u64::from(pc & !1) - 1);
+        debug!(
+            "Unwinding through exception frame at {:#010x}",
+            frame.address
+        );
 
-        return_frame
+        let stacked_registers = [
+            (0, frame.r0),
+            (1, frame.r1),
+            (2, frame.r2),
+            (3, frame.r3),
+            (12, frame.r12),
+            (14, frame.lr),
+            (15, frame.pc),
+        ];
+
+        for &(register, value) in &stacked_registers {
+            self.registers.set_value(register, Some(u64::from(value)));
+        }
+
+        self.registers
+            .set_call_frame_address(Some(u64::from(frame.stack_pointer_before_exception())));
+
+        // The stacked PC is the instruction which was interrupted, it has not been executed yet.
+        self.pc = Some(u64::from(frame.pc));
+        self.is_exception_boundary = true;
+
+        Ok(true)
     }
 }
 
@@ -346,18 +453,15 @@ type FunctionDie<'abbrev, 'unit> = gimli::DebuggingInformationEntry<
     gimli::EndianReader<gimli::LittleEndian, std::rc::Rc<[u8]>>,
     usize,
 >;
-type EntriesCursor<'abbrev, 'unit> = gimli::EntriesCursor<
-    'abbrev,
-    'unit,
-    gimli::EndianReader<gimli::LittleEndian, std::rc::Rc<[u8]>>,
->;
 type UnitIter =
     gimli::DebugInfoUnitHeadersIter<gimli::EndianReader<gimli::LittleEndian, std::rc::Rc<[u8]>>>;
 
-/// Debug information which is parsed from DWARF debugging information.
+/// Debug information which is parsed from DWARF debugging information,
+/// and the symbol table of the ELF file.
 pub struct DebugInfo {
     dwarf: gimli::Dwarf<DwarfReader>,
     frame_section: gimli::DebugFrame<DwarfReader>,
+    symbols: SymbolTable,
 }
 
 impl DebugInfo {
@@ -399,9 +503,31 @@ impl DebugInfo {
             //object,
             dwarf: dwarf_cow,
             frame_section,
+            symbols: SymbolTable::from_object(&object),
         })
     }
 
+    /// Find the address range of a function by its name.
+    ///
+    /// The name is either the full path of the function, like `my_crate::module::func`,
+    /// or a trailing part of the path, like `module::func` or `func`.
+    pub fn get_function_range(&self, name: &str) -> Option<Range<u64>> {
+        self.symbols
+            .find_by_name(name, SymbolKind::Function)
+            .map(Symbol::range)
+    }
+
+    /// Find a static variable by its name, which is matched like in [`DebugInfo::get_function_range`].
+    pub fn get_static_symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.find_by_name(name, SymbolKind::Data)
+    }
+
+    /// Find the function or static variable containing `address`,
+    /// and return it with the offset of `address` into it.
+    pub fn get_symbol_at(&self, address: u64) -> Option<(&Symbol, u64)> {
+        self.symbols.find_by_address(address)
+    }
+
     pub fn get_source_location(&self, address: u64) -> Option<SourceLocation> {
         let mut units = self.dwarf.units();
 
@@ -497,6 +623,73 @@ impl DebugInfo {
         None
     }
 
+    /// Find the row of the line program which contains `address`.
+    fn get_line_row(&self, address: u64) -> Option<LineRow> {
+        let mut units = self.get_units();
+
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            let line_program = match unit_info.unit.line_program.clone() {
+                Some(line_program) => line_program,
+                None => continue,
+            };
+
+            let (program, sequences) = match line_program.sequences() {
+                Ok(program_and_sequences) => program_and_sequences,
+                Err(_) => continue,
+            };
+
+            let sequence = match sequences
+                .iter()
+                .find(|sequence| sequence.start <= address && address < sequence.end)
+            {
+                Some(sequence) => sequence,
+                None => continue,
+            };
+
+            let mut rows = program.resume_from(sequence);
+            let mut line_row = None;
+
+            // If several rows start at the same address, the last one applies.
+            while let Ok(Some((_, row))) = rows.next_row() {
+                if row.address() > address {
+                    break;
+                }
+
+                line_row = Some(LineRow {
+                    address: row.address(),
+                    file_index: row.file_index(),
+                    line: row.line().map(NonZeroU64::get),
+                    is_stmt: row.is_stmt(),
+                });
+            }
+
+            return line_row;
+        }
+
+        None
+    }
+
+    /// Get the address ranges of the function containing `address`, and of the functions
+    /// inlined into it at `address`, from the outermost to the innermost function.
+    fn get_function_ranges(&self, address: u64) -> Vec<Vec<Range<u64>>> {
+        let mut units = self.get_units();
+
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            let function_dies = unit_info.get_function_dies(address);
+
+            if !function_dies.is_empty() {
+                return function_dies
+                    .iter()
+                    .map(|die_cursor_state| {
+                        unit_info.get_die_ranges(&die_cursor_state.function_die)
+                    })
+                    .collect();
+            }
+        }
+
+        Vec::new()
+    }
+
     fn get_units(&self) -> UnitIter {
         self.dwarf.units()
     }
@@ -513,46 +706,79 @@ impl DebugInfo {
         None
     }
 
+    /// Get the stack frames at `address`.
+    ///
+    /// One frame is returned for the function containing `address`, and one virtual frame for
+    /// every function inlined at `address`. The innermost inlined function is returned first.
     fn get_stackframe_info(
         &self,
         core: &mut Core<'_>,
         address: u64,
         frame_count: u64,
         registers: Registers,
-    ) -> Result<StackFrame, DebugError> {
+        cfa: Option<u64>,
+    ) -> Result<Vec<StackFrame>, DebugError> {
         let mut units = self.get_units();
         let unknown_function = format!("<unknown_function_{}>", frame_count);
         while let Some(unit_info) = self.get_next_unit_info(&mut units) {
-            if let Some(die_cursor_state) = &mut unit_info.get_function_die(address) {
+            let function_dies = unit_info.get_function_dies(address);
+
+            let function_die = match function_dies.first() {
+                Some(die_cursor_state) => &die_cursor_state.function_die,
+                None => continue,
+            };
+
+            // Only the function has a frame base, the inlined functions share it.
+            let frame_base = unit_info
+                .get_frame_base(function_die, &registers, cfa)
+                .or(cfa);
+
+            let mut frames = Vec::with_capacity(function_dies.len());
+
+            // The innermost function is located at the address, every other function
+            // at the call of the function inlined into it.
+            let mut source_location = self.get_source_location(address);
+
+            for (inline_depth, die_cursor_state) in function_dies.iter().enumerate().rev() {
+                let function_die = &die_cursor_state.function_die;
+
                 let function_name = unit_info
-                    .get_function_name(&die_cursor_state.function_die)
-                    .unwrap_or(unknown_function);
+                    .get_function_name(function_die)
+                    .unwrap_or_else(|| unknown_function.clone());
                 let variables = unit_info.get_function_variables(
                     core,
-                    die_cursor_state,
-                    u64::from(registers.get_call_frame_address().unwrap_or(0)),
-                    u64::from(registers.get_frame_program_counter().unwrap_or(0)),
+                    function_die,
+                    frame_base.unwrap_or(0),
+                    registers.get_frame_program_counter().unwrap_or(0),
                 )?;
-                // dbg!(&variables);
-                return Ok(StackFrame {
-                    id: registers.get_call_frame_address().unwrap_or(0) as u64, //MS DAP Specification requires the id to be unique accross all threads, so using the frame pointer as the id.
+                let call_location = unit_info.get_call_location(function_die);
+
+                frames.push(StackFrame {
+                    // The id is assigned by the `StackFrameIterator`.
+                    id: 0,
                     function_name,
-                    source_location: self.get_source_location(address),
-                    registers,
-                    pc: address as u32,
+                    source_location: std::mem::replace(&mut source_location, call_location),
+                    registers: registers.clone(),
+                    pc: address,
                     variables,
+                    is_exception_boundary: false,
+                    is_inlined: inline_depth > 0,
                 });
             }
+
+            return Ok(frames);
         }
 
-        Ok(StackFrame {
-            id: frame_count,
+        Ok(vec![StackFrame {
+            id: 0,
             function_name: unknown_function,
             source_location: self.get_source_location(address),
             registers,
-            pc: address as u32,
+            pc: address,
             variables: vec![],
-        })
+            is_exception_boundary: false,
+            is_inlined: false,
+        }])
     }
 
     pub fn try_unwind<'probe, 'core>(
@@ -704,9 +930,31 @@ impl DebugInfo {
     }
 }
 
+/// A row of the line program, which describes the source line of a range of instructions.
+#[derive(Debug, Clone, Copy)]
+struct LineRow {
+    /// The address of the first instruction of the row.
+    address: u64,
+    /// The index of the source file in the line program of the unit.
+    file_index: u64,
+    line: Option<u64>,
+    /// The row is a recommended breakpoint location for its source line.
+    is_stmt: bool,
+}
+
+impl LineRow {
+    /// Returns true if both rows belong to the same source line.
+    fn is_same_line(&self, other: &LineRow) -> bool {
+        self.file_index == other.file_index && self.line == other.line
+    }
+}
+
+/// The maximum number of `DW_AT_abstract_origin` and `DW_AT_specification` references
+/// which are followed to find the name of a function.
+const MAX_FUNCTION_REFERENCES: usize = 8;
+
 struct DieCursorState<'abbrev, 'unit> {
-    _entries_cursor: EntriesCursor<'abbrev, 'unit>,
-    _depth: isize,
+    depth: isize,
     function_die: FunctionDie<'abbrev, 'unit>,
 }
 
@@ -716,44 +964,170 @@ struct UnitInfo<'debuginfo> {
 }
 
 impl<'debuginfo> UnitInfo<'debuginfo> {
-    fn get_function_die(&self, address: u64) -> Option<DieCursorState> {
+    /// Find the function containing `address`, and the functions which are inlined into it at `address`.
+    ///
+    /// The function is returned first, followed by the inlined functions from the outermost
+    /// to the innermost inlining level.
+    fn get_function_dies(&self, address: u64) -> Vec<DieCursorState> {
         let mut entries_cursor = self.unit.entries();
+        let mut depth = 0;
+        let mut function_dies: Vec<DieCursorState> = Vec::new();
 
-        while let Ok(Some((depth, current))) = entries_cursor.next_dfs() {
-            match current.tag() {
-                gimli::DW_TAG_subprogram | gimli::DW_TAG_inlined_subroutine => {
-                    let mut ranges = self
-                        .debug_info
-                        .dwarf
-                        .die_ranges(&self.unit, &current)
-                        .unwrap();
-
-                    while let Ok(Some(ranges)) = ranges.next() {
-                        if (ranges.begin <= address) && (address < ranges.end) {
-                            return Some(DieCursorState {
-                                _depth: depth,
-                                function_die: current.clone(),
-                                _entries_cursor: entries_cursor,
-                            });
-                        }
-                    }
+        while let Ok(Some((delta_depth, current))) = entries_cursor.next_dfs() {
+            depth += delta_depth;
+
+            // All inlined functions are descendants of the function they are inlined into.
+            if let Some(function) = function_dies.first() {
+                if depth <= function.depth {
+                    break;
                 }
-                _ => (),
+            }
+
+            let is_function = match current.tag() {
+                gimli::DW_TAG_subprogram => function_dies.is_empty(),
+                gimli::DW_TAG_inlined_subroutine => !function_dies.is_empty(),
+                _ => false,
             };
+
+            if is_function && self.die_contains_address(current, address) {
+                function_dies.push(DieCursorState {
+                    depth,
+                    function_die: current.clone(),
+                });
+            }
+        }
+
+        function_dies
+    }
+
+    fn die_contains_address(&self, die: &FunctionDie, address: u64) -> bool {
+        let mut ranges = match self.debug_info.dwarf.die_ranges(&self.unit, die) {
+            Ok(ranges) => ranges,
+            Err(_) => return false,
+        };
+
+        while let Ok(Some(range)) = ranges.next() {
+            if (range.begin <= address) && (address < range.end) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn get_die_ranges(&self, die: &FunctionDie) -> Vec<Range<u64>> {
+        let mut die_ranges = Vec::new();
+
+        if let Ok(mut ranges) = self.debug_info.dwarf.die_ranges(&self.unit, die) {
+            while let Ok(Some(range)) = ranges.next() {
+                die_ranges.push(range.begin..range.end);
+            }
+        }
+
+        die_ranges
+    }
+
+    /// The source location of the call of an inlined function, in the function it is inlined into.
+    fn get_call_location(&self, inlined_die: &FunctionDie) -> Option<SourceLocation> {
+        let file_index = match inlined_die.attr_value(gimli::DW_AT_call_file).ok()?? {
+            gimli::AttributeValue::FileIndex(index) => index,
+            _ => return None,
+        };
+
+        let header = self.unit.line_program.as_ref()?.header();
+        let file_entry = header.file(file_index)?;
+
+        let attr_string = |value| {
+            self.debug_info
+                .dwarf
+                .attr_string(&self.unit, value)
+                .ok()
+                .map(|string| String::from_utf8_lossy(&string).to_string())
+        };
+
+        let udata_attr = |attribute| {
+            inlined_die
+                .attr_value(attribute)
+                .ok()
+                .flatten()
+                .and_then(|value| value.udata_value())
+        };
+
+        Some(SourceLocation {
+            line: udata_attr(gimli::DW_AT_call_line),
+            column: udata_attr(gimli::DW_AT_call_column).map(|column| match column {
+                0 => ColumnType::LeftEdge,
+                column => ColumnType::Column(column),
+            }),
+            file: attr_string(file_entry.path_name()),
+            directory: file_entry
+                .directory(header)
+                .and_then(attr_string)
+                .map(PathBuf::from),
+        })
+    }
+
+    /// Evaluate the `DW_AT_frame_base` of a function, using the registers of its stack frame.
+    fn get_frame_base(
+        &self,
+        function_die: &FunctionDie,
+        registers: &Registers,
+        cfa: Option<u64>,
+    ) -> Option<u64> {
+        let expression = match function_die.attr_value(gimli::DW_AT_frame_base).ok()?? {
+            gimli::AttributeValue::Exprloc(expression) => expression,
+            _ => return None,
+        };
+
+        let mut evaluation = expression.evaluation(self.unit.encoding());
+        let mut result = evaluation.evaluate().ok()?;
+
+        loop {
+            use gimli::EvaluationResult::*;
+
+            result = match result {
+                Complete => break,
+                RequiresRegister { register, .. } => {
+                    let value = registers.get_value(register.0)?;
+                    evaluation
+                        .resume_with_register(gimli::Value::Generic(value))
+                        .ok()?
+                }
+                RequiresCallFrameCfa => evaluation.resume_with_call_frame_cfa(cfa?).ok()?,
+                _ => return None,
+            }
+        }
+
+        match evaluation.result().first()?.location {
+            Location::Register { register } => registers.get_value(register.0),
+            Location::Address { address } => Some(address),
+            _ => None,
         }
-        None
     }
 
     fn get_function_name(&self, function_die: &FunctionDie) -> Option<String> {
-        if let Some(fn_name_attr) = function_die
-            .attr(gimli::DW_AT_name)
-            .expect(" Failed to parse entry")
-        {
-            if let gimli::AttributeValue::DebugStrRef(fn_name_ref) = fn_name_attr.value() {
-                let fn_name_raw = self.debug_info.dwarf.string(fn_name_ref).unwrap();
-
-                return Some(String::from_utf8_lossy(&fn_name_raw).to_string());
+        let mut die = function_die.clone();
+
+        // Malformed debug information can contain cycles of references,
+        // so only a limited number of them is followed.
+        for _ in 0..MAX_FUNCTION_REFERENCES {
+            if let Ok(Some(fn_name_attr)) = die.attr_value(gimli::DW_AT_name) {
+                if let Ok(fn_name_raw) = self.debug_info.dwarf.attr_string(&self.unit, fn_name_attr)
+                {
+                    return Some(String::from_utf8_lossy(&fn_name_raw).to_string());
+                }
             }
+
+            // Inlined functions, and the out-of-line instances of inlined functions,
+            // refer to the abstract description of the function for their name.
+            die = [gimli::DW_AT_abstract_origin, gimli::DW_AT_specification]
+                .iter()
+                .find_map(|attribute| match die.attr_value(*attribute) {
+                    Ok(Some(gimli::AttributeValue::UnitRef(offset))) => {
+                        self.unit.entry(offset).ok()
+                    }
+                    _ => None,
+                })?;
         }
 
         None
@@ -777,7 +1151,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                 Complete => break,
                 RequiresMemory { address, size, .. } => {
                     let mut buff = vec![0u8; size as usize];
-                    core.read_8(address as u32, &mut buff)
+                    core.read_8(address, &mut buff)
                         .expect("Failed to read memory");
                     match size {
                         1 => evaluation.resume_with_memory(gimli::Value::U8(buff[0]))?,
@@ -805,7 +1179,17 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                     register,
                     base_type,
                 } => {
-                    let raw_value = core.read_core_reg(register.0 as u16)?;
+                    // The DWARF register number is the position in the register file.
+                    let core_register = core
+                        .registers()
+                        .get_platform_register(register.0 as usize)
+                        .ok_or_else(|| {
+                            crate::Error::Other(anyhow::anyhow!(
+                                "Unknown DWARF register {}",
+                                register.0
+                            ))
+                        })?;
+                    let raw_value = core.read_core_reg(core_register)?;
 
                     if base_type != gimli::UnitOffset(0) {
                         todo!(
@@ -813,7 +1197,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                         )
                     }
 
-                    evaluation.resume_with_register(gimli::Value::Generic(raw_value as u64))?
+                    evaluation.resume_with_register(gimli::Value::Generic(raw_value))?
                 }
                 x => {
                     todo!("expr_to_piece {:?}", x)
@@ -1128,7 +1512,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
     fn get_function_variables(
         &self,
         core: &mut Core<'_>,
-        die_cursor_state: &mut DieCursorState,
+        function_die: &FunctionDie,
         frame_base: u64,
         program_counter: u64,
     ) -> Result<Vec<Variable>, DebugError> {
@@ -1136,7 +1520,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
         let mut tree = self
             .unit
             .header
-            .entries_tree(abbrevs, Some(die_cursor_state.function_die.offset()))?;
+            .entries_tree(abbrevs, Some(function_die.offset()))?;
         let function_node = tree.root()?;
         let mut root_variable = Variable::new();
         root_variable.name = "<locals>".to_string();
@@ -1251,7 +1635,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                                         };
                                         //Now, retrieve the location by reading the adddress pointed to by the parent variable
                                         let mut buff = [0u8; 4];
-                                        core.read_8(variable.memory_location as u32, &mut buff)?;
+                                        core.read_8(variable.memory_location, &mut buff)?;
                                         referenced_variable.memory_location =
                                             u32::from_le_bytes(buff) as u64;
                                         self.extract_type(
@@ -1311,7 +1695,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                     }
                 };
                 let mut buff = [0u8; 1]; //NOTE: hard-coding value of variable.byte_size to 1 ... replace with code if necessary
-                core.read_8(variable.memory_location as u32, &mut buff)?;
+                core.read_8(variable.memory_location, &mut buff)?;
                 let this_enum_const_value = u8::from_le_bytes(buff).to_string();
                 let enumumerator_value =
                     match enumerator_values.into_iter().find(|enumerator_variable| {
@@ -1604,7 +1988,7 @@ pub(crate) fn _print_all_attributes(
                         Complete => break,
                         RequiresMemory { address, size, .. } => {
                             let mut buff = vec![0u8; size as usize];
-                            core.read_8(address as u32, &mut buff)
+                            core.read_8(address, &mut buff)
                                 .expect("Failed to read memory");
                             match size {
                                 1 => evaluation
@@ -1651,7 +2035,7 @@ pub(crate) fn _print_all_attributes(
                                 )
                             }
                             evaluation
-                                .resume_with_register(gimli::Value::Generic(raw_value as u64))
+                                .resume_with_register(gimli::Value::Generic(raw_value))
                                 .unwrap()
                         }
                         x => {
@@ -1682,3 +2066,258 @@ pub(crate) fn _print_all_attributes(
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::architecture::arm::core::dump::CoreDump;
+    use gimli::write::{
+        Address, AttributeValue, DwarfUnit, EndianVec, LineProgram, LineString, Sections,
+    };
+    use std::{collections::BTreeMap, rc::Rc};
+
+    /// Debug information of a function `outer` at 0x100..0x140, into which `middle`
+    /// is inlined at 0x110..0x130, into which `inner` is inlined at 0x118..0x120.
+    pub(super) fn inlined_debug_info() -> DebugInfo {
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+
+        let mut dwarf = DwarfUnit::new(encoding);
+
+        let mut line_program = LineProgram::new(
+            encoding,
+            gimli::LineEncoding::default(),
+            LineString::String(b"/src".to_vec()),
+            LineString::String(b"main.rs".to_vec()),
+            None,
+        );
+        let directory = line_program.default_directory();
+        let file = line_program.add_file(LineString::String(b"main.rs".to_vec()), directory, None);
+
+        line_program.begin_sequence(Some(Address::Constant(0x100)));
+        for (address_offset, line) in &[(0x00, 10), (0x10, 40), (0x18, 50), (0x20, 41)] {
+            let row = line_program.row();
+            row.file = file;
+            row.address_offset = *address_offset;
+            row.line = *line;
+            line_program.generate_row();
+        }
+        line_program.end_sequence(0x40);
+        dwarf.unit.line_program = line_program;
+
+        let root = dwarf.unit.root();
+        let root_die = dwarf.unit.get_mut(root);
+        root_die.set(
+            gimli::DW_AT_name,
+            AttributeValue::String(b"main.rs".to_vec()),
+        );
+        root_die.set(
+            gimli::DW_AT_comp_dir,
+            AttributeValue::String(b"/src".to_vec()),
+        );
+        root_die.set(
+            gimli::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(0x100)),
+        );
+        root_die.set(gimli::DW_AT_high_pc, AttributeValue::Udata(0x40));
+
+        let mut subprogram = |name: Option<&str>| {
+            let id = dwarf.unit.add(root, gimli::DW_TAG_subprogram);
+            if let Some(name) = name {
+                dwarf.unit.get_mut(id).set(
+                    gimli::DW_AT_name,
+                    AttributeValue::String(name.as_bytes().to_vec()),
+                );
+            }
+            id
+        };
+
+        let inner = subprogram(Some("inner"));
+        let middle = subprogram(Some("middle"));
+        let outer = subprogram(Some("outer"));
+        let cyclic = [subprogram(None), subprogram(None)];
+
+        let outer_die = dwarf.unit.get_mut(outer);
+        outer_die.set(
+            gimli::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(0x100)),
+        );
+        outer_die.set(gimli::DW_AT_high_pc, AttributeValue::Udata(0x40));
+
+        let mut inlined_subroutine = |parent, origin, low_pc, line, column| {
+            let id = dwarf.unit.add(parent, gimli::DW_TAG_inlined_subroutine);
+            let die = dwarf.unit.get_mut(id);
+            die.set(
+                gimli::DW_AT_abstract_origin,
+                AttributeValue::UnitRef(origin),
+            );
+            die.set(
+                gimli::DW_AT_low_pc,
+                AttributeValue::Address(Address::Constant(low_pc)),
+            );
+            die.set(gimli::DW_AT_high_pc, AttributeValue::Udata(0x8));
+            die.set(
+                gimli::DW_AT_call_file,
+                AttributeValue::FileIndex(Some(file)),
+            );
+            die.set(gimli::DW_AT_call_line, AttributeValue::Udata(line));
+            die.set(gimli::DW_AT_call_column, AttributeValue::Udata(column));
+            id
+        };
+
+        let middle_inlined = inlined_subroutine(outer, middle, 0x110, 20, 5);
+        inlined_subroutine(middle_inlined, inner, 0x118, 30, 0);
+        dwarf
+            .unit
+            .get_mut(middle_inlined)
+            .set(gimli::DW_AT_high_pc, AttributeValue::Udata(0x20));
+
+        // References which form a cycle, as found in malformed debug information.
+        dwarf.unit.get_mut(cyclic[0]).set(
+            gimli::DW_AT_abstract_origin,
+            AttributeValue::UnitRef(cyclic[1]),
+        );
+        dwarf.unit.get_mut(cyclic[1]).set(
+            gimli::DW_AT_specification,
+            AttributeValue::UnitRef(cyclic[0]),
+        );
+
+        let mut sections = Sections::new(EndianVec::new(gimli::LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let mut section_data = BTreeMap::new();
+        sections
+            .for_each(|id, section| -> Result<(), gimli::Error> {
+                section_data.insert(id, section.slice().to_vec());
+                Ok(())
+            })
+            .unwrap();
+
+        let load_section = |id: gimli::SectionId| -> Result<DwarfReader, gimli::Error> {
+            let data = section_data.get(&id).cloned().unwrap_or_default();
+
+            Ok(gimli::EndianRcSlice::new(
+                Rc::from(data.into_boxed_slice()),
+                gimli::LittleEndian,
+            ))
+        };
+
+        DebugInfo {
+            dwarf: gimli::Dwarf::load(load_section).unwrap(),
+            frame_section: load_section(gimli::SectionId::DebugFrame).unwrap().into(),
+            symbols: SymbolTable::default(),
+        }
+    }
+
+    #[test]
+    fn function_dies_of_inlined_functions() {
+        let debug_info = inlined_debug_info();
+        let unit_info = debug_info
+            .get_next_unit_info(&mut debug_info.get_units())
+            .unwrap();
+
+        let names = |address| -> Vec<Option<String>> {
+            unit_info
+                .get_function_dies(address)
+                .iter()
+                .map(|state| unit_info.get_function_name(&state.function_die))
+                .collect()
+        };
+
+        let name = |name: &str| Some(name.to_owned());
+
+        assert_eq!(names(0x104), vec![name("outer")]);
+        assert_eq!(names(0x112), vec![name("outer"), name("middle")]);
+        assert_eq!(
+            names(0x11a),
+            vec![name("outer"), name("middle"), name("inner")]
+        );
+        assert_eq!(names(0x140), vec![]);
+    }
+
+    #[test]
+    fn call_location_of_inlined_functions() {
+        let debug_info = inlined_debug_info();
+        let unit_info = debug_info
+            .get_next_unit_info(&mut debug_info.get_units())
+            .unwrap();
+
+        let function_dies = unit_info.get_function_dies(0x11a);
+        let call_locations: Vec<_> = function_dies
+            .iter()
+            .map(|state| unit_info.get_call_location(&state.function_die))
+            .collect();
+
+        assert!(call_locations[0].is_none());
+
+        let middle_call = call_locations[1].as_ref().unwrap();
+        assert_eq!(middle_call.line, Some(20));
+        assert_eq!(middle_call.column, Some(ColumnType::Column(5)));
+        assert_eq!(middle_call.file.as_deref(), Some("main.rs"));
+        assert_eq!(middle_call.directory, Some(PathBuf::from("/src")));
+
+        let inner_call = call_locations[2].as_ref().unwrap();
+        assert_eq!(inner_call.line, Some(30));
+        assert_eq!(inner_call.column, Some(ColumnType::LeftEdge));
+    }
+
+    #[test]
+    fn stackframes_of_inlined_functions() {
+        let debug_info = inlined_debug_info();
+        let mut dump = CoreDump::new(BTreeMap::new(), vec![]);
+        let mut core = dump.core();
+        let registers = Registers::from_core(&mut core);
+
+        let frames = debug_info
+            .get_stackframe_info(&mut core, 0x11a, 0, registers, None)
+            .unwrap();
+
+        let summary: Vec<_> = frames
+            .iter()
+            .map(|frame| {
+                (
+                    frame.function_name.as_str(),
+                    frame.is_inlined,
+                    frame
+                        .source_location
+                        .as_ref()
+                        .and_then(|location| location.line),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("inner", true, Some(50)),
+                ("middle", true, Some(30)),
+                ("outer", false, Some(20)),
+            ]
+        );
+    }
+
+    #[test]
+    fn function_name_with_reference_cycle() {
+        let debug_info = inlined_debug_info();
+        let unit_info = debug_info
+            .get_next_unit_info(&mut debug_info.get_units())
+            .unwrap();
+
+        let mut entries = unit_info.unit.entries();
+        let mut unnamed_functions = 0;
+
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            if entry.tag() == gimli::DW_TAG_subprogram
+                && entry.attr(gimli::DW_AT_name).unwrap().is_none()
+            {
+                assert_eq!(unit_info.get_function_name(entry), None);
+                unnamed_functions += 1;
+            }
+        }
+
+        assert_eq!(unnamed_functions, 2);
+    }
+}
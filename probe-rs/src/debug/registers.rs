@@ -0,0 +1,241 @@
+use crate::core::{Architecture, Core, RegisterDescription, RegisterFile};
+
+/// The values of the core registers in a stack frame.
+///
+/// Registers are identified by their DWARF register number, which is the position
+/// of the register in the platform registers of the [`RegisterFile`] of the core.
+/// The program counter is stored separately if it has no DWARF register number,
+/// like on RISC-V.
+#[derive(Debug, Clone)]
+pub struct Registers {
+    register_file: &'static RegisterFile,
+    architecture: Architecture,
+    values: Vec<Option<u64>>,
+    program_counter: Option<u64>,
+}
+
+impl Registers {
+    /// Read the current values of all registers from the core.
+    pub fn from_core(core: &mut Core) -> Self {
+        let register_file = core.registers();
+
+        let values = register_file
+            .registers()
+            .map(|register| core.read_core_reg(register).ok())
+            .collect();
+
+        let program_counter = core.read_core_reg(register_file.program_counter()).ok();
+
+        Registers {
+            register_file,
+            architecture: core.architecture(),
+            values,
+            program_counter,
+        }
+    }
+
+    /// The number of registers with a DWARF register number.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if the core has no registers with a DWARF register number.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The value of the register with the given DWARF register number, if it is known.
+    pub fn get_value(&self, dwarf_register: u16) -> Option<u64> {
+        self.values.get(dwarf_register as usize).copied().flatten()
+    }
+
+    /// Set the value of the register with the given DWARF register number.
+    pub fn set_value(&mut self, dwarf_register: u16, value: Option<u64>) {
+        if let Some(register) = self.values.get_mut(dwarf_register as usize) {
+            *register = value;
+        }
+    }
+
+    /// The DWARF register number of the stack pointer.
+    pub fn stack_pointer_register(&self) -> Option<u16> {
+        self.dwarf_register(self.register_file.stack_pointer())
+    }
+
+    /// The DWARF register number of the register containing the return address.
+    pub fn return_address_register(&self) -> Option<u16> {
+        self.dwarf_register(self.register_file.return_address())
+    }
+
+    /// The DWARF register number of the program counter, if it has one.
+    pub fn program_counter_register(&self) -> Option<u16> {
+        self.dwarf_register(self.register_file.program_counter())
+    }
+
+    pub fn get_call_frame_address(&self) -> Option<u64> {
+        self.stack_pointer_register()
+            .and_then(|register| self.get_value(register))
+    }
+
+    pub fn set_call_frame_address(&mut self, value: Option<u64>) {
+        if let Some(register) = self.stack_pointer_register() {
+            self.set_value(register, value);
+        }
+    }
+
+    pub fn get_return_address(&self) -> Option<u64> {
+        self.return_address_register()
+            .and_then(|register| self.get_value(register))
+    }
+
+    pub fn get_frame_program_counter(&self) -> Option<u64> {
+        match self.program_counter_register() {
+            Some(register) => self.get_value(register),
+            None => self.program_counter,
+        }
+    }
+
+    pub fn set_frame_program_counter(&mut self, value: Option<u64>) {
+        match self.program_counter_register() {
+            Some(register) => self.set_value(register, value),
+            None => self.program_counter = value,
+        }
+    }
+
+    /// Returns true if the register with the given DWARF register number has to be preserved
+    /// by a called function, according to the calling convention of the architecture.
+    pub fn is_callee_saved(&self, dwarf_register: u16) -> bool {
+        match self.architecture {
+            // R9 is platform specific, and might be used as a scratch register.
+            Architecture::Arm => matches!(dwarf_register, 4..=8 | 10 | 11),
+            // s0 - s11
+            Architecture::Riscv => matches!(dwarf_register, 8 | 9 | 18..=27),
+        }
+    }
+
+    /// Iterate over the names and values of all registers, including the program counter.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, Option<u64>)> + '_ {
+        let register_file = self.register_file;
+
+        let program_counter = match self.program_counter_register() {
+            Some(_) => None,
+            None => Some((register_file.program_counter().name(), self.program_counter)),
+        };
+
+        register_file
+            .registers()
+            .map(move |register| register_name(register_file, register))
+            .zip(self.values.iter().copied())
+            .chain(program_counter)
+    }
+
+    fn dwarf_register(&self, register: &RegisterDescription) -> Option<u16> {
+        self.register_file
+            .registers()
+            .position(|r| r.address == register.address)
+            .map(|position| position as u16)
+    }
+}
+
+/// The name of a register, using the names of the special registers like the stack pointer.
+fn register_name(
+    register_file: &'static RegisterFile,
+    register: &'static RegisterDescription,
+) -> &'static str {
+    let special_registers = [
+        register_file.stack_pointer(),
+        register_file.return_address(),
+        register_file.program_counter(),
+    ];
+
+    special_registers
+        .iter()
+        .find(|special| special.address == register.address)
+        .map_or(register.name(), |special| special.name())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::architecture::{arm::core::ARM_REGISTER_FILE, riscv::register::RISCV_REGISTERS};
+
+    fn registers(register_file: &'static RegisterFile, architecture: Architecture) -> Registers {
+        Registers {
+            register_file,
+            architecture,
+            values: (0..register_file.registers().count() as u64)
+                .map(Some)
+                .collect(),
+            program_counter: Some(0x100),
+        }
+    }
+
+    #[test]
+    fn arm_dwarf_registers() {
+        let mut registers = registers(&ARM_REGISTER_FILE, Architecture::Arm);
+
+        assert_eq!(registers.len(), 16);
+        assert_eq!(registers.stack_pointer_register(), Some(13));
+        assert_eq!(registers.return_address_register(), Some(14));
+        assert_eq!(registers.program_counter_register(), Some(15));
+
+        assert_eq!(registers.get_call_frame_address(), Some(13));
+        assert_eq!(registers.get_return_address(), Some(14));
+        assert_eq!(registers.get_frame_program_counter(), Some(15));
+
+        // The program counter is one of the DWARF registers.
+        registers.set_frame_program_counter(Some(0x0800_0100));
+        assert_eq!(registers.get_value(15), Some(0x0800_0100));
+
+        registers.set_value(16, Some(1));
+        assert_eq!(registers.get_value(16), None);
+    }
+
+    #[test]
+    fn riscv_dwarf_registers() {
+        let mut registers = registers(&RISCV_REGISTERS, Architecture::Riscv);
+
+        assert_eq!(registers.len(), 32);
+        assert_eq!(registers.stack_pointer_register(), Some(2));
+        assert_eq!(registers.return_address_register(), Some(1));
+        assert_eq!(registers.program_counter_register(), None);
+
+        assert_eq!(registers.get_call_frame_address(), Some(2));
+        assert_eq!(registers.get_return_address(), Some(1));
+
+        // The program counter is stored separately.
+        assert_eq!(registers.get_frame_program_counter(), Some(0x100));
+        registers.set_frame_program_counter(Some(0x200));
+        assert_eq!(registers.get_frame_program_counter(), Some(0x200));
+        assert!(registers.values.iter().all(|value| *value != Some(0x200)));
+    }
+
+    #[test]
+    fn callee_saved_registers() {
+        let arm = registers(&ARM_REGISTER_FILE, Architecture::Arm);
+        let saved: Vec<u16> = (0..16).filter(|&r| arm.is_callee_saved(r)).collect();
+        assert_eq!(saved, [4, 5, 6, 7, 8, 10, 11]);
+
+        let riscv = registers(&RISCV_REGISTERS, Architecture::Riscv);
+        let saved: Vec<u16> = (0..32).filter(|&r| riscv.is_callee_saved(r)).collect();
+        assert_eq!(saved, [8, 9, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27]);
+    }
+
+    #[test]
+    fn register_names() {
+        let arm = registers(&ARM_REGISTER_FILE, Architecture::Arm);
+        let names: Vec<_> = arm.iter().map(|(name, _)| name).collect();
+
+        assert_eq!(names.len(), 16);
+        assert_eq!(names[..2], ["R0", "R1"]);
+        assert_eq!(names[13..], ["SP", "LR", "PC"]);
+
+        let riscv = registers(&RISCV_REGISTERS, Architecture::Riscv);
+        let registers: Vec<_> = riscv.iter().collect();
+
+        // The program counter is added after the DWARF registers.
+        assert_eq!(registers.len(), 33);
+        assert_eq!(registers[1], ("ra", Some(1)));
+        assert_eq!(registers[2], ("sp", Some(2)));
+        assert_eq!(registers[32], ("pc", Some(0x100)));
+    }
+}
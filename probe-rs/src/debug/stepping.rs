@@ -0,0 +1,599 @@
+//! Stepping through a program at the granularity of source lines.
+
+use std::ops::Range;
+use std::time::Duration;
+
+use super::{DebugError, DebugInfo, LineRow, StackFrame};
+use crate::{Core, CoreInformation, DebugProbeError};
+
+/// How long to wait for the core to return from a function call which is stepped over.
+const RETURN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The maximum number of instructions which are stepped for a single step through the source.
+///
+/// Code without a new source line to stop at, like an endless loop, is not stepped forever.
+const MAX_STEPS: usize = 10_000;
+
+/// The maximum number of halts in recursive calls which are skipped while running to a return address.
+const MAX_RECURSIVE_HALTS: usize = 1_000;
+
+/// The state of the core after a step.
+#[derive(Debug, Clone)]
+pub enum SteppingResult {
+    /// The core halted at the program counter.
+    Halted(CoreInformation),
+    /// The core is still running a function call which is stepped over, because the call
+    /// did not return in time.
+    ///
+    /// If a breakpoint was set at the return address for the step, it stays set as
+    /// `temporary_breakpoint`, so that the core halts when the call returns. It has to be
+    /// cleared once the core has halted.
+    Running { temporary_breakpoint: Option<u64> },
+}
+
+/// The granularity of a step through the program.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SteppingMode {
+    /// Step a single instruction.
+    StepInstruction,
+    /// Step to the next source line, stepping over function calls.
+    OverStatement,
+    /// Step to the next source line, stepping into function calls.
+    IntoStatement,
+    /// Run until the current function has returned to its caller.
+    OutOfStatement,
+}
+
+impl SteppingMode {
+    /// Step the halted core, and return the program counter it stopped at.
+    ///
+    /// Stepping to the next source line falls back to stepping a single instruction,
+    /// if there is no line information for the current program counter. If the core halts
+    /// at a breakpoint while running over a function call, it stays halted there.
+    ///
+    /// The core is left running if a function call which is stepped over does not return
+    /// in time, for example because it waits for an event.
+    pub fn step(
+        &self,
+        core: &mut Core<'_>,
+        debug_info: &DebugInfo,
+    ) -> Result<SteppingResult, DebugError> {
+        let mut remaining_steps = MAX_STEPS;
+
+        match self {
+            SteppingMode::StepInstruction => Ok(SteppingResult::Halted(core.step()?)),
+            SteppingMode::OverStatement => {
+                step_statement(core, debug_info, false, &mut remaining_steps)
+            }
+            SteppingMode::IntoStatement => {
+                step_statement(core, debug_info, true, &mut remaining_steps)
+            }
+            SteppingMode::OutOfStatement => step_out(core, debug_info, &mut remaining_steps),
+        }
+    }
+}
+
+/// Step until the first instruction of another source line is reached.
+///
+/// Function calls are stepped over by running to their return address, unless `into_calls`
+/// is set and there is line information for the called function. Inlined function calls
+/// are stepped over by stepping until their instructions have been left.
+///
+/// Stepping stops early when `remaining_steps` instructions have been stepped.
+fn step_statement(
+    core: &mut Core<'_>,
+    debug_info: &DebugInfo,
+    into_calls: bool,
+    remaining_steps: &mut usize,
+) -> Result<SteppingResult, DebugError> {
+    let pc = read_program_counter(core)?;
+
+    let (mut line_row, mut function_ranges) = match statement_context(debug_info, pc) {
+        Some(context) => context,
+        None => {
+            log::debug!(
+                "No line information for address {:#010x}, stepping a single instruction",
+                pc
+            );
+            return Ok(SteppingResult::Halted(core.step()?));
+        }
+    };
+
+    loop {
+        if *remaining_steps == 0 {
+            log::warn!(
+                "No new source line was reached after stepping {} instructions",
+                MAX_STEPS
+            );
+            return Ok(SteppingResult::Halted(CoreInformation {
+                pc: read_program_counter(core)?,
+            }));
+        }
+
+        *remaining_steps -= 1;
+
+        let mut pc = core.step()?.pc;
+
+        let function = &function_ranges[0];
+
+        // A jump to the start of the current function is a recursive call.
+        if !contains(function, pc) || Some(pc) == function.first().map(|range| range.start) {
+            let return_address = core.read_core_reg(core.registers().return_address())? & !1;
+
+            if contains(function, return_address) {
+                if into_calls && debug_info.get_line_row(pc).is_some() {
+                    return Ok(SteppingResult::Halted(CoreInformation { pc }));
+                }
+
+                let stack_pointer = read_stack_pointer(core)?;
+
+                match run_to_address(core, return_address, stack_pointer)? {
+                    SteppingResult::Halted(core_information)
+                        if core_information.pc == return_address => {}
+                    result => return Ok(result),
+                }
+
+                pc = return_address;
+            } else {
+                // The function has returned, finish the source line of the caller.
+                match statement_context(debug_info, pc) {
+                    Some((caller_line_row, caller_function_ranges)) => {
+                        line_row = caller_line_row;
+                        function_ranges = caller_function_ranges;
+                        continue;
+                    }
+                    None => return Ok(SteppingResult::Halted(CoreInformation { pc })),
+                }
+            }
+        }
+
+        let row = match debug_info.get_line_row(pc) {
+            Some(row) => row,
+            None => continue,
+        };
+
+        // Jumping back to the start of the current line, like in a loop, also starts a new line.
+        let is_new_line = !row.is_same_line(&line_row) || row.address == line_row.address;
+
+        if row.address == pc && row.is_stmt && row.line.is_some() && is_new_line {
+            let inline_depth = debug_info.get_function_ranges(pc).len();
+
+            if into_calls || inline_depth <= function_ranges.len() {
+                return Ok(SteppingResult::Halted(CoreInformation { pc }));
+            }
+        }
+    }
+}
+
+/// Run until the current function has returned to its caller.
+///
+/// The return address is taken from the unwound registers of the current function. Inlined
+/// functions are left by stepping over source lines until their instructions have been left.
+fn step_out(
+    core: &mut Core<'_>,
+    debug_info: &DebugInfo,
+    remaining_steps: &mut usize,
+) -> Result<SteppingResult, DebugError> {
+    let pc = read_program_counter(core)?;
+
+    let function_ranges = debug_info.get_function_ranges(pc);
+
+    if function_ranges.len() > 1 {
+        let inlined_function = &function_ranges[function_ranges.len() - 1];
+
+        loop {
+            let result = step_statement(core, debug_info, false, remaining_steps)?;
+
+            match &result {
+                SteppingResult::Halted(core_information)
+                    if contains(inlined_function, core_information.pc) && *remaining_steps > 0 => {}
+                _ => return Ok(result),
+            }
+        }
+    }
+
+    let (return_address, stack_pointer) = {
+        let mut frames = debug_info.try_unwind(core, pc);
+        let caller = frames.nth(1).ok_or(DebugError::NoReturnAddress)?;
+
+        (
+            return_address(&caller).ok_or(DebugError::NoReturnAddress)?,
+            caller.registers.get_call_frame_address().unwrap_or(0),
+        )
+    };
+
+    run_to_address(core, return_address, stack_pointer)
+}
+
+/// The address the current function returns to, given the stack frame of its caller.
+///
+/// The registers of the caller frame are unwound from the current function, so its return
+/// address register holds the return address. The caller of an exception handler is the
+/// interrupted code, which continues at the program counter restored from the exception frame.
+fn return_address(caller: &StackFrame) -> Option<u64> {
+    if caller.is_exception_boundary {
+        caller.registers.get_frame_program_counter()
+    } else {
+        // Remove the lowest bit, which indicates Thumb mode on ARM.
+        caller
+            .registers
+            .get_return_address()
+            .map(|address| address & !1)
+    }
+}
+
+/// Run the core until it halts at `address`, with a stack pointer of at least `stack_pointer`.
+///
+/// A temporary breakpoint is set at `address`. Halts at the breakpoint with a lower stack pointer
+/// belong to recursive calls of the function, and are skipped. If the core halts at another
+/// address, for example at a breakpoint set by the user, it stays halted there.
+///
+/// If the core does not halt in time, it is left running, and a temporary breakpoint stays set.
+fn run_to_address(
+    core: &mut Core<'_>,
+    address: u64,
+    stack_pointer: u64,
+) -> Result<SteppingResult, DebugError> {
+    let is_temporary_breakpoint = !core.has_hw_breakpoint(address);
+
+    if is_temporary_breakpoint {
+        core.set_hw_breakpoint(address)?;
+    }
+
+    let result = run_until_halted_at(core, address, stack_pointer);
+
+    // The breakpoint is still needed to halt the core, if it is running.
+    if is_temporary_breakpoint && !matches!(result, Ok(None)) {
+        core.clear_hw_breakpoint(address)?;
+    }
+
+    Ok(match result? {
+        Some(core_information) => SteppingResult::Halted(core_information),
+        None => SteppingResult::Running {
+            temporary_breakpoint: Some(address).filter(|_| is_temporary_breakpoint),
+        },
+    })
+}
+
+/// Run the core until it halts at `address` with a stack pointer of at least `stack_pointer`,
+/// or somewhere else. Returns `None` if the core is still running after the timeout.
+fn run_until_halted_at(
+    core: &mut Core<'_>,
+    address: u64,
+    stack_pointer: u64,
+) -> Result<Option<CoreInformation>, DebugError> {
+    for _ in 0..MAX_RECURSIVE_HALTS {
+        core.run()?;
+
+        match core.wait_for_core_halted(RETURN_TIMEOUT) {
+            Ok(()) => (),
+            Err(crate::Error::Probe(DebugProbeError::Timeout)) => {
+                log::warn!(
+                    "The core did not halt at address {:#010x} within {:?}, it is still running",
+                    address,
+                    RETURN_TIMEOUT
+                );
+                return Ok(None);
+            }
+            Err(error) => return Err(error.into()),
+        }
+
+        let pc = read_program_counter(core)?;
+
+        if pc != address || read_stack_pointer(core)? >= stack_pointer {
+            return Ok(Some(CoreInformation { pc }));
+        }
+    }
+
+    log::warn!(
+        "Stopped at address {:#010x} after skipping {} halts in recursive calls",
+        address,
+        MAX_RECURSIVE_HALTS
+    );
+
+    Ok(Some(CoreInformation {
+        pc: read_program_counter(core)?,
+    }))
+}
+
+/// The line program row and the function ranges at `address`, if both are known.
+fn statement_context(
+    debug_info: &DebugInfo,
+    address: u64,
+) -> Option<(LineRow, Vec<Vec<Range<u64>>>)> {
+    let line_row = debug_info.get_line_row(address)?;
+    let function_ranges = debug_info.get_function_ranges(address);
+
+    if function_ranges.is_empty() {
+        None
+    } else {
+        Some((line_row, function_ranges))
+    }
+}
+
+fn contains(ranges: &[Range<u64>], address: u64) -> bool {
+    ranges.iter().any(|range| range.contains(&address))
+}
+
+fn read_program_counter(core: &mut Core<'_>) -> Result<u64, DebugError> {
+    Ok(core.read_core_reg(core.registers().program_counter())?)
+}
+
+fn read_stack_pointer(core: &mut Core<'_>) -> Result<u64, DebugError> {
+    Ok(core.read_core_reg(core.registers().stack_pointer())?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        architecture::arm::core::{register, ARM_REGISTER_FILE},
+        config::{ArmCoreAccessOptions, CoreAccessOptions},
+        core::{CoreInterface, CoreState, RegisterFile},
+        debug::{test::inlined_debug_info, Registers},
+        memory::mock::{forward_memory_interface, MockMemory},
+        Architecture, CoreRegisterAddress, CoreStatus, Error, HaltReason, VectorCatchCondition,
+        WatchpointKind,
+    };
+    use std::{cell::Cell, rc::Rc};
+
+    /// A core which executes an instruction at every address, and counts its steps and runs.
+    struct TestCore {
+        pc: u64,
+        sp: u64,
+        lr: u64,
+        /// The program counter after stepping the instruction at the program counter.
+        next_pc: fn(u64) -> u64,
+        /// The program counter and stack pointer at which the core halts after running.
+        /// The core keeps running if this is not set.
+        halt_at: Option<(u64, u64)>,
+        steps: Rc<Cell<usize>>,
+        runs: Rc<Cell<usize>>,
+        /// The test core has no memory, every access returns an error.
+        memory: MockMemory,
+    }
+
+    impl TestCore {
+        fn new(pc: u64, next_pc: fn(u64) -> u64, halt_at: Option<(u64, u64)>) -> Self {
+            TestCore {
+                pc,
+                sp: 0x2000_1000,
+                lr: 0x0800_0101,
+                next_pc,
+                halt_at,
+                steps: Rc::default(),
+                runs: Rc::default(),
+                memory: MockMemory::new(),
+            }
+        }
+    }
+
+    fn unsupported() -> Error {
+        Error::Other(anyhow::anyhow!("Not supported by the test core"))
+    }
+
+    forward_memory_interface!(TestCore, memory);
+
+    impl CoreInterface for TestCore {
+        fn wait_for_core_halted(&mut self, _timeout: Duration) -> Result<(), Error> {
+            match self.halt_at {
+                Some(_) => Ok(()),
+                None => Err(Error::Probe(DebugProbeError::Timeout)),
+            }
+        }
+
+        fn core_halted(&mut self) -> Result<bool, Error> {
+            Ok(self.halt_at.is_some())
+        }
+
+        fn status(&mut self) -> Result<CoreStatus, Error> {
+            Ok(CoreStatus::Halted(HaltReason::Unknown))
+        }
+
+        fn halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+            Ok(CoreInformation { pc: self.pc })
+        }
+
+        fn run(&mut self) -> Result<(), Error> {
+            self.runs.set(self.runs.get() + 1);
+
+            if let Some((pc, sp)) = self.halt_at {
+                self.pc = pc;
+                self.sp = sp;
+            }
+
+            Ok(())
+        }
+
+        fn reset(&mut self) -> Result<(), Error> {
+            Err(unsupported())
+        }
+
+        fn reset_and_halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+            Err(unsupported())
+        }
+
+        fn step(&mut self) -> Result<CoreInformation, Error> {
+            self.steps.set(self.steps.get() + 1);
+            self.pc = (self.next_pc)(self.pc);
+
+            Ok(CoreInformation { pc: self.pc })
+        }
+
+        fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u64, Error> {
+            if address == register::PC.address {
+                Ok(self.pc)
+            } else if address == register::SP.address {
+                Ok(self.sp)
+            } else if address == register::LR.address {
+                Ok(self.lr)
+            } else {
+                Ok(0)
+            }
+        }
+
+        fn write_core_reg(
+            &mut self,
+            _address: CoreRegisterAddress,
+            _value: u64,
+        ) -> anyhow::Result<()> {
+            Err(anyhow::anyhow!("Not supported by the test core"))
+        }
+
+        fn get_available_breakpoint_units(&mut self) -> Result<u32, Error> {
+            Ok(4)
+        }
+
+        fn enable_breakpoints(&mut self, _state: bool) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn set_breakpoint(&mut self, _bp_unit_index: usize, _addr: u64) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn clear_breakpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn get_available_watchpoint_units(&mut self) -> Result<u32, Error> {
+            Ok(0)
+        }
+
+        fn set_watchpoint(
+            &mut self,
+            _unit_index: usize,
+            _address: u64,
+            _size: u32,
+            _kind: WatchpointKind,
+        ) -> Result<(), Error> {
+            Err(unsupported())
+        }
+
+        fn clear_watchpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+            Err(unsupported())
+        }
+
+        fn enable_vector_catch(&mut self, _condition: VectorCatchCondition) -> Result<(), Error> {
+            Err(unsupported())
+        }
+
+        fn disable_vector_catch(&mut self, _condition: VectorCatchCondition) -> Result<(), Error> {
+            Err(unsupported())
+        }
+
+        fn registers(&self) -> &'static RegisterFile {
+            &ARM_REGISTER_FILE
+        }
+
+        fn hw_breakpoints_enabled(&self) -> bool {
+            true
+        }
+
+        fn architecture(&self) -> Architecture {
+            Architecture::Arm
+        }
+    }
+
+    fn core_state() -> CoreState {
+        CoreState::new(0, CoreAccessOptions::Arm(ArmCoreAccessOptions { ap: 0 }))
+    }
+
+    fn halted_pc(result: SteppingResult) -> u64 {
+        match result {
+            SteppingResult::Halted(core_information) => core_information.pc,
+            running => panic!("Expected the core to be halted, but it is {:?}", running),
+        }
+    }
+
+    #[test]
+    fn step_over_endless_loop() {
+        let debug_info = inlined_debug_info();
+        let test_core = TestCore::new(0x104, |pc| pc, None);
+        let steps = test_core.steps.clone();
+        let mut state = core_state();
+        let mut core = Core::new(test_core, &mut state);
+
+        let result = SteppingMode::OverStatement
+            .step(&mut core, &debug_info)
+            .unwrap();
+
+        assert_eq!(halted_pc(result), 0x104);
+        assert_eq!(steps.get(), MAX_STEPS);
+    }
+
+    #[test]
+    fn step_over_statement() {
+        let debug_info = inlined_debug_info();
+
+        let step = |stepping_mode: SteppingMode| {
+            let mut state = core_state();
+            let mut core = Core::new(TestCore::new(0x100, |pc| pc + 2, None), &mut state);
+
+            halted_pc(stepping_mode.step(&mut core, &debug_info).unwrap())
+        };
+
+        // The lines of the inlined functions at 0x110..0x130 are stepped over,
+        // and there is no other line before the end of the function.
+        assert_eq!(step(SteppingMode::OverStatement), 0x140);
+        assert_eq!(step(SteppingMode::IntoStatement), 0x110);
+    }
+
+    #[test]
+    fn run_to_address_of_blocking_call() {
+        let test_core = TestCore::new(0x120, |pc| pc, None);
+        let mut state = core_state();
+        let mut core = Core::new(test_core, &mut state);
+
+        let result = run_to_address(&mut core, 0x0800_0100, 0x2000_1000).unwrap();
+
+        match result {
+            SteppingResult::Running {
+                temporary_breakpoint,
+            } => assert_eq!(temporary_breakpoint, Some(0x0800_0100)),
+            halted => panic!("Expected the core to be running, but it is {:?}", halted),
+        }
+
+        // The breakpoint is left set to halt the core when the call returns.
+        assert!(core.has_hw_breakpoint(0x0800_0100));
+    }
+
+    #[test]
+    fn run_to_address_in_recursive_call() {
+        // Every halt at the address is in a recursive call with a lower stack pointer.
+        let test_core = TestCore::new(0x120, |pc| pc, Some((0x0800_0100, 0x2000_0F00)));
+        let runs = test_core.runs.clone();
+        let mut state = core_state();
+        let mut core = Core::new(test_core, &mut state);
+
+        let result = run_to_address(&mut core, 0x0800_0100, 0x2000_1000).unwrap();
+
+        assert_eq!(halted_pc(result), 0x0800_0100);
+        assert_eq!(runs.get(), MAX_RECURSIVE_HALTS);
+        assert!(!core.has_hw_breakpoint(0x0800_0100));
+    }
+
+    #[test]
+    fn return_address_of_caller() {
+        let mut state = core_state();
+        let mut core = Core::new(TestCore::new(0x0800_0200, |pc| pc, None), &mut state);
+
+        let mut caller = StackFrame {
+            id: 1,
+            function_name: "caller".to_owned(),
+            source_location: None,
+            registers: Registers::from_core(&mut core),
+            pc: 0x0800_0200,
+            variables: vec![],
+            is_exception_boundary: false,
+            is_inlined: false,
+        };
+
+        // The Thumb bit of the unwound LR is removed.
+        assert_eq!(return_address(&caller), Some(0x0800_0100));
+
+        // Interrupted code continues at its program counter.
+        caller.is_exception_boundary = true;
+        assert_eq!(return_address(&caller), Some(0x0800_0200));
+    }
+}
@@ -0,0 +1,260 @@
+//! Functions and statics from the symbol table of an ELF file.
+
+use std::ops::Range;
+
+use object::{Object, ObjectSymbol};
+
+/// The kind of a symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// The symbol is a function.
+    Function,
+    /// The symbol is a static variable.
+    Data,
+}
+
+/// A function or static variable from the symbol table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// The demangled name of the symbol, like `my_crate::module::func`.
+    ///
+    /// The hash of mangled Rust names is not part of the name.
+    pub name: String,
+    /// The address of the symbol. For functions, the Thumb bit is not set.
+    pub address: u64,
+    /// The size of the symbol in bytes, which is zero if it is unknown.
+    pub size: u64,
+    pub kind: SymbolKind,
+}
+
+impl Symbol {
+    /// The address range of the symbol.
+    pub fn range(&self) -> Range<u64> {
+        self.address..self.address + self.size
+    }
+
+    /// Returns true if `name` is the name of the symbol, or the trailing part of its path.
+    fn has_name(&self, name: &str) -> bool {
+        match self.name.strip_suffix(name) {
+            Some("") => true,
+            Some(prefix) => prefix.ends_with("::"),
+            None => false,
+        }
+    }
+}
+
+/// The functions and static variables of an ELF file, sorted by address.
+#[derive(Debug, Default)]
+pub(crate) struct SymbolTable {
+    symbols: Vec<Symbol>,
+}
+
+impl SymbolTable {
+    pub(crate) fn from_object(object: &object::File) -> Self {
+        let mut symbols: Vec<Symbol> = object
+            .symbols()
+            .filter(|symbol| symbol.is_definition())
+            .filter_map(|symbol| {
+                let kind = match symbol.kind() {
+                    object::SymbolKind::Text => SymbolKind::Function,
+                    object::SymbolKind::Data => SymbolKind::Data,
+                    _ => return None,
+                };
+
+                let name = symbol.name().ok()?;
+
+                let address = match kind {
+                    SymbolKind::Function => symbol.address() & !1,
+                    SymbolKind::Data => symbol.address(),
+                };
+
+                Some(Symbol {
+                    name: demangle(name).unwrap_or_else(|| name.to_owned()),
+                    address,
+                    size: symbol.size(),
+                    kind,
+                })
+            })
+            .collect();
+
+        symbols.sort_by_key(|symbol| symbol.address);
+
+        SymbolTable { symbols }
+    }
+
+    /// Find a symbol of the given kind by its name.
+    ///
+    /// An exact match of the name is preferred over a match of the trailing part of the path.
+    pub(crate) fn find_by_name(&self, name: &str, kind: SymbolKind) -> Option<&Symbol> {
+        let mut symbols = self.symbols.iter().filter(|symbol| symbol.kind == kind);
+
+        symbols
+            .clone()
+            .find(|symbol| symbol.name == name)
+            .or_else(|| symbols.find(|symbol| symbol.has_name(name)))
+    }
+
+    /// Find the symbol containing `address`, and return it with the offset of `address` into it.
+    ///
+    /// If the size of the nearest symbol before `address` is unknown, it is assumed to contain `address`.
+    pub(crate) fn find_by_address(&self, address: u64) -> Option<(&Symbol, u64)> {
+        self.symbols
+            .iter()
+            .rev()
+            .find(|symbol| symbol.address <= address)
+            .filter(|symbol| symbol.size == 0 || address < symbol.address + symbol.size)
+            .map(|symbol| (symbol, address - symbol.address))
+    }
+}
+
+/// Demangle a Rust symbol name in the legacy mangling scheme, and remove its hash.
+///
+/// Returns `None` if `name` is not a mangled Rust name.
+fn demangle(name: &str) -> Option<String> {
+    // On some platforms, an underscore is added in front of every symbol.
+    let mut rest = name
+        .strip_prefix("_ZN")
+        .or_else(|| name.strip_prefix("__ZN"))?;
+
+    let mut path = Vec::new();
+
+    while !rest.starts_with('E') {
+        let length_end = rest.find(|c: char| !c.is_ascii_digit())?;
+        let length: usize = rest[..length_end].parse().ok()?;
+
+        let element = rest.get(length_end..length_end + length)?;
+        rest = &rest[length_end + length..];
+
+        path.push(element);
+    }
+
+    if let Some(hash) = path.last() {
+        if hash.len() == 17
+            && hash.starts_with('h')
+            && hash[1..].chars().all(|c| c.is_ascii_hexdigit())
+        {
+            path.pop();
+        }
+    }
+
+    let path: Option<Vec<String>> = path.iter().map(|element| unescape(element)).collect();
+
+    Some(path?.join("::"))
+}
+
+/// Replace the escape sequences in an element of a mangled path.
+fn unescape(element: &str) -> Option<String> {
+    // Elements which would start with `$` are prefixed with an underscore.
+    let mut rest = element
+        .strip_prefix("_$")
+        .map_or(element, |_| &element[1..]);
+    let mut unescaped = String::with_capacity(rest.len());
+
+    while !rest.is_empty() {
+        if let Some(after_dots) = rest.strip_prefix("..") {
+            unescaped.push_str("::");
+            rest = after_dots;
+        } else if let Some(escape) = rest.strip_prefix('$') {
+            let end = escape.find('$')?;
+
+            let c = match &escape[..end] {
+                "SP" => '@',
+                "BP" => '*',
+                "RF" => '&',
+                "LT" => '<',
+                "GT" => '>',
+                "LP" => '(',
+                "RP" => ')',
+                "C" => ',',
+                code => {
+                    let code = code.strip_prefix('u')?;
+                    std::char::from_u32(u32::from_str_radix(code, 16).ok()?)?
+                }
+            };
+
+            unescaped.push(c);
+            rest = &escape[end + 1..];
+        } else {
+            let end = rest
+                .char_indices()
+                .skip(1)
+                .find(|(_, c)| *c == '$' || *c == '.')
+                .map_or(rest.len(), |(end, _)| end);
+
+            unescaped.push_str(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn demangle_legacy_names() {
+        assert_eq!(
+            demangle("_ZN8my_crate6module4func17h0123456789abcdefE").as_deref(),
+            Some("my_crate::module::func")
+        );
+        assert_eq!(
+            demangle("_ZN66_$LT$alloc..vec..Vec$LT$T$GT$$u20$as$u20$core..ops..drop..Drop$GT$4drop17h0123456789abcdefE")
+                .as_deref(),
+            Some("<alloc::vec::Vec<T> as core::ops::drop::Drop>::drop")
+        );
+        assert_eq!(demangle("main"), None);
+        assert_eq!(demangle("_ZN3foo"), None);
+    }
+
+    #[test]
+    fn find_symbols() {
+        let symbol = |name: &str, address, size, kind| Symbol {
+            name: name.to_owned(),
+            address,
+            size,
+            kind,
+        };
+
+        let table = SymbolTable {
+            symbols: vec![
+                symbol("main", 0x100, 0x10, SymbolKind::Function),
+                symbol("app::run", 0x110, 0x20, SymbolKind::Function),
+                symbol("run", 0x130, 0x8, SymbolKind::Function),
+                symbol("app::COUNTER", 0x2000_0000, 4, SymbolKind::Data),
+            ],
+        };
+
+        assert_eq!(
+            table
+                .find_by_name("run", SymbolKind::Function)
+                .unwrap()
+                .address,
+            0x130
+        );
+        assert_eq!(
+            table
+                .find_by_name("app::run", SymbolKind::Function)
+                .unwrap()
+                .address,
+            0x110
+        );
+        assert!(table.find_by_name("un", SymbolKind::Function).is_none());
+        assert!(table
+            .find_by_name("COUNTER", SymbolKind::Function)
+            .is_none());
+        assert_eq!(
+            table
+                .find_by_name("COUNTER", SymbolKind::Data)
+                .unwrap()
+                .range(),
+            0x2000_0000..0x2000_0004
+        );
+
+        let (found, offset) = table.find_by_address(0x11a).unwrap();
+        assert_eq!((found.name.as_str(), offset), ("app::run", 0xa));
+        assert!(table.find_by_address(0x138).is_none());
+        assert!(table.find_by_address(0x50).is_none());
+    }
+}
@@ -0,0 +1,286 @@
+//! Writer for 32 bit little-endian ELF files containing memory contents.
+//!
+//! This is used to store flash readbacks and core dumps. The files consist of the
+//! ELF header, the program headers, the notes, the contents of the loadable segments,
+//! and optionally a section header table with one section per segment.
+
+use object::{
+    elf::{
+        FileHeader32, Ident, NoteHeader32, ProgramHeader32, SectionHeader32, ELFCLASS32,
+        ELFDATA2LSB, ELFMAG, ELFOSABI_NONE, EV_CURRENT, PT_LOAD, PT_NOTE, SHF_ALLOC, SHF_EXECINSTR,
+        SHT_PROGBITS, SHT_STRTAB,
+    },
+    endian::{LittleEndian, U16, U32},
+};
+use std::{
+    io::{self, Write},
+    mem::size_of,
+};
+
+/// A note, which is stored in the single `PT_NOTE` segment of the file.
+struct Note<'data> {
+    name: &'data str,
+    note_type: u32,
+    desc: &'data [u8],
+}
+
+impl Note<'_> {
+    /// The size of the note in the file, including the header and padding.
+    fn size(&self) -> usize {
+        size_of::<NoteHeader32<LittleEndian>>()
+            + align4(self.name.len() + 1)
+            + align4(self.desc.len())
+    }
+}
+
+/// A loadable segment.
+struct Segment<'data> {
+    address: u32,
+    data: &'data [u8],
+    flags: u32,
+}
+
+/// Collects the notes and segments of an ELF file, and writes them.
+pub(crate) struct ElfWriter<'data> {
+    file_type: u16,
+    machine: u16,
+    notes: Vec<Note<'data>>,
+    segments: Vec<Segment<'data>>,
+    section_prefix: Option<&'data str>,
+}
+
+impl<'data> ElfWriter<'data> {
+    /// Create a writer for a file of type `file_type`, e.g. `ET_CORE`, for the machine `machine`.
+    pub(crate) fn new(file_type: u16, machine: u16) -> Self {
+        ElfWriter {
+            file_type,
+            machine,
+            notes: Vec::new(),
+            segments: Vec::new(),
+            section_prefix: None,
+        }
+    }
+
+    /// Add a note with the owner `name`.
+    pub(crate) fn add_note(&mut self, name: &'data str, note_type: u32, desc: &'data [u8]) {
+        self.notes.push(Note {
+            name,
+            note_type,
+            desc,
+        });
+    }
+
+    /// Add a loadable segment, which contains `data` at `address`.
+    pub(crate) fn add_segment(&mut self, address: u32, data: &'data [u8], flags: u32) {
+        self.segments.push(Segment {
+            address,
+            data,
+            flags,
+        });
+    }
+
+    /// Add a section for every segment, named `prefix` followed by the index of the segment.
+    pub(crate) fn add_sections(&mut self, prefix: &'data str) {
+        self.section_prefix = Some(prefix);
+    }
+
+    /// Write the ELF file.
+    pub(crate) fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        let endian = LittleEndian;
+
+        let header_size = size_of::<FileHeader32<LittleEndian>>();
+        let program_header_size = size_of::<ProgramHeader32<LittleEndian>>();
+        let section_header_size = size_of::<SectionHeader32<LittleEndian>>();
+
+        let program_header_count = usize::from(!self.notes.is_empty()) + self.segments.len();
+        let notes_offset = header_size + program_header_count * program_header_size;
+        let notes_size: usize = self.notes.iter().map(Note::size).sum();
+        let data_offset = notes_offset + notes_size;
+        let data_size: usize = self.segments.iter().map(|segment| segment.data.len()).sum();
+
+        // The section names are stored in the section header string table,
+        // which follows the data of the segments.
+        let mut string_table = vec![0u8];
+        let mut name_offsets = Vec::with_capacity(self.segments.len());
+
+        if let Some(prefix) = self.section_prefix {
+            for index in 0..self.segments.len() {
+                name_offsets.push(string_table.len() as u32);
+                string_table.extend_from_slice(format!("{}{}\0", prefix, index).as_bytes());
+            }
+        }
+
+        let string_table_name_offset = string_table.len() as u32;
+        string_table.extend_from_slice(b".shstrtab\0");
+
+        let string_table_offset = data_offset + data_size;
+        let section_header_offset = align4(string_table_offset + string_table.len());
+
+        // A null section, one section per segment and the string table.
+        let section_count = if self.section_prefix.is_some() {
+            self.segments.len() + 2
+        } else {
+            0
+        };
+
+        let header = FileHeader32 {
+            e_ident: Ident {
+                magic: ELFMAG,
+                class: ELFCLASS32,
+                data: ELFDATA2LSB,
+                version: EV_CURRENT,
+                os_abi: ELFOSABI_NONE,
+                abi_version: 0,
+                padding: [0; 7],
+            },
+            e_type: U16::new(endian, self.file_type),
+            e_machine: U16::new(endian, self.machine),
+            e_version: U32::new(endian, u32::from(EV_CURRENT)),
+            e_entry: U32::new(endian, 0),
+            e_phoff: U32::new(endian, header_size as u32),
+            e_shoff: U32::new(
+                endian,
+                if section_count > 0 {
+                    section_header_offset as u32
+                } else {
+                    0
+                },
+            ),
+            e_flags: U32::new(endian, 0),
+            e_ehsize: U16::new(endian, header_size as u16),
+            e_phentsize: U16::new(endian, program_header_size as u16),
+            e_phnum: U16::new(endian, program_header_count as u16),
+            e_shentsize: U16::new(
+                endian,
+                if section_count > 0 {
+                    section_header_size as u16
+                } else {
+                    0
+                },
+            ),
+            e_shnum: U16::new(endian, section_count as u16),
+            e_shstrndx: U16::new(endian, section_count.saturating_sub(1) as u16),
+        };
+
+        writer.write_all(object::bytes_of(&header))?;
+
+        if !self.notes.is_empty() {
+            let notes_header = ProgramHeader32 {
+                p_type: U32::new(endian, PT_NOTE),
+                p_offset: U32::new(endian, notes_offset as u32),
+                p_vaddr: U32::new(endian, 0),
+                p_paddr: U32::new(endian, 0),
+                p_filesz: U32::new(endian, notes_size as u32),
+                p_memsz: U32::new(endian, 0),
+                p_flags: U32::new(endian, 0),
+                p_align: U32::new(endian, 4),
+            };
+
+            writer.write_all(object::bytes_of(&notes_header))?;
+        }
+
+        let mut offset = data_offset;
+        for segment in &self.segments {
+            let size = segment.data.len() as u32;
+
+            let program_header = ProgramHeader32 {
+                p_type: U32::new(endian, PT_LOAD),
+                p_offset: U32::new(endian, offset as u32),
+                p_vaddr: U32::new(endian, segment.address),
+                p_paddr: U32::new(endian, segment.address),
+                p_filesz: U32::new(endian, size),
+                p_memsz: U32::new(endian, size),
+                p_flags: U32::new(endian, segment.flags),
+                p_align: U32::new(endian, 1),
+            };
+
+            writer.write_all(object::bytes_of(&program_header))?;
+
+            offset += segment.data.len();
+        }
+
+        for note in &self.notes {
+            let header = NoteHeader32 {
+                n_namesz: U32::new(endian, note.name.len() as u32 + 1),
+                n_descsz: U32::new(endian, note.desc.len() as u32),
+                n_type: U32::new(endian, note.note_type),
+            };
+
+            writer.write_all(object::bytes_of(&header))?;
+            write_padded(writer, note.name.as_bytes(), align4(note.name.len() + 1))?;
+            write_padded(writer, note.desc, align4(note.desc.len()))?;
+        }
+
+        for segment in &self.segments {
+            writer.write_all(segment.data)?;
+        }
+
+        if section_count == 0 {
+            return Ok(());
+        }
+
+        write_padded(
+            writer,
+            &string_table,
+            section_header_offset - string_table_offset,
+        )?;
+
+        let section_header =
+            |name: u32, kind: u32, flags: u32, address: u32, offset: usize, size: usize| {
+                SectionHeader32 {
+                    sh_name: U32::new(endian, name),
+                    sh_type: U32::new(endian, kind),
+                    sh_flags: U32::new(endian, flags),
+                    sh_addr: U32::new(endian, address),
+                    sh_offset: U32::new(endian, offset as u32),
+                    sh_size: U32::new(endian, size as u32),
+                    sh_link: U32::new(endian, 0),
+                    sh_info: U32::new(endian, 0),
+                    sh_addralign: U32::new(endian, 1),
+                    sh_entsize: U32::new(endian, 0),
+                }
+            };
+
+        writer.write_all(object::bytes_of(&section_header(0, 0, 0, 0, 0, 0)))?;
+
+        let mut offset = data_offset;
+        for (segment, name) in self.segments.iter().zip(name_offsets) {
+            let header = section_header(
+                name,
+                SHT_PROGBITS,
+                SHF_ALLOC | SHF_EXECINSTR,
+                segment.address,
+                offset,
+                segment.data.len(),
+            );
+
+            writer.write_all(object::bytes_of(&header))?;
+
+            offset += segment.data.len();
+        }
+
+        let header = section_header(
+            string_table_name_offset,
+            SHT_STRTAB,
+            0,
+            0,
+            string_table_offset,
+            string_table.len(),
+        );
+
+        writer.write_all(object::bytes_of(&header))?;
+
+        Ok(())
+    }
+}
+
+/// Round `size` up to a multiple of four.
+fn align4(size: usize) -> usize {
+    (size + 3) & !3
+}
+
+/// Write `data`, followed by zeros up to a total size of `size` bytes.
+fn write_padded(writer: &mut impl Write, data: &[u8], size: usize) -> io::Result<()> {
+    writer.write_all(data)?;
+    writer.write_all(&vec![0; size - data.len()])
+}
@@ -15,8 +15,16 @@ pub enum Error {
     CoreNotFound(usize),
     #[error("Unable to load specification for chip")]
     ChipNotFound(#[from] RegistryError),
+    #[error("The address {0:#x} is outside of the 32 bit address space of the target")]
+    AddressOutOfRange(u64),
+    #[error("The value {0:#x} does not fit into a 32 bit register")]
+    RegisterValueOutOfRange(u64),
     #[error("This feature requires one of the following architectures: {0:?}")]
     ArchitectureRequired(&'static [&'static str]),
+    #[error("The core uses a different architecture than the debug interface of the target")]
+    CoreArchitectureMismatch,
+    #[error("The target is locked and can only be unlocked by erasing it")]
+    TargetLocked,
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
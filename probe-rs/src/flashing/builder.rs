@@ -59,6 +59,11 @@ pub struct FlashSector {
 }
 
 impl FlashSector {
+    /// Creates a new flash sector.
+    pub(super) fn new(address: u32, size: u32) -> Self {
+        Self { address, size }
+    }
+
     /// Returns the start address of the sector.
     pub fn address(&self) -> u32 {
         self.address
@@ -387,6 +392,7 @@ mod tests {
         let region = NvmRegion {
             is_boot_memory: true,
             range: 0..1 << 16,
+            flash_algorithm: None,
         };
 
         (region, flash_algorithm)
@@ -411,6 +417,7 @@ mod tests {
         let region = NvmRegion {
             is_boot_memory: true,
             range: 0..1 << 16,
+            flash_algorithm: None,
         };
 
         (region, flash_algorithm)
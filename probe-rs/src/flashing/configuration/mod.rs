@@ -0,0 +1,264 @@
+//! Reading and writing of configuration regions, like the option bytes of
+//! STM32 chips, the UICR of nRF chips or the user row of SAMD chips.
+//!
+//! The configuration region of a target is described in its target description,
+//! together with the named fields of the region. Writing the region requires
+//! a chip specific sequence, which is selected by the kind of the region.
+
+mod nrf52;
+mod samd;
+pub(crate) mod stm32f4;
+
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use crate::config::{ConfigurationField, ConfigurationKind, ConfigurationRegion};
+use crate::{Error, MemoryInterface, Session};
+
+/// Time to wait for the core to halt before writing the configuration.
+const HALT_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Errors which can occur when accessing the configuration region of a target.
+#[derive(Debug, Error)]
+pub enum ConfigurationError {
+    /// The target description does not contain a configuration region.
+    #[error("The target {0} does not have a configuration region.")]
+    NotSupported(String),
+    /// The configuration region does not contain a field with the given name.
+    #[error("The configuration region does not have a field named '{0}'.")]
+    UnknownField(String),
+    /// The value is too large for the field.
+    #[error("The value {value:#x} does not fit into the {width} bit wide field '{field}'.")]
+    ValueTooLarge {
+        /// The name of the field.
+        field: String,
+        /// The value which should have been written.
+        value: u32,
+        /// The width of the field in bits.
+        width: u8,
+    },
+    /// The size of the configuration region is not a non-zero multiple of four bytes.
+    #[error("The configuration region has an invalid size of {0} bytes.")]
+    InvalidSize(u32),
+    /// The configuration would lock the target permanently, which was not allowed.
+    #[error(
+        "The configuration would lock the target permanently, this has to be allowed explicitly."
+    )]
+    PermanentLock,
+    /// The configuration region is still locked after trying to unlock it.
+    #[error("Failed to unlock the configuration region.")]
+    UnlockFailed,
+    /// The chip did not finish writing the configuration in time.
+    #[error("Timeout while writing the configuration region.")]
+    Timeout,
+    /// Accessing the memory of the target failed.
+    #[error("Something during the interaction with the core went wrong")]
+    Core(#[from] Error),
+}
+
+/// The contents of the configuration region of a target.
+///
+/// The contents are read with [`read_configuration`], and can be
+/// modified and written back with [`write_configuration`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Configuration {
+    region: ConfigurationRegion,
+    words: Vec<u32>,
+}
+
+impl Configuration {
+    /// The description of the configuration region.
+    pub fn region(&self) -> &ConfigurationRegion {
+        &self.region
+    }
+
+    /// The raw contents of the configuration region.
+    pub fn words(&self) -> &[u32] {
+        &self.words
+    }
+
+    /// Iterate over all fields of the region, together with their values.
+    pub fn fields(&self) -> impl Iterator<Item = (&ConfigurationField, u32)> + '_ {
+        self.region
+            .fields
+            .iter()
+            .map(move |field| (field, self.value(field)))
+    }
+
+    /// Get the value of the field with the given name.
+    ///
+    /// The name is compared case-insensitively.
+    pub fn field(&self, name: &str) -> Result<u32, ConfigurationError> {
+        let field = self.find_field(name)?;
+
+        Ok(self.value(field))
+    }
+
+    /// Set the value of the field with the given name.
+    ///
+    /// The change only affects the target after the
+    /// configuration is written with [`write_configuration`].
+    pub fn set_field(&mut self, name: &str, value: u32) -> Result<(), ConfigurationError> {
+        let field = self.find_field(name)?.clone();
+        let mask = field.mask();
+
+        let fits = u64::from(value)
+            .checked_shr(field.width.into())
+            .unwrap_or(0)
+            == 0;
+
+        if !fits || mask == 0 {
+            return Err(ConfigurationError::ValueTooLarge {
+                field: field.name,
+                value,
+                width: field.width,
+            });
+        }
+
+        let word = &mut self.words[field.offset as usize / 4];
+        *word = (*word & !mask) | ((value << field.bit) & mask);
+
+        Ok(())
+    }
+
+    fn find_field(&self, name: &str) -> Result<&ConfigurationField, ConfigurationError> {
+        self.region
+            .fields
+            .iter()
+            .find(|field| field.name.eq_ignore_ascii_case(name))
+            .filter(|field| (field.offset as usize / 4) < self.words.len())
+            .ok_or_else(|| ConfigurationError::UnknownField(name.to_owned()))
+    }
+
+    fn value(&self, field: &ConfigurationField) -> u32 {
+        self.words
+            .get(field.offset as usize / 4)
+            .map_or(0, |word| (word & field.mask()) >> field.bit.min(31))
+    }
+}
+
+/// Read the configuration region of the target.
+pub fn read_configuration(session: &mut Session) -> Result<Configuration, ConfigurationError> {
+    let region = session
+        .target()
+        .configuration
+        .clone()
+        .ok_or_else(|| ConfigurationError::NotSupported(session.target().name.clone()))?;
+
+    if region.size == 0 || region.size % 4 != 0 {
+        return Err(ConfigurationError::InvalidSize(region.size));
+    }
+
+    let mut words = vec![0; region.size as usize / 4];
+    session
+        .core(0)?
+        .read_32(region.address.into(), &mut words)?;
+
+    Ok(Configuration { region, words })
+}
+
+/// Write the configuration region of the target.
+///
+/// The core is halted while the configuration is written. Most chips only
+/// apply the new configuration after a reset, which is not done by this function.
+///
+/// Configurations which lock the target permanently, like read protection level 2
+/// of STM32 chips, are only written if `allow_permanent_lock` is true.
+pub fn write_configuration(
+    session: &mut Session,
+    configuration: &Configuration,
+    allow_permanent_lock: bool,
+) -> Result<(), ConfigurationError> {
+    let current = read_configuration(session)?;
+
+    if current.words == configuration.words {
+        log::info!("The configuration is unchanged, skipping the write");
+        return Ok(());
+    }
+
+    let mut core = session.core(0)?;
+    core.halt(HALT_TIMEOUT)?;
+
+    let region = &configuration.region;
+
+    match region.kind {
+        ConfigurationKind::Stm32f4OptionBytes => {
+            stm32f4::write(&mut core, &configuration.words, allow_permanent_lock)
+        }
+        ConfigurationKind::Nrf52Uicr => {
+            nrf52::write(&mut core, region, &current.words, &configuration.words)
+        }
+        ConfigurationKind::SamdUserRow => samd::write(&mut core, region, &configuration.words),
+    }
+}
+
+/// Poll `done` until it returns true, or the timeout has elapsed.
+fn wait_until(
+    timeout: Duration,
+    mut done: impl FnMut() -> Result<bool, Error>,
+) -> Result<(), ConfigurationError> {
+    let start = Instant::now();
+
+    while !done()? {
+        if start.elapsed() > timeout {
+            return Err(ConfigurationError::Timeout);
+        }
+
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn configuration() -> Configuration {
+        let field = |name: &str, offset, bit, width| ConfigurationField {
+            name: name.to_owned(),
+            description: String::new(),
+            offset,
+            bit,
+            width,
+        };
+
+        Configuration {
+            region: ConfigurationRegion {
+                kind: ConfigurationKind::Stm32f4OptionBytes,
+                address: 0x4002_3C14,
+                size: 8,
+                fields: vec![
+                    field("BOR_LEV", 0, 2, 2),
+                    field("RDP", 0, 8, 8),
+                    field("WORD", 4, 0, 32),
+                ],
+            },
+            words: vec![0x0FFF_AAED, 0x1234_5678],
+        }
+    }
+
+    #[test]
+    fn read_fields() {
+        let configuration = configuration();
+
+        assert_eq!(configuration.field("BOR_LEV").unwrap(), 3);
+        assert_eq!(configuration.field("rdp").unwrap(), 0xAA);
+        assert_eq!(configuration.field("WORD").unwrap(), 0x1234_5678);
+        assert!(configuration.field("nWRP").is_err());
+    }
+
+    #[test]
+    fn modify_fields() {
+        let mut configuration = configuration();
+
+        configuration.set_field("BOR_LEV", 1).unwrap();
+        configuration.set_field("WORD", 0xFFFF_FFFF).unwrap();
+
+        assert_eq!(configuration.words(), &[0x0FFF_AAE5, 0xFFFF_FFFF]);
+
+        assert!(configuration.set_field("BOR_LEV", 4).is_err());
+        assert_eq!(configuration.words()[0], 0x0FFF_AAE5);
+    }
+}
@@ -0,0 +1,69 @@
+//! UICR of the nRF52 series.
+//!
+//! The UICR is written through the NVMC, like the flash. Bits can only be
+//! cleared by writing, so setting a bit requires erasing the whole UICR.
+
+use std::time::Duration;
+
+use super::{wait_until, ConfigurationError};
+use crate::config::ConfigurationRegion;
+use crate::{Core, MemoryInterface};
+
+const NVMC_BASE: u64 = 0x4001_E000;
+const NVMC_READY: u64 = NVMC_BASE + 0x400;
+const NVMC_CONFIG: u64 = NVMC_BASE + 0x504;
+const NVMC_ERASEUICR: u64 = NVMC_BASE + 0x514;
+
+const CONFIG_READ: u32 = 0;
+const CONFIG_WRITE: u32 = 1;
+const CONFIG_ERASE: u32 = 2;
+
+const ERASED: u32 = 0xFFFF_FFFF;
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+pub(super) fn write(
+    core: &mut Core,
+    region: &ConfigurationRegion,
+    current: &[u32],
+    words: &[u32],
+) -> Result<(), ConfigurationError> {
+    let needs_erase = current
+        .iter()
+        .zip(words)
+        .any(|(current, new)| !current & new != 0);
+
+    if needs_erase {
+        log::info!("Erasing the UICR to set bits which were cleared");
+
+        core.write_word_32(NVMC_CONFIG, CONFIG_ERASE)?;
+        core.write_word_32(NVMC_ERASEUICR, 1)?;
+        wait_ready(core)?;
+    }
+
+    core.write_word_32(NVMC_CONFIG, CONFIG_WRITE)?;
+    wait_ready(core)?;
+
+    for (index, (current, new)) in current.iter().zip(words).enumerate() {
+        let unchanged = if needs_erase {
+            *new == ERASED
+        } else {
+            current == new
+        };
+
+        if !unchanged {
+            let address = u64::from(region.address) + 4 * index as u64;
+
+            core.write_word_32(address, *new)?;
+            wait_ready(core)?;
+        }
+    }
+
+    core.write_word_32(NVMC_CONFIG, CONFIG_READ)?;
+
+    Ok(())
+}
+
+fn wait_ready(core: &mut Core) -> Result<(), ConfigurationError> {
+    wait_until(TIMEOUT, || Ok(core.read_word_32(NVMC_READY)? & 1 != 0))
+}
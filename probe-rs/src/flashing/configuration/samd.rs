@@ -0,0 +1,76 @@
+//! User row of the SAMD series.
+//!
+//! The user row is written through the NVMCTRL. It has to be erased as a
+//! whole row before writing, so the complete row is written again.
+
+use std::time::Duration;
+
+use super::{wait_until, ConfigurationError};
+use crate::config::ConfigurationRegion;
+use crate::{Core, MemoryInterface};
+
+const NVMCTRL_BASE: u64 = 0x4100_4000;
+const NVMCTRL_CTRLA: u64 = NVMCTRL_BASE;
+const NVMCTRL_CTRLB: u64 = NVMCTRL_BASE + 0x04;
+const NVMCTRL_INTFLAG: u64 = NVMCTRL_BASE + 0x14;
+const NVMCTRL_ADDR: u64 = NVMCTRL_BASE + 0x1C;
+
+/// Key which has to be written together with a command.
+const CMDEX_KEY: u32 = 0xA5 << 8;
+/// Erase the auxiliary row.
+const CMD_EAR: u32 = 0x05;
+/// Write the page buffer to an auxiliary page.
+const CMD_WAP: u32 = 0x06;
+/// Clear the page buffer.
+const CMD_PBC: u32 = 0x44;
+
+const INTFLAG_READY: u32 = 1 << 0;
+/// Manual write, pages are only written with an explicit command.
+const CTRLB_MANW: u32 = 1 << 7;
+
+const ROW_SIZE: usize = 256;
+const PAGE_SIZE: usize = 64;
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+pub(super) fn write(
+    core: &mut Core,
+    region: &ConfigurationRegion,
+    words: &[u32],
+) -> Result<(), ConfigurationError> {
+    let address = u64::from(region.address);
+
+    // Erasing the row also erases the data after the configuration,
+    // so the rest of the row is written back unchanged.
+    let mut row = vec![0; ROW_SIZE / 4];
+    core.read_32(address, &mut row)?;
+    let count = words.len().min(row.len());
+    row[..count].copy_from_slice(&words[..count]);
+
+    let ctrlb = core.read_word_32(NVMCTRL_CTRLB)?;
+    core.write_word_32(NVMCTRL_CTRLB, ctrlb | CTRLB_MANW)?;
+
+    command(core, CMD_EAR, address)?;
+
+    for (index, page) in row.chunks(PAGE_SIZE / 4).enumerate() {
+        let page_address = address + (index * PAGE_SIZE) as u64;
+
+        command(core, CMD_PBC, page_address)?;
+        core.write_32(page_address, page)?;
+        command(core, CMD_WAP, page_address)?;
+    }
+
+    core.write_word_32(NVMCTRL_CTRLB, ctrlb)?;
+
+    Ok(())
+}
+
+fn command(core: &mut Core, command: u32, address: u64) -> Result<(), ConfigurationError> {
+    // The address register contains the address of a 16-bit word.
+    core.write_word_32(NVMCTRL_ADDR, (address >> 1) as u32)?;
+    core.write_word_32(NVMCTRL_CTRLA, CMDEX_KEY | command)?;
+
+    wait_until(TIMEOUT, || {
+        Ok(core.read_word_32(NVMCTRL_INTFLAG)? & INTFLAG_READY != 0)
+    })
+}
@@ -0,0 +1,199 @@
+//! Option bytes of the STM32F2, STM32F4 and STM32F7 series.
+//!
+//! The option bytes are read and written through the `FLASH_OPTCR` register,
+//! which has to be unlocked first. They are written as the configuration region
+//! of the target, and by the [`Stm32f4`] debug sequence to remove the read protection.
+//!
+//! [`Stm32f4`]: crate::architecture::arm::sequences::stm32::Stm32f4
+
+use std::time::Duration;
+
+use super::{wait_until, ConfigurationError};
+use crate::{Core, Error, Memory, MemoryInterface};
+
+const FLASH_BASE: u32 = 0x4002_3C00;
+const FLASH_OPTKEYR: u32 = FLASH_BASE + 0x08;
+const FLASH_SR: u32 = FLASH_BASE + 0x0C;
+const FLASH_OPTCR: u32 = FLASH_BASE + 0x14;
+
+const OPTKEY1: u32 = 0x0819_2A3B;
+const OPTKEY2: u32 = 0x4C5D_6E7F;
+
+const SR_BSY: u32 = 1 << 16;
+const OPTCR_OPTLOCK: u32 = 1 << 0;
+const OPTCR_OPTSTRT: u32 = 1 << 1;
+const OPTCR_RDP_SHIFT: u32 = 8;
+const OPTCR_RDP_MASK: u32 = 0xff << OPTCR_RDP_SHIFT;
+
+/// RDP value for level 0, where the flash is not read protected.
+pub(crate) const RDP_LEVEL_0: u8 = 0xAA;
+/// RDP value for level 2, which disables debugging permanently.
+const RDP_LEVEL_2: u8 = 0xCC;
+
+/// Changing the read protection erases the whole flash, which takes a while.
+const TIMEOUT: Duration = Duration::from_secs(40);
+
+/// Access to the registers of the flash interface.
+///
+/// The registers are accessed through a core when the configuration region is written,
+/// and directly through the access port by the debug sequence, before a core is attached.
+pub(crate) trait FlashRegisters {
+    fn read_register(&mut self, address: u32) -> Result<u32, Error>;
+    fn write_register(&mut self, address: u32, value: u32) -> Result<(), Error>;
+}
+
+impl FlashRegisters for Core<'_> {
+    fn read_register(&mut self, address: u32) -> Result<u32, Error> {
+        self.read_word_32(address.into())
+    }
+
+    fn write_register(&mut self, address: u32, value: u32) -> Result<(), Error> {
+        self.write_word_32(address.into(), value)
+    }
+}
+
+impl FlashRegisters for Memory<'_> {
+    fn read_register(&mut self, address: u32) -> Result<u32, Error> {
+        self.read_word_32(address)
+    }
+
+    fn write_register(&mut self, address: u32, value: u32) -> Result<(), Error> {
+        self.write_word_32(address, value)
+    }
+}
+
+/// Read the `FLASH_OPTCR` register, which contains the option bytes.
+pub(crate) fn read_optcr(flash: &mut impl FlashRegisters) -> Result<u32, Error> {
+    flash.read_register(FLASH_OPTCR)
+}
+
+/// The RDP byte of `optcr`, which selects the read protection level.
+pub(crate) fn read_protection(optcr: u32) -> u8 {
+    ((optcr & OPTCR_RDP_MASK) >> OPTCR_RDP_SHIFT) as u8
+}
+
+/// Replace the RDP byte of `optcr` with `rdp`.
+pub(crate) fn with_read_protection(optcr: u32, rdp: u8) -> u32 {
+    (optcr & !OPTCR_RDP_MASK) | (u32::from(rdp) << OPTCR_RDP_SHIFT)
+}
+
+/// Program the option bytes in `optcr`, and lock the option bytes again afterwards.
+pub(crate) fn write_optcr(
+    flash: &mut impl FlashRegisters,
+    optcr: u32,
+) -> Result<(), ConfigurationError> {
+    // The lowest bits control the programming, and are not part of the option bytes.
+    let optcr = optcr & !(OPTCR_OPTLOCK | OPTCR_OPTSTRT);
+
+    // Writing the keys while the register is unlocked is a wrong sequence,
+    // which locks the register until the next reset.
+    if flash.read_register(FLASH_OPTCR)? & OPTCR_OPTLOCK != 0 {
+        flash.write_register(FLASH_OPTKEYR, OPTKEY1)?;
+        flash.write_register(FLASH_OPTKEYR, OPTKEY2)?;
+
+        if flash.read_register(FLASH_OPTCR)? & OPTCR_OPTLOCK != 0 {
+            return Err(ConfigurationError::UnlockFailed);
+        }
+    }
+
+    log::debug!("Writing {:#010x} to FLASH_OPTCR", optcr);
+
+    flash.write_register(FLASH_OPTCR, optcr)?;
+    flash.write_register(FLASH_OPTCR, optcr | OPTCR_OPTSTRT)?;
+
+    wait_until(TIMEOUT, || Ok(flash.read_register(FLASH_SR)? & SR_BSY == 0))?;
+
+    flash.write_register(FLASH_OPTCR, optcr | OPTCR_OPTLOCK)?;
+
+    Ok(())
+}
+
+/// Write the configuration region, which consists of the `FLASH_OPTCR` register.
+///
+/// Read protection level 2 can not be removed again, so it is only set if
+/// `allow_permanent_lock` is true.
+pub(super) fn write(
+    core: &mut Core,
+    words: &[u32],
+    allow_permanent_lock: bool,
+) -> Result<(), ConfigurationError> {
+    let optcr = words[0];
+
+    if read_protection(optcr) == RDP_LEVEL_2 && !allow_permanent_lock {
+        return Err(ConfigurationError::PermanentLock);
+    }
+
+    write_optcr(core, optcr)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Flash interface registers, which record all writes.
+    #[derive(Default)]
+    struct TestFlash {
+        registers: HashMap<u32, u32>,
+        writes: Vec<(u32, u32)>,
+    }
+
+    impl FlashRegisters for TestFlash {
+        fn read_register(&mut self, address: u32) -> Result<u32, Error> {
+            Ok(self.registers.get(&address).copied().unwrap_or(0))
+        }
+
+        fn write_register(&mut self, address: u32, value: u32) -> Result<(), Error> {
+            self.writes.push((address, value));
+
+            match (address, value) {
+                // The second key unlocks the register.
+                (FLASH_OPTKEYR, OPTKEY2) => {
+                    *self.registers.entry(FLASH_OPTCR).or_default() &= !OPTCR_OPTLOCK
+                }
+                (FLASH_OPTKEYR, _) => (),
+                _ => {
+                    self.registers.insert(address, value);
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn unlock_only_when_locked() {
+        let mut flash = TestFlash::default();
+        flash.registers.insert(FLASH_OPTCR, 0x0FFF_AAED);
+
+        write_optcr(&mut flash, 0x0FFF_AAE4).unwrap();
+
+        assert_eq!(
+            flash.writes,
+            [
+                (FLASH_OPTKEYR, OPTKEY1),
+                (FLASH_OPTKEYR, OPTKEY2),
+                (FLASH_OPTCR, 0x0FFF_AAE4),
+                (FLASH_OPTCR, 0x0FFF_AAE6),
+                (FLASH_OPTCR, 0x0FFF_AAE5),
+            ]
+        );
+
+        // Writing the keys again would lock the register until the next reset.
+        let mut flash = TestFlash::default();
+        flash.registers.insert(FLASH_OPTCR, 0x0FFF_AAEC);
+
+        write_optcr(&mut flash, 0x0FFF_AAE4).unwrap();
+
+        assert!(flash
+            .writes
+            .iter()
+            .all(|(address, _)| *address != FLASH_OPTKEYR));
+    }
+
+    #[test]
+    fn read_protection_byte() {
+        assert_eq!(read_protection(0x0FFF_AAED), RDP_LEVEL_0);
+        assert_eq!(with_read_protection(0x0FFF_55ED, RDP_LEVEL_0), 0x0FFF_AAED);
+    }
+}
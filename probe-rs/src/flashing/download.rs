@@ -19,6 +19,13 @@ pub struct BinOptions {
     pub skip: u32,
 }
 
+/// Extended options for flashing a UF2 file.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Uf2Options {
+    /// If this is set, only blocks with this family ID, or without a family ID, are flashed.
+    pub family_id: Option<u32>,
+}
+
 /// A finite list of all the available binary formats probe-rs understands.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum Format {
@@ -30,6 +37,11 @@ pub enum Format {
     Hex,
     /// Marks a file in the [ELF](https://en.wikipedia.org/wiki/Executable_and_Linkable_Format) format.
     Elf,
+    /// Marks a file in the [UF2](https://github.com/microsoft/uf2) format.
+    /// [Uf2Options] can be used to only flash the blocks for a specific chip family.
+    Uf2(Uf2Options),
+    /// Marks a file in the [Motorola S-record](https://en.wikipedia.org/wiki/SREC_(file_format)) format.
+    Srec,
 }
 
 impl FromStr for Format {
@@ -43,6 +55,8 @@ impl FromStr for Format {
             })),
             "hex" | "ihex" | "intelhex" => Ok(Format::Hex),
             "elf" => Ok(Format::Elf),
+            "uf2" => Ok(Format::Uf2(Uf2Options::default())),
+            "srec" | "s19" | "s28" | "s37" | "mot" => Ok(Format::Srec),
             _ => Err(format!("Format '{}' is unknown.", s)),
         }
     }
@@ -76,6 +90,17 @@ pub enum FileDownloadError {
     /// This is most likely because of a bad linker script.
     #[error("No loadable ELF sections were found.")]
     NoLoadableSegments,
+    /// The UF2 file is invalid.
+    #[error("Invalid UF2 file: {0}.")]
+    Uf2(&'static str),
+    /// A record of the S-record file is invalid.
+    #[error("Invalid S-record in line {line}: {reason}.")]
+    Srec {
+        /// The line of the invalid record, starting at 1.
+        line: usize,
+        /// Why the record is invalid.
+        reason: &'static str,
+    },
 }
 
 /// Options for downloading a file onto a target chip.
@@ -99,6 +124,21 @@ pub struct DownloadOptions<'progress> {
     /// If the chip was pre-erased with external erasers, this flag can set to true to skip erasing
     /// It may be useful for mass production.
     pub skip_erase: bool,
+    /// After programming, check that the flash contents match the written data.
+    /// A mismatch is reported as a [FlashError::VerifyFailed] error.
+    pub verify: bool,
+    /// Compare the data with the flash contents first, and only erase and program
+    /// the sectors which differ. Unless `keep_unwritten_bytes` is set, the bytes of a
+    /// sector that are not part of the data have to be erased for it to be skipped.
+    pub skip_unchanged_sectors: bool,
+    /// Prepare the core to execute an image linked for RAM after loading it.
+    ///
+    /// On ARM, the stack pointer and program counter are taken from the vector table at the
+    /// start of the image, and VTOR is pointed to it. On RISC-V, the program counter is set
+    /// to the entry point of the image. The core is left halted unless `run_after_boot` is set.
+    pub boot_from_ram: bool,
+    /// Start the core after preparing it with `boot_from_ram`.
+    pub run_after_boot: bool,
 }
 
 /// Downloads a file of given `format` at `path` to the flash of the target given in `session`.
@@ -136,6 +176,8 @@ pub fn download_file_with_options<P: AsRef<Path>>(
         Format::Bin(options) => loader.load_bin_data(&mut file, options),
         Format::Elf => loader.load_elf_data(&mut file),
         Format::Hex => loader.load_hex_data(&mut file),
+        Format::Uf2(options) => loader.load_uf2_data(&mut file, options),
+        Format::Srec => loader.load_srec_data(&mut file),
     }?;
 
     loader
@@ -262,7 +304,7 @@ pub(super) fn extract_from_elf<'data>(
 mod tests {
     use std::str::FromStr;
 
-    use super::{BinOptions, Format};
+    use super::{BinOptions, Format, Uf2Options};
 
     #[test]
     fn parse_format() {
@@ -305,6 +347,12 @@ mod tests {
         );
         assert_eq!(Format::from_str("Elf"), Ok(Format::Elf));
         assert_eq!(Format::from_str("elf"), Ok(Format::Elf));
+        assert_eq!(
+            Format::from_str("UF2"),
+            Ok(Format::Uf2(Uf2Options { family_id: None }))
+        );
+        assert_eq!(Format::from_str("srec"), Ok(Format::Srec));
+        assert_eq!(Format::from_str("S19"), Ok(Format::Srec));
         assert_eq!(
             Format::from_str("elfbin"),
             Err("Format 'elfbin' is unknown.".to_string())
@@ -0,0 +1,160 @@
+//! Erasing of the flash, without programming any data.
+
+use std::{collections::HashSet, ops::Range};
+
+use super::{FlashAlgorithm, FlashError, FlashLoader, FlashProgress, FlashSector, Flasher};
+use crate::config::{MemoryRange, MemoryRegion, NvmRegion};
+use crate::session::Session;
+
+/// Erase the entire flash of the target.
+///
+/// For every flash region, a chip erase is done if the flash algorithm supports it.
+/// Otherwise all sectors of the region are erased one by one.
+/// Regions without a flash algorithm are skipped.
+///
+/// A chip erase erases all regions of a flash algorithm, so it is only done once per algorithm.
+pub fn erase_all(session: &mut Session) -> Result<(), FlashError> {
+    let progress = FlashProgress::new(|_| {});
+
+    let mut chip_erased_algorithms = HashSet::new();
+
+    for region in nvm_regions(session) {
+        let flash_algorithm = match FlashLoader::build_flash_algorithm(&region, session.target()) {
+            Ok(flash_algorithm) => flash_algorithm,
+            Err(FlashError::NoFlashLoaderAlgorithmAttached) => {
+                log::warn!(
+                    "Skipping region {:#010x}..{:#010x}, which has no flash algorithm.",
+                    region.range.start,
+                    region.range.end
+                );
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+
+        let sectors = sectors_in_range(&flash_algorithm, &region.range);
+        let chip_erase_supported = flash_algorithm.pc_erase_all.is_some();
+
+        if chip_erase_supported && !chip_erased_algorithms.insert(flash_algorithm.name.clone()) {
+            log::debug!(
+                "Region {:#010x}..{:#010x} was erased by the chip erase of {}.",
+                region.range.start,
+                region.range.end,
+                flash_algorithm.name
+            );
+            continue;
+        }
+
+        let mut flasher = Flasher::new(session, flash_algorithm, region);
+
+        if chip_erase_supported {
+            flasher.chip_erase(&sectors, &progress)?;
+        } else {
+            flasher.sector_erase(&sectors, &progress)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Erase all sectors of the flash which intersect `range`.
+///
+/// Sectors which are only partially covered by `range` are erased completely.
+/// The entire range has to be contained in the flash of the target.
+pub fn erase_sectors(session: &mut Session, range: Range<u32>) -> Result<(), FlashError> {
+    let regions = nvm_regions(session)
+        .filter(|region| region.range.intersects_range(&range))
+        .collect::<Vec<_>>();
+
+    let covered: u32 = regions
+        .iter()
+        .map(|region| region.range.end.min(range.end) - region.range.start.max(range.start))
+        .sum();
+
+    if range.is_empty() || covered != range.end - range.start {
+        return Err(FlashError::NoSuitableNvm {
+            start: range.start as u64,
+            end: range.end as u64,
+            description_source: session.target().source().clone(),
+        });
+    }
+
+    let progress = FlashProgress::new(|_| {});
+
+    for region in regions {
+        let flash_algorithm = FlashLoader::build_flash_algorithm(&region, session.target())?;
+
+        let range = range.start.max(region.range.start)..range.end.min(region.range.end);
+        let sectors = sectors_in_range(&flash_algorithm, &range);
+
+        let mut flasher = Flasher::new(session, flash_algorithm, region);
+        flasher.sector_erase(&sectors, &progress)?;
+    }
+
+    Ok(())
+}
+
+/// All flash regions of the target.
+fn nvm_regions(session: &Session) -> impl Iterator<Item = NvmRegion> {
+    session
+        .target()
+        .memory_map
+        .iter()
+        .filter_map(|region| match region {
+            MemoryRegion::Nvm(region) => Some(region.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// The sectors of the flash algorithm which intersect `range`.
+fn sectors_in_range(flash_algorithm: &FlashAlgorithm, range: &Range<u32>) -> Vec<FlashSector> {
+    flash_algorithm
+        .iter_sectors()
+        .filter(|sector| {
+            (sector.base_address..sector.base_address + sector.size).intersects_range(range)
+        })
+        .map(|sector| FlashSector::new(sector.base_address, sector.size))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{FlashProperties, SectorDescription};
+
+    #[test]
+    fn partially_covered_sectors() {
+        let flash_algorithm = FlashAlgorithm {
+            flash_properties: FlashProperties {
+                sectors: vec![
+                    SectorDescription {
+                        size: 0x4000,
+                        address: 0x0,
+                    },
+                    SectorDescription {
+                        size: 0x1_0000,
+                        address: 0x1_0000,
+                    },
+                ],
+                address_range: 0x800_0000..0x804_0000,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let sectors = sectors_in_range(&flash_algorithm, &(0x800_3000..0x801_0001));
+
+        assert_eq!(
+            sectors,
+            vec![
+                FlashSector::new(0x800_0000, 0x4000),
+                FlashSector::new(0x800_4000, 0x4000),
+                FlashSector::new(0x800_8000, 0x4000),
+                FlashSector::new(0x800_C000, 0x4000),
+                FlashSector::new(0x801_0000, 0x1_0000),
+            ]
+        );
+    }
+}
@@ -14,8 +14,8 @@ pub enum FlashError {
         "No flash memory contains the entire requested memory range {start:#010x}..{end:#10x}."
     )]
     NoSuitableNvm {
-        start: u32,
-        end: u32,
+        start: u64,
+        end: u64,
         description_source: TargetDescriptionSource,
     },
     #[error("Failed to erase the whole chip.")]
@@ -32,6 +32,10 @@ pub enum FlashError {
         page_address: u32,
         source: Box<dyn std::error::Error + 'static + Send + Sync>,
     },
+    #[error(
+        "The flash contents do not match the written data, starting at address {address:#010x}."
+    )]
+    VerifyFailed { address: u32 },
     #[error("The initialization of the flash algorithm failed.")]
     Init(#[source] Box<dyn std::error::Error + 'static + Send + Sync>),
     #[error("The uninitialization of the flash algorithm failed.")]
@@ -55,6 +59,12 @@ pub enum FlashError {
     #[error("Trying to write flash, but no suitable flash loader algorithm is linked to the given target information.")]
     NoFlashLoaderAlgorithmAttached,
 
+    #[error("The flash algorithm '{name}' specified for {region:?} is not part of the target.")]
+    UnknownFlashAlgorithm { name: String, region: NvmRegion },
+
+    #[error("The image does not contain any data for RAM, so it can not be booted from RAM.")]
+    NoRamImage,
+
     // TODO: 1 Add source of target definition
     #[error("No RAM defined for chip.")]
     NoRamDefined { chip: String },
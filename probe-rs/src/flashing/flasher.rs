@@ -1,5 +1,6 @@
 use super::{
-    FlashAlgorithm, FlashBuilder, FlashError, FlashFill, FlashLayout, FlashPage, FlashProgress,
+    DownloadOptions, FlashAlgorithm, FlashBuilder, FlashError, FlashFill, FlashLayout, FlashPage,
+    FlashProgress, FlashSector,
 };
 use crate::config::{MemoryRange, NvmRegion};
 use crate::memory::MemoryInterface;
@@ -114,11 +115,11 @@ impl<'session> Flasher<'session> {
             algo.load_address
         );
 
-        core.write_32(algo.load_address, algo.instructions.as_slice())
+        core.write_32(algo.load_address.into(), algo.instructions.as_slice())
             .map_err(FlashError::Core)?;
 
         let mut data = vec![0; algo.instructions.len()];
-        core.read_32(algo.load_address, &mut data)
+        core.read_32(algo.load_address.into(), &mut data)
             .map_err(FlashError::Core)?;
 
         for (offset, (original, read_back)) in algo.instructions.iter().zip(data.iter()).enumerate()
@@ -211,25 +212,35 @@ impl<'session> Flasher<'session> {
 
         let mut fb = FlashBuilder::new();
         fb.add_data(address, data)?;
-        self.program(&fb, do_chip_erase, true, true, false, progress)?;
+
+        let options = DownloadOptions {
+            keep_unwritten_bytes: true,
+            do_chip_erase,
+            ..Default::default()
+        };
+        self.program(&fb, &options, true, progress)?;
 
         Ok(())
     }
 
     /// Program the contents of given `FlashBuilder` to the flash.
     ///
-    /// If `restore_unwritten_bytes` is `true`, all bytes of a sector,
+    /// If `keep_unwritten_bytes` is set in the `options`, all bytes of a sector,
     /// that are not to be written during flashing will be read from the flash first
     /// and written again once the sector is erased.
+    ///
+    /// The `dry_run` option is ignored, all other `options` are applied as described
+    /// in [DownloadOptions].
     pub(super) fn program(
         &mut self,
         flash_builder: &FlashBuilder,
-        mut do_chip_erase: bool,
-        restore_unwritten_bytes: bool,
+        options: &DownloadOptions<'_>,
         enable_double_buffering: bool,
-        skip_erasing: bool,
         progress: &FlashProgress,
     ) -> Result<(), FlashError> {
+        let mut do_chip_erase = options.do_chip_erase;
+        let restore_unwritten_bytes = options.keep_unwritten_bytes;
+
         log::debug!("Starting program procedure.");
         // Convert the list of flash operations into flash sectors and pages.
         let mut flash_layout = flash_builder.build_sectors_and_pages(
@@ -238,8 +249,54 @@ impl<'session> Flasher<'session> {
             restore_unwritten_bytes,
         )?;
 
+        let mut unchanged_sectors = Vec::new();
+
+        if options.skip_unchanged_sectors {
+            if self.flash_algorithm.flash_properties.page_size > self.smallest_sector_size() {
+                log::warn!("Unchanged sectors can not be skipped, because the pages of the flash are larger than its sectors.");
+            } else {
+                unchanged_sectors =
+                    self.unchanged_sectors(flash_builder, &flash_layout, restore_unwritten_bytes)?;
+            }
+        }
+
+        if !unchanged_sectors.is_empty() {
+            log::info!("Skipping {} unchanged sectors.", unchanged_sectors.len());
+
+            // Only keep the data of the changed sectors.
+            let mut changed_data = FlashBuilder::new();
+            for sector in flash_layout.sectors() {
+                if unchanged_sectors.contains(sector) {
+                    continue;
+                }
+
+                let range = sector.address()..sector.address() + sector.size();
+                for (address, data) in flash_builder.data_in_range(&range) {
+                    changed_data.add_data(address, data)?;
+                }
+            }
+
+            flash_layout = changed_data.build_sectors_and_pages(
+                &self.region,
+                &self.flash_algorithm,
+                restore_unwritten_bytes,
+            )?;
+        }
+
         progress.initialized(flash_layout.clone());
 
+        for sector in &unchanged_sectors {
+            progress.sector_skipped(sector.address(), sector.size());
+        }
+
+        // A chip erase would also erase the unchanged sectors.
+        if !unchanged_sectors.is_empty() && do_chip_erase {
+            log::info!(
+                "Erasing single sectors instead of the whole chip, to keep the unchanged sectors."
+            );
+            do_chip_erase = false;
+        }
+
         // If the flash algo doesn't support erase all, disable chip erase.
         if self.flash_algorithm().pc_erase_all.is_none() {
             do_chip_erase = false;
@@ -278,12 +335,12 @@ impl<'session> Flasher<'session> {
         progress.finished_filling();
 
         // Skip erase if necessary
-        if !skip_erasing {
+        if !options.skip_erase {
             // Erase all necessary sectors
             if do_chip_erase {
-                self.chip_erase(&flash_layout, progress)?;
+                self.chip_erase(flash_layout.sectors(), progress)?;
             } else {
-                self.sector_erase(&flash_layout, progress)?;
+                self.sector_erase(flash_layout.sectors(), progress)?;
             }
         }
 
@@ -294,6 +351,10 @@ impl<'session> Flasher<'session> {
             self.program_simple(&flash_layout, progress)?;
         };
 
+        if options.verify {
+            self.verify(&flash_layout)?;
+        }
+
         Ok(())
     }
 
@@ -312,25 +373,77 @@ impl<'session> Flasher<'session> {
         self.run_verify(|active| {
             active
                 .core
-                .read_8(fill.address(), page_slice)
+                .read_8(fill.address().into(), page_slice)
                 .map_err(FlashError::Core)
         })
     }
 
+    /// Verifies that the flash contains the pages given in `flash_layout`.
+    ///
+    /// If the flash algorithm has a verify routine, it is used to compare the pages.
+    /// Otherwise the pages are read back from the flash.
+    fn verify(&mut self, flash_layout: &FlashLayout) -> Result<(), FlashError> {
+        log::debug!("Verifying the flash contents.");
+
+        self.run_verify(|active| {
+            for page in flash_layout.pages() {
+                if let Some(address) = active.find_mismatch(page.address(), page.data())? {
+                    return Err(FlashError::VerifyFailed { address });
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Compares the flash contents with the contents they would have after flashing,
+    /// and returns the sectors of `flash_layout` which already contain them.
+    fn unchanged_sectors(
+        &mut self,
+        flash_builder: &FlashBuilder,
+        flash_layout: &FlashLayout,
+        restore_unwritten_bytes: bool,
+    ) -> Result<Vec<FlashSector>, FlashError> {
+        log::debug!("Comparing the flash contents with the data.");
+
+        let erased_byte_value = self.flash_algorithm.flash_properties.erased_byte_value;
+
+        self.run_verify(|active| {
+            select_unchanged_sectors(
+                flash_builder,
+                flash_layout,
+                erased_byte_value,
+                restore_unwritten_bytes,
+                |address, data| active.find_mismatch(address, data),
+            )
+        })
+    }
+
+    /// The size of the smallest sector of the flash.
+    fn smallest_sector_size(&self) -> u32 {
+        self.flash_algorithm
+            .flash_properties
+            .sectors
+            .iter()
+            .map(|sector| sector.size)
+            .min()
+            .unwrap_or(0)
+    }
+
     /// Erase the entire flash of the chip.
     ///
     /// This takes the list of available sectors only for progress reporting reasons.
     /// It does not indeed erase single sectors but erases the entire flash.
-    fn chip_erase(
+    pub(super) fn chip_erase(
         &mut self,
-        flash_layout: &FlashLayout,
+        sectors: &[FlashSector],
         progress: &FlashProgress,
     ) -> Result<(), FlashError> {
         progress.started_erasing();
 
         let mut t = std::time::Instant::now();
         let result = self.run_erase(|active| active.erase_all());
-        for sector in flash_layout.sectors() {
+        for sector in sectors {
             progress.sector_erased(sector.size(), t.elapsed());
             t = std::time::Instant::now();
         }
@@ -375,17 +488,17 @@ impl<'session> Flasher<'session> {
         result
     }
 
-    /// Perform an erase of all sectors given in `flash_layout`.
-    fn sector_erase(
+    /// Perform an erase of all given sectors.
+    pub(super) fn sector_erase(
         &mut self,
-        flash_layout: &FlashLayout,
+        sectors: &[FlashSector],
         progress: &FlashProgress,
     ) -> Result<(), FlashError> {
         progress.started_erasing();
 
         let mut t = std::time::Instant::now();
         let result = self.run_erase(|active| {
-            for sector in flash_layout.sectors() {
+            for sector in sectors {
                 active
                     .erase_sector(sector.address())
                     .map_err(|e| FlashError::EraseFailed {
@@ -629,7 +742,8 @@ impl<'probe, O: Operation> ActiveFlasher<'probe, O> {
 
         for (description, value) in &registers {
             if let Some(v) = value {
-                self.core.write_core_reg(description.address, *v)?;
+                self.core
+                    .write_core_reg(description.address, u64::from(*v))?;
                 log::debug!(
                     "content of {} {:#x}: 0x{:08x} should be: 0x{:08x}",
                     description.name,
@@ -663,7 +777,7 @@ impl<'probe, O: Operation> ActiveFlasher<'probe, O> {
         self.core.wait_for_core_halted(timeout)?;
 
         let r = self.core.read_core_reg(regs.result_register(0).address)?;
-        Ok(r)
+        Ok(r as u32)
     }
 }
 
@@ -744,6 +858,170 @@ impl<'probe> ActiveFlasher<'probe, Erase> {
     }
 }
 
+impl<'p> ActiveFlasher<'p, Verify> {
+    /// Compares `bytes` with the flash contents at `address`.
+    ///
+    /// The verify routine of the flash algorithm is used if there is one,
+    /// otherwise the flash is read back.
+    ///
+    /// Returns the address of the first mismatch, if there is one.
+    pub(super) fn find_mismatch(
+        &mut self,
+        address: u32,
+        bytes: &[u8],
+    ) -> Result<Option<u32>, FlashError> {
+        let page_size = self.flash_algorithm.flash_properties.page_size;
+
+        // The verify routine gets the data through the page buffer,
+        // so the data is compared in chunks of one page.
+        find_mismatch_in_pages(address, bytes, page_size, |chunk_address, chunk| match self
+            .flash_algorithm
+            .pc_verify
+        {
+            Some(pc_verify) => self.verify_page(pc_verify, chunk_address, chunk),
+            None => self.compare_page(chunk_address, chunk),
+        })
+    }
+
+    /// Compares a page with the flash contents using the verify routine of the flash algorithm.
+    ///
+    /// Returns the address of the first mismatch, if there is one.
+    fn verify_page(
+        &mut self,
+        pc_verify: u32,
+        address: u32,
+        bytes: &[u8],
+    ) -> Result<Option<u32>, FlashError> {
+        // Transfer the bytes to RAM.
+        self.core
+            .write_8(self.flash_algorithm.begin_data.into(), bytes)
+            .map_err(FlashError::Core)?;
+
+        let end = address + bytes.len() as u32;
+
+        let result = self
+            .call_function_and_wait(
+                &Registers {
+                    pc: pc_verify,
+                    r0: Some(address),
+                    r1: Some(bytes.len() as u32),
+                    r2: Some(self.flash_algorithm.begin_data),
+                    r3: None,
+                },
+                false,
+                Duration::from_millis(
+                    self.flash_algorithm.flash_properties.program_page_timeout as u64,
+                ),
+            )
+            .map_err(FlashError::Core)?;
+
+        Ok(verify_result(end, result))
+    }
+
+    /// Compares a page with the flash contents by reading back the flash.
+    ///
+    /// Returns the address of the first mismatch, if there is one.
+    fn compare_page(&mut self, address: u32, bytes: &[u8]) -> Result<Option<u32>, FlashError> {
+        let mut flash_contents = vec![0; bytes.len()];
+
+        self.core
+            .read_8(address.into(), &mut flash_contents)
+            .map_err(FlashError::Core)?;
+
+        Ok(first_mismatch(address, &flash_contents, bytes))
+    }
+}
+
+/// Returns the sectors of `flash_layout` which already contain the contents they would have
+/// after flashing, according to `find_mismatch`, which compares data with the flash contents.
+///
+/// If `restore_unwritten_bytes` is `true`, the bytes without data in `flash_builder` keep
+/// their value, so only the data is compared. Otherwise the whole sector is compared, and
+/// the bytes without data have to be erased already.
+fn select_unchanged_sectors(
+    flash_builder: &FlashBuilder,
+    flash_layout: &FlashLayout,
+    erased_byte_value: u8,
+    restore_unwritten_bytes: bool,
+    mut find_mismatch: impl FnMut(u32, &[u8]) -> Result<Option<u32>, FlashError>,
+) -> Result<Vec<FlashSector>, FlashError> {
+    let mut unchanged_sectors = Vec::new();
+
+    'sectors: for sector in flash_layout.sectors() {
+        let range = sector.address()..sector.address() + sector.size();
+
+        if restore_unwritten_bytes {
+            for (address, data) in flash_builder.data_in_range(&range) {
+                if find_mismatch(address, data)?.is_some() {
+                    continue 'sectors;
+                }
+            }
+        } else {
+            let mut contents = vec![erased_byte_value; sector.size() as usize];
+
+            for page in flash_layout
+                .pages()
+                .iter()
+                .filter(|page| range.contains(&page.address()))
+            {
+                let offset = (page.address() - sector.address()) as usize;
+                contents[offset..offset + page.data().len()].copy_from_slice(page.data());
+            }
+
+            if find_mismatch(sector.address(), &contents)?.is_some() {
+                continue;
+            }
+        }
+
+        unchanged_sectors.push(sector.clone());
+    }
+
+    Ok(unchanged_sectors)
+}
+
+/// Compares `bytes` in chunks of `page_size` bytes using `compare_page`, which returns
+/// the address of the first mismatch in a chunk.
+///
+/// Returns the address of the first mismatch, if there is one.
+fn find_mismatch_in_pages(
+    address: u32,
+    bytes: &[u8],
+    page_size: u32,
+    mut compare_page: impl FnMut(u32, &[u8]) -> Result<Option<u32>, FlashError>,
+) -> Result<Option<u32>, FlashError> {
+    for (index, chunk) in bytes.chunks(page_size as usize).enumerate() {
+        let chunk_address = address + index as u32 * page_size;
+
+        if let Some(mismatch) = compare_page(chunk_address, chunk)? {
+            return Ok(Some(mismatch));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Interprets the return value of the verify routine of a flash algorithm, for a range ending at `end`.
+///
+/// The verify routine returns the end of the checked range on success,
+/// and the address of the first mismatch otherwise.
+fn verify_result(end: u32, result: u32) -> Option<u32> {
+    if result == end {
+        None
+    } else {
+        Some(result)
+    }
+}
+
+/// Returns the address of the first byte of `read_back`, which was read from `address`,
+/// that differs from `expected`.
+fn first_mismatch(address: u32, read_back: &[u8], expected: &[u8]) -> Option<u32> {
+    read_back
+        .iter()
+        .zip(expected)
+        .position(|(read_back, expected)| read_back != expected)
+        .map(|offset| address + offset as u32)
+}
+
 impl<'p> ActiveFlasher<'p, Program> {
     pub(super) fn program_page(&mut self, address: u32, bytes: &[u8]) -> Result<(), FlashError> {
         let t1 = std::time::Instant::now();
@@ -756,7 +1034,7 @@ impl<'p> ActiveFlasher<'p, Program> {
 
         // Transfer the bytes to RAM.
         self.core
-            .write_8(self.flash_algorithm.begin_data, bytes)
+            .write_8(self.flash_algorithm.begin_data.into(), bytes)
             .map_err(FlashError::Core)?;
 
         let result = self
@@ -843,9 +1121,135 @@ impl<'p> ActiveFlasher<'p, Program> {
 
         // Transfer the buffer bytes to RAM.
         self.core
-            .write_8(algo.page_buffers[buffer_number], bytes)
+            .write_8(algo.page_buffers[buffer_number].into(), bytes)
             .map_err(FlashError::Core)?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{FlashProperties, SectorDescription};
+
+    #[test]
+    fn compare_page_contents() {
+        let expected = [1, 2, 3, 4];
+
+        assert_eq!(first_mismatch(0x1000, &[1, 2, 3, 4], &expected), None);
+        assert_eq!(
+            first_mismatch(0x1000, &[1, 2, 0xFF, 0xFF], &expected),
+            Some(0x1002)
+        );
+
+        assert_eq!(verify_result(0x1400, 0x1400), None);
+        assert_eq!(verify_result(0x1400, 0x1004), Some(0x1004));
+    }
+
+    #[test]
+    fn mismatch_address_across_pages() {
+        let flash: Vec<u8> = (0..=255).collect();
+
+        let mut data = flash.clone();
+        data[0x9A] = 0;
+
+        let mut compared_pages = Vec::new();
+
+        let mismatch = find_mismatch_in_pages(0x0800_0000, &data, 0x40, |address, chunk| {
+            compared_pages.push(address);
+
+            let offset = (address - 0x0800_0000) as usize;
+            Ok(first_mismatch(
+                address,
+                &flash[offset..offset + chunk.len()],
+                chunk,
+            ))
+        })
+        .unwrap();
+
+        assert_eq!(mismatch, Some(0x0800_009A));
+        // The comparison stops at the page containing the mismatch.
+        assert_eq!(compared_pages, vec![0x0800_0000, 0x0800_0040, 0x0800_0080]);
+
+        let error = FlashError::VerifyFailed {
+            address: mismatch.unwrap(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "The flash contents do not match the written data, starting at address 0x0800009a."
+        );
+    }
+
+    #[test]
+    fn skip_unchanged_sectors() {
+        let flash_algorithm = FlashAlgorithm {
+            flash_properties: FlashProperties {
+                address_range: 0..0x1000,
+                page_size: 0x40,
+                erased_byte_value: 0xFF,
+                program_page_timeout: 200,
+                erase_sector_timeout: 200,
+                sectors: vec![SectorDescription {
+                    size: 0x100,
+                    address: 0,
+                }],
+            },
+            ..Default::default()
+        };
+
+        let region = NvmRegion {
+            is_boot_memory: true,
+            range: 0..0x1000,
+            flash_algorithm: None,
+        };
+
+        // The data ends in the middle of the third sector.
+        let data: Vec<u8> = (0..0x280).map(|i| i as u8).collect();
+
+        let mut flash_builder = FlashBuilder::new();
+        flash_builder.add_data(0, &data).unwrap();
+
+        let unchanged_sectors = |flash: &[u8], restore_unwritten_bytes| {
+            let flash_layout = flash_builder
+                .build_sectors_and_pages(&region, &flash_algorithm, restore_unwritten_bytes)
+                .unwrap();
+
+            let unchanged = select_unchanged_sectors(
+                &flash_builder,
+                &flash_layout,
+                0xFF,
+                restore_unwritten_bytes,
+                |address, data| {
+                    let offset = address as usize;
+                    Ok(first_mismatch(
+                        address,
+                        &flash[offset..offset + data.len()],
+                        data,
+                    ))
+                },
+            )
+            .unwrap();
+
+            unchanged
+                .iter()
+                .map(|sector| sector.address())
+                .collect::<Vec<u32>>()
+        };
+
+        // Only a single byte in the second sector differs.
+        let mut flash = vec![0xFF; 0x1000];
+        flash[..data.len()].copy_from_slice(&data);
+        flash[0x1F0] = 0;
+
+        assert_eq!(unchanged_sectors(&flash, false), vec![0x000, 0x200]);
+        assert_eq!(unchanged_sectors(&flash, true), vec![0x000, 0x200]);
+
+        // A stale byte after the data in the third sector would be erased,
+        // unless the unwritten bytes are kept.
+        flash[0x2C0] = 0;
+
+        assert_eq!(unchanged_sectors(&flash, false), vec![0x000]);
+        assert_eq!(unchanged_sectors(&flash, true), vec![0x000, 0x200]);
+    }
+}
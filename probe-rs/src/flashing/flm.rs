@@ -0,0 +1,444 @@
+//! Loading of flash algorithms from CMSIS-Pack flash algorithm (FLM) files.
+//!
+//! An FLM file is an ELF file, which contains the position-independent code of
+//! the algorithm in the `PrgCode` section, its data in the `PrgData` section and
+//! a `FlashDevice` structure describing the flash.
+//!
+//! See the [CMSIS-Pack documentation](https://arm-software.github.io/CMSIS_5/Pack/html/flashAlgorithm.html)
+//! for a description of the format.
+
+use std::convert::TryFrom;
+
+use object::{Object, ObjectSection, ObjectSymbol};
+use scroll::{Pread, LE};
+use thiserror::Error;
+
+use crate::config::{FlashProperties, RawFlashAlgorithm, SectorDescription};
+
+/// Size of the `FlashDevice` structure, without the sector descriptions.
+const FLASH_DEVICE_HEADER_SIZE: usize = 160;
+/// Maximum length of the device name, including the terminating zero.
+const DEVICE_NAME_SIZE: usize = 128;
+/// Marks the end of the list of sector descriptions.
+const SECTOR_END: u32 = 0xFFFF_FFFF;
+
+/// An error which occured while loading an FLM file.
+#[derive(Debug, Error)]
+pub enum FlmError {
+    /// Reading the ELF file has failed.
+    #[error("Could not read FLM file")]
+    Elf(#[from] object::read::Error),
+    /// A section which is required for the flash algorithm is missing.
+    #[error("The FLM file does not contain a '{0}' section.")]
+    MissingSection(&'static str),
+    /// A symbol which is required for the flash algorithm is missing.
+    #[error("The FLM file does not contain the '{0}' symbol.")]
+    MissingSymbol(&'static str),
+    /// The code, data or symbols of the flash algorithm are not in the expected places.
+    #[error("Invalid layout of the flash algorithm: {0}.")]
+    InvalidLayout(&'static str),
+    /// The `FlashDevice` structure is invalid.
+    #[error("Invalid flash device description: {0}.")]
+    InvalidFlashDevice(&'static str),
+}
+
+/// Load a flash algorithm from the contents of an FLM file.
+///
+/// The name and description of the returned algorithm are taken from the device name
+/// in the `FlashDevice` structure. The algorithm is not marked as the default one.
+/// Use [`Target::add_flash_algorithm`] to use it for a target.
+///
+/// [`Target::add_flash_algorithm`]: crate::Target::add_flash_algorithm
+pub fn read_flm(data: &[u8]) -> Result<RawFlashAlgorithm, FlmError> {
+    let file = object::File::parse(data)?;
+
+    let code = file
+        .section_by_name("PrgCode")
+        .ok_or(FlmError::MissingSection("PrgCode"))?;
+    let code_start = code.address();
+
+    // The zero-initialized part of the data is stored in a separate
+    // `PrgData` section without any contents.
+    let data_sections = file
+        .sections()
+        .filter(|section| section.name() == Ok("PrgData"))
+        .collect::<Vec<_>>();
+
+    let data_start = data_sections
+        .iter()
+        .map(|section| section.address())
+        .min()
+        .ok_or(FlmError::MissingSection("PrgData"))?;
+
+    // The data is expected after the code, all offsets are relative to the start of the code.
+    let offset_from_code = |address: u64| -> Result<u32, FlmError> {
+        address
+            .checked_sub(code_start)
+            .and_then(|offset| u32::try_from(offset).ok())
+            .ok_or(FlmError::InvalidLayout(
+                "Data or symbols are placed before the code",
+            ))
+    };
+
+    let data_section_offset = offset_from_code(data_start)?;
+
+    let mut end = 0;
+    for section in data_sections.iter().chain(std::iter::once(&code)) {
+        let section_end =
+            section
+                .address()
+                .checked_add(section.size())
+                .ok_or(FlmError::InvalidLayout(
+                    "A section exceeds the address space",
+                ))?;
+
+        end = end.max(offset_from_code(section_end)?);
+    }
+
+    // Code and data are loaded together, so they are combined into one image.
+    // The image has to consist of 32-bit words.
+    let image_size = end.checked_add(3).ok_or(FlmError::InvalidLayout(
+        "The image exceeds the address space",
+    ))? & !3;
+    let mut instructions = vec![0; image_size as usize];
+
+    for section in data_sections.iter().chain(std::iter::once(&code)) {
+        let contents = section.data()?;
+        let offset = offset_from_code(section.address())? as usize;
+
+        instructions
+            .get_mut(offset..offset + contents.len())
+            .ok_or(FlmError::InvalidLayout(
+                "The contents of a section exceed its size",
+            ))?
+            .copy_from_slice(contents);
+    }
+
+    let symbol = |name: &'static str| {
+        file.symbols()
+            .find(|symbol| symbol.name() == Ok(name))
+            .map(|symbol| offset_from_code(symbol.address()))
+            .transpose()
+    };
+
+    let required_symbol = |name| symbol(name)?.ok_or(FlmError::MissingSymbol(name));
+
+    let flash_device = file
+        .symbols()
+        .find(|symbol| symbol.name() == Ok("FlashDevice"))
+        .ok_or(FlmError::MissingSymbol("FlashDevice"))?;
+    let flash_device_section = flash_device
+        .section_index()
+        .ok_or(FlmError::MissingSymbol("FlashDevice"))?;
+    let flash_device_section = file.section_by_index(flash_device_section)?;
+    let flash_device_data = flash_device
+        .address()
+        .checked_sub(flash_device_section.address())
+        .and_then(|offset| flash_device_section.data().ok()?.get(offset as usize..))
+        .ok_or(FlmError::InvalidFlashDevice("Not contained in its section"))?;
+
+    let (name, flash_properties) = parse_flash_device(flash_device_data)?;
+
+    Ok(RawFlashAlgorithm {
+        description: name.clone(),
+        name,
+        default: false,
+        instructions,
+        pc_init: symbol("Init")?,
+        pc_uninit: symbol("UnInit")?,
+        pc_program_page: required_symbol("ProgramPage")?,
+        pc_erase_sector: required_symbol("EraseSector")?,
+        pc_erase_all: symbol("EraseChip")?,
+        pc_verify: symbol("Verify")?,
+        data_section_offset,
+        flash_properties,
+    })
+}
+
+/// Parse the `FlashDevice` structure, and return the device name and flash properties.
+fn parse_flash_device(data: &[u8]) -> Result<(String, FlashProperties), FlmError> {
+    if data.len() < FLASH_DEVICE_HEADER_SIZE {
+        return Err(FlmError::InvalidFlashDevice("Too short"));
+    }
+
+    let field = |offset: usize| -> u32 { data.pread_with(offset, LE).unwrap() };
+
+    let name = &data[2..2 + DEVICE_NAME_SIZE];
+    let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+    let name = String::from_utf8_lossy(&name[..name_len]).into_owned();
+
+    let address = field(132);
+    let size = field(136);
+
+    let mut sectors = Vec::new();
+
+    for offset in (FLASH_DEVICE_HEADER_SIZE..).step_by(8) {
+        let size = data
+            .pread_with::<u32>(offset, LE)
+            .map_err(|_| FlmError::InvalidFlashDevice("Missing end of sector list"))?;
+        let address = data
+            .pread_with::<u32>(offset + 4, LE)
+            .map_err(|_| FlmError::InvalidFlashDevice("Missing end of sector list"))?;
+
+        if size == SECTOR_END && address == SECTOR_END {
+            break;
+        }
+
+        sectors.push(SectorDescription { size, address });
+    }
+
+    if sectors.is_empty() || sectors[0].address != 0 {
+        return Err(FlmError::InvalidFlashDevice(
+            "Sectors do not start at the beginning of the flash",
+        ));
+    }
+
+    let flash_properties = FlashProperties {
+        address_range: address..address.wrapping_add(size),
+        page_size: field(140),
+        erased_byte_value: data[148],
+        program_page_timeout: field(152),
+        erase_sector_timeout: field(156),
+        sectors,
+    };
+
+    Ok((name, flash_properties))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use scroll::Pwrite;
+
+    #[test]
+    fn flash_device() {
+        let mut data = vec![0u8; FLASH_DEVICE_HEADER_SIZE + 3 * 8];
+
+        data.pwrite_with(0x0101u16, 0, LE).unwrap();
+        data[2..2 + 11].copy_from_slice(b"Test 256kB\0");
+
+        for (offset, value) in [
+            (132, 0x0800_0000),
+            (136, 0x0004_0000),
+            (140, 0x400),
+            (152, 100),
+            (156, 3000),
+            (160, 0x4000),
+            (164, 0),
+            (168, 0x2_0000),
+            (172, 0x2_0000),
+            (176, SECTOR_END),
+            (180, SECTOR_END),
+        ]
+        .iter()
+        {
+            data.pwrite_with(*value, *offset, LE).unwrap();
+        }
+        data[148] = 0xff;
+
+        let (name, properties) = parse_flash_device(&data).unwrap();
+
+        assert_eq!(name, "Test 256kB");
+        assert_eq!(properties.address_range, 0x0800_0000..0x0804_0000);
+        assert_eq!(properties.page_size, 0x400);
+        assert_eq!(properties.erased_byte_value, 0xff);
+        assert_eq!(properties.program_page_timeout, 100);
+        assert_eq!(properties.erase_sector_timeout, 3000);
+        assert_eq!(
+            properties.sectors,
+            vec![
+                SectorDescription {
+                    size: 0x4000,
+                    address: 0
+                },
+                SectorDescription {
+                    size: 0x2_0000,
+                    address: 0x2_0000
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_sector_end() {
+        let mut data = vec![0u8; FLASH_DEVICE_HEADER_SIZE + 8];
+        data.pwrite_with(0x1000u32, 160, LE).unwrap();
+
+        assert!(parse_flash_device(&data).is_err());
+    }
+
+    /// Build a minimal FLM file, with the code at `code_address` and the data at `data_address`.
+    fn flm_file(code_address: u32, data_address: u32) -> Vec<u8> {
+        let code: Vec<u8> = (1..=32).collect();
+        let data = [0xaa; 8];
+
+        let mut device = vec![0u8; FLASH_DEVICE_HEADER_SIZE + 2 * 8];
+        device[2..2 + 9].copy_from_slice(b"TEST 64kB");
+        for (offset, value) in [
+            (132, 0x0800_0000),
+            (136, 0x1_0000),
+            (140, 0x400),
+            (152, 100),
+            (156, 3000),
+            (160, 0x400),
+            (164, 0),
+            (168, SECTOR_END),
+            (172, SECTOR_END),
+        ]
+        .iter()
+        {
+            device.pwrite_with(*value, *offset, LE).unwrap();
+        }
+        device[148] = 0xff;
+
+        let mut strings = vec![0u8];
+        let mut symbols = vec![0u8; 16];
+        let mut symbol = |name: &str, value: u32, size: u32, info: u8, section: u16| {
+            let name_offset = strings.len() as u32;
+            strings.extend_from_slice(name.as_bytes());
+            strings.push(0);
+
+            symbols.extend_from_slice(&name_offset.to_le_bytes());
+            symbols.extend_from_slice(&value.to_le_bytes());
+            symbols.extend_from_slice(&size.to_le_bytes());
+            symbols.extend_from_slice(&[info, 0]);
+            symbols.extend_from_slice(&section.to_le_bytes());
+        };
+
+        // Global functions in `PrgCode`, and the global `FlashDevice` object in `DevDscr`.
+        for (name, offset) in [
+            ("Init", 0x1),
+            ("UnInit", 0x5),
+            ("EraseSector", 0x9),
+            ("ProgramPage", 0xd),
+            ("EraseChip", 0x11),
+        ]
+        .iter()
+        {
+            symbol(name, code_address + offset, 4, 0x12, 1);
+        }
+        symbol("FlashDevice", 0x1000, device.len() as u32, 0x11, 4);
+
+        let section_names = b"\0PrgCode\0PrgData\0DevDscr\0.symtab\0.strtab\0.shstrtab\0";
+
+        let mut file = vec![0u8; 52];
+        let mut append = |contents: &[u8]| {
+            file.resize((file.len() + 3) & !3, 0);
+            let offset = file.len() as u32;
+            file.extend_from_slice(contents);
+            offset
+        };
+
+        let code_offset = append(&code);
+        let data_offset = append(&data);
+        let device_offset = append(&device);
+        let symbols_offset = append(&symbols);
+        let strings_offset = append(&strings);
+        let section_names_offset = append(section_names);
+        let section_headers_offset = append(&[]);
+
+        // Name, type, flags, address, offset, size, link, info, entry size
+        let sections = [
+            [0, 0, 0, 0, 0, 0, 0, 0, 0],
+            [1, 1, 6, code_address, code_offset, 32, 0, 0, 0],
+            [9, 1, 3, data_address, data_offset, 8, 0, 0, 0],
+            [9, 8, 3, data_address + 8, device_offset, 16, 0, 0, 0],
+            [
+                17,
+                1,
+                2,
+                0x1000,
+                device_offset,
+                device.len() as u32,
+                0,
+                0,
+                0,
+            ],
+            [25, 2, 0, 0, symbols_offset, symbols.len() as u32, 6, 1, 16],
+            [33, 3, 0, 0, strings_offset, strings.len() as u32, 0, 0, 0],
+            [
+                41,
+                3,
+                0,
+                0,
+                section_names_offset,
+                section_names.len() as u32,
+                0,
+                0,
+                0,
+            ],
+        ];
+
+        for header in sections.iter() {
+            for (index, value) in header.iter().enumerate() {
+                file.extend_from_slice(&value.to_le_bytes());
+                // The alignment comes before the entry size.
+                if index == 7 {
+                    file.extend_from_slice(&1u32.to_le_bytes());
+                }
+            }
+        }
+
+        file[..16].copy_from_slice(b"\x7fELF\x01\x01\x01\0\0\0\0\0\0\0\0\0");
+        for (offset, value) in [(16, 2u16), (18, 40), (40, 52), (46, 40), (48, 8), (50, 7)].iter() {
+            file.pwrite_with(*value, *offset, LE).unwrap();
+        }
+        for (offset, value) in [(20, 1u32), (32, section_headers_offset), (36, 0x0500_0000)].iter()
+        {
+            file.pwrite_with(*value, *offset, LE).unwrap();
+        }
+
+        file
+    }
+
+    #[test]
+    fn flm_file_layout() {
+        for &code_address in [0, 0x100].iter() {
+            let algorithm = read_flm(&flm_file(code_address, code_address + 0x20)).unwrap();
+
+            assert_eq!(algorithm.name, "TEST 64kB");
+            assert_eq!(algorithm.instructions.len(), 0x38);
+            assert_eq!(
+                algorithm.instructions[..32],
+                (1..=32).collect::<Vec<u8>>()[..]
+            );
+            assert_eq!(algorithm.instructions[0x20..0x28], [0xaa; 8]);
+            assert_eq!(algorithm.instructions[0x28..], [0; 16]);
+            assert_eq!(algorithm.pc_init, Some(0x1));
+            assert_eq!(algorithm.pc_uninit, Some(0x5));
+            assert_eq!(algorithm.pc_erase_sector, 0x9);
+            assert_eq!(algorithm.pc_program_page, 0xd);
+            assert_eq!(algorithm.pc_erase_all, Some(0x11));
+            assert_eq!(algorithm.pc_verify, None);
+            assert_eq!(algorithm.data_section_offset, 0x20);
+            assert_eq!(
+                algorithm.flash_properties.address_range,
+                0x0800_0000..0x0801_0000
+            );
+        }
+    }
+
+    #[test]
+    fn flm_file_with_data_before_code() {
+        assert!(matches!(
+            read_flm(&flm_file(0x100, 0x20)),
+            Err(FlmError::InvalidLayout(_))
+        ));
+    }
+
+    #[test]
+    fn flm_file_without_code() {
+        let mut file = flm_file(0, 0x20);
+        // Rename the `PrgCode` section.
+        let names = file
+            .windows(8)
+            .position(|window| window == b"PrgCode\0")
+            .unwrap();
+        file[names] = b'X';
+
+        assert!(matches!(
+            read_flm(&file),
+            Err(FlmError::MissingSection("PrgCode"))
+        ));
+    }
+}
@@ -1,16 +1,24 @@
 use ihex::Record;
+use object::Object;
+use std::convert::TryFrom;
 use std::io::{Read, Seek, SeekFrom};
 use std::ops::Range;
+use std::time::Duration;
 
 use super::builder::FlashBuilder;
 use super::{
-    extract_from_elf, BinOptions, DownloadOptions, FileDownloadError, FlashAlgorithm, FlashError,
-    FlashProgress, Flasher,
+    extract_from_elf, srec, uf2, BinOptions, DownloadOptions, FileDownloadError, FlashAlgorithm,
+    FlashError, FlashProgress, Flasher, Uf2Options,
 };
+use crate::architecture::arm::core::register;
+use crate::architecture::arm::m4::Vtor;
+use crate::core::{Architecture, CoreRegister};
 use crate::memory::MemoryInterface;
 use crate::session::Session;
 use crate::{
-    config::{MemoryRange, MemoryRegion, NvmRegion, RamRegion, TargetDescriptionSource},
+    config::{
+        MemoryRange, MemoryRegion, NvmRegion, RamRegion, RawFlashAlgorithm, TargetDescriptionSource,
+    },
     Target,
 };
 
@@ -24,6 +32,9 @@ pub struct FlashLoader {
     memory_map: Vec<MemoryRegion>,
     builder: FlashBuilder,
 
+    /// The entry point of the loaded image, if the file specifies one.
+    entry_point: Option<u32>,
+
     /// Source of the flash description,
     /// used for diagnostics.
     source: TargetDescriptionSource,
@@ -35,18 +46,23 @@ impl FlashLoader {
         Self {
             memory_map,
             builder: FlashBuilder::new(),
+            entry_point: None,
             source,
         }
     }
 
     /// Check the given address range is completely covered by the memory map,
     /// possibly by multiple memory regions.
-    fn check_data_in_memory_map(&mut self, range: Range<u32>) -> Result<(), FlashError> {
+    fn check_data_in_memory_map(&mut self, range: Range<u64>) -> Result<(), FlashError> {
         let mut address = range.start;
         while address < range.end {
-            match Self::get_region_for_address(&self.memory_map, address) {
-                Some(MemoryRegion::Nvm(region)) => address = region.range.end,
-                Some(MemoryRegion::Ram(region)) => address = region.range.end,
+            let region = u32::try_from(address)
+                .ok()
+                .and_then(|address| Self::get_region_for_address(&self.memory_map, address));
+
+            match region {
+                Some(MemoryRegion::Nvm(region)) => address = region.range.end.into(),
+                Some(MemoryRegion::Ram(region)) => address = region.range.end.into(),
                 _ => {
                     return Err(FlashError::NoSuitableNvm {
                         start: range.start,
@@ -62,15 +78,18 @@ impl FlashLoader {
     /// Stages a chunk of data to be programmed.
     ///
     /// The chunk can cross flash boundaries as long as one flash region connects to another flash region.
-    pub fn add_data(&mut self, address: u32, data: &[u8]) -> Result<(), FlashError> {
+    pub fn add_data(&mut self, address: u64, data: &[u8]) -> Result<(), FlashError> {
         log::debug!(
             "Adding data at address {:#010x} with size {} bytes",
             address,
             data.len()
         );
 
-        self.check_data_in_memory_map(address..address + data.len() as u32)?;
-        self.builder.add_data(address, data)
+        self.check_data_in_memory_map(address..address + data.len() as u64)?;
+
+        // All memory regions are inside the 32 bit address space, so the check above
+        // ensures that the address fits.
+        self.builder.add_data(address as u32, data)
     }
 
     pub(super) fn get_region_for_address(
@@ -104,7 +123,7 @@ impl FlashLoader {
 
         self.add_data(
             if let Some(address) = options.base_address {
-                address
+                address.into()
             } else {
                 // If no base address is specified use the start of the boot memory.
                 // TODO: Implement this as soon as we know targets.
@@ -130,7 +149,7 @@ impl FlashLoader {
             match record {
                 Data { offset, value } => {
                     let offset = base_address + offset as u32;
-                    self.add_data(offset, &value)?;
+                    self.add_data(offset.into(), &value)?;
                 }
                 EndOfFile => (),
                 ExtendedSegmentAddress(address) => {
@@ -140,12 +159,45 @@ impl FlashLoader {
                 ExtendedLinearAddress(address) => {
                     base_address = (address as u32) << 16;
                 }
-                StartLinearAddress(_) => (),
+                StartLinearAddress(address) => self.entry_point = Some(address),
             };
         }
         Ok(())
     }
 
+    /// Reads the blocks of a UF2 file and adds them as loadable data blocks to the loader.
+    ///
+    /// Blocks which are not meant for the main flash are skipped, as well as blocks
+    /// for other families than the one given in the `options`.
+    pub fn load_uf2_data<T: Read>(
+        &mut self,
+        file: &mut T,
+        options: Uf2Options,
+    ) -> Result<(), FileDownloadError> {
+        let mut uf2_buffer = Vec::new();
+        file.read_to_end(&mut uf2_buffer)?;
+
+        for block in uf2::extract_blocks(&uf2_buffer, options.family_id)? {
+            self.add_data(block.address.into(), block.data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the data records of a Motorola S-record file and adds them as loadable data blocks to the loader.
+    ///
+    /// The checksum of every record is validated.
+    pub fn load_srec_data<T: Read>(&mut self, file: &mut T) -> Result<(), FileDownloadError> {
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+
+        for record in srec::extract_data(&data)? {
+            self.add_data(record.address.into(), &record.data)?;
+        }
+
+        Ok(())
+    }
+
     /// Prepares the data sections that have to be loaded into flash from an ELF file.
     /// This will validate the ELF file and transform all its data into sections but no flash loader commands yet.
     pub fn load_elf_data<T: Read>(&mut self, file: &mut T) -> Result<(), FileDownloadError> {
@@ -182,9 +234,12 @@ impl FlashLoader {
         }
 
         for data in extracted_data {
-            self.add_data(data.address, data.data)?;
+            self.add_data(data.address.into(), data.data)?;
         }
 
+        let entry_point = object::File::parse(elf_buffer.as_slice())?.entry();
+        self.entry_point = u32::try_from(entry_point).ok().filter(|&entry| entry != 0);
+
         Ok(())
     }
 
@@ -228,6 +283,82 @@ impl FlashLoader {
             }
         }
 
+        if options.boot_from_ram && !options.dry_run {
+            self.boot_from_ram(session, options.run_after_boot)?;
+        }
+
+        Ok(())
+    }
+
+    /// Prepare the core to execute the image which was loaded into RAM.
+    ///
+    /// The image starts at the lowest address written to RAM. On ARM, it has to start
+    /// with the vector table.
+    fn boot_from_ram(&self, session: &mut Session, run: bool) -> Result<(), FlashError> {
+        let image_start = self
+            .memory_map
+            .iter()
+            .filter_map(|region| match region {
+                MemoryRegion::Ram(region) => self
+                    .builder
+                    .data_in_range(&region.range)
+                    .next()
+                    .map(|(address, _)| address),
+                _ => None,
+            })
+            .min()
+            .ok_or(FlashError::NoRamImage)?;
+
+        let architecture = session.architecture();
+        let mut core = session.core(0).map_err(FlashError::Core)?;
+
+        if !core.core_halted().map_err(FlashError::Core)? {
+            core.halt(Duration::from_millis(100))
+                .map_err(FlashError::Core)?;
+        }
+
+        let registers = core.registers();
+
+        let (stack_pointer, program_counter) = match architecture {
+            Architecture::Arm => {
+                let stack_pointer = core
+                    .read_word_32(image_start.into())
+                    .map_err(FlashError::Core)?;
+                let reset_vector = core
+                    .read_word_32((image_start + 4).into())
+                    .map_err(FlashError::Core)?;
+
+                // The vector table has to be relocated, so interrupts use the handlers of the image.
+                // Cores without a VTOR, like the Cortex-M0, can only use the vector table at address 0.
+                if let Err(error) = core.write_word_32(Vtor::ADDRESS.into(), image_start) {
+                    log::warn!("Failed to set VTOR to {:#010x}: {}", image_start, error);
+                }
+
+                // Execution has to continue in Thumb state.
+                core.write_core_reg(register::XPSR.address, 1 << 24)
+                    .map_err(FlashError::Core)?;
+
+                let entry_point = self.entry_point.unwrap_or(reset_vector);
+
+                (Some(stack_pointer), entry_point & !1)
+            }
+            Architecture::Riscv => (None, self.entry_point.unwrap_or(image_start)),
+        };
+
+        if let Some(stack_pointer) = stack_pointer {
+            log::info!("Setting SP to {:#010x}", stack_pointer);
+            core.write_core_reg(registers.stack_pointer().into(), stack_pointer.into())
+                .map_err(FlashError::Core)?;
+        }
+
+        log::info!("Setting PC to {:#010x}", program_counter);
+        core.write_core_reg(registers.program_counter().into(), program_counter.into())
+            .map_err(FlashError::Core)?;
+
+        if run {
+            core.run().map_err(FlashError::Core)?;
+        }
+
         Ok(())
     }
 
@@ -242,17 +373,56 @@ impl FlashLoader {
                 data.len()
             );
             // Write data to memory.
-            core.write_8(address, data).map_err(FlashError::Core)?;
+            core.write_8(address.into(), data)
+                .map_err(FlashError::Core)?;
         }
 
         Ok(())
     }
 
-    fn build_flash_algorithm(
-        &self,
+    /// Build the flash algorithm used to program `region`.
+    ///
+    /// This is either the algorithm specified for the region, or the one covering its address range.
+    pub(super) fn build_flash_algorithm(
         region: &NvmRegion,
         target: &Target,
     ) -> Result<FlashAlgorithm, FlashError> {
+        let algorithms = &target.flash_algorithms;
+
+        let raw_flash_algorithm = match &region.flash_algorithm {
+            // Use the algorithm which is specified for the region
+            Some(name) => algorithms.iter().find(|a| &a.name == name).ok_or_else(|| {
+                FlashError::UnknownFlashAlgorithm {
+                    name: name.clone(),
+                    region: region.clone(),
+                }
+            })?,
+            None => Self::find_flash_algorithm(region, target)?,
+        };
+
+        let mm = &target.memory_map;
+        let ram = mm
+            .iter()
+            .find_map(|mm| match mm {
+                MemoryRegion::Ram(ram) => Some(ram),
+                _ => None,
+            })
+            .ok_or(FlashError::NoRamDefined {
+                chip: target.name.clone(),
+            })?;
+
+        let flash_algorithm = FlashAlgorithm::assemble_from_raw(raw_flash_algorithm, ram, target)?;
+
+        Ok(flash_algorithm)
+    }
+
+    /// Find the flash algorithm for a region, based on the address range of the algorithms.
+    ///
+    /// If multiple algorithms cover the region, the default algorithm is used.
+    fn find_flash_algorithm<'target>(
+        region: &NvmRegion,
+        target: &'target Target,
+    ) -> Result<&'target RawFlashAlgorithm, FlashError> {
         // Try to find a flash algorithm for the range of the current builder
         let algorithms = &target.flash_algorithms;
 
@@ -287,20 +457,7 @@ impl FlashLoader {
                 .ok_or(FlashError::NoFlashLoaderAlgorithmAttached)?,
         };
 
-        let mm = &target.memory_map;
-        let ram = mm
-            .iter()
-            .find_map(|mm| match mm {
-                MemoryRegion::Ram(ram) => Some(ram),
-                _ => None,
-            })
-            .ok_or(FlashError::NoRamDefined {
-                chip: target.name.clone(),
-            })?;
-
-        let flash_algorithm = FlashAlgorithm::assemble_from_raw(raw_flash_algorithm, ram, target)?;
-
-        Ok(flash_algorithm)
+        Ok(raw_flash_algorithm)
     }
 
     fn commit_nvm(
@@ -315,7 +472,7 @@ impl FlashLoader {
             region.range.end
         );
 
-        let flash_algorithm = self.build_flash_algorithm(region, session.target())?;
+        let flash_algorithm = Self::build_flash_algorithm(region, session.target())?;
 
         if options.dry_run {
             log::info!("Skipping programming, dry run!");
@@ -330,13 +487,216 @@ impl FlashLoader {
 
         flasher.program(
             &self.builder,
-            options.do_chip_erase,
-            options.keep_unwritten_bytes,
+            options,
             true,
-            options.skip_erase,
             options.progress.unwrap_or(&FlashProgress::new(|_| {})),
         )?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elf_writer::ElfWriter;
+    use ihex::Record;
+    use object::elf::{EM_ARM, ET_EXEC, PF_R, PF_X};
+    use std::io::Cursor;
+
+    fn ram_loader() -> FlashLoader {
+        FlashLoader::new(
+            vec![MemoryRegion::Ram(RamRegion {
+                range: 0x2000_0000..0x2001_0000,
+                is_boot_memory: false,
+            })],
+            TargetDescriptionSource::Generic,
+        )
+    }
+
+    fn elf_with_entry(entry: u32) -> Vec<u8> {
+        let data = [0u8; 16];
+
+        let mut writer = ElfWriter::new(ET_EXEC, EM_ARM);
+        writer.add_segment(0x2000_0000, &data, PF_R | PF_X);
+        writer.add_sections(".text");
+
+        let mut elf = Vec::new();
+        writer.write(&mut elf).unwrap();
+
+        // The entry point is stored at offset 24 of the ELF header.
+        elf[24..28].copy_from_slice(&entry.to_le_bytes());
+        elf
+    }
+
+    #[test]
+    fn entry_point_from_elf() {
+        let mut loader = ram_loader();
+        loader
+            .load_elf_data(&mut Cursor::new(elf_with_entry(0x2000_0009)))
+            .unwrap();
+        assert_eq!(loader.entry_point, Some(0x2000_0009));
+
+        // An entry point of zero means that the file does not specify one.
+        let mut loader = ram_loader();
+        loader
+            .load_elf_data(&mut Cursor::new(elf_with_entry(0)))
+            .unwrap();
+        assert_eq!(loader.entry_point, None);
+    }
+
+    #[test]
+    fn entry_point_from_hex() {
+        let hex = |records: &[Record]| ihex::create_object_file_representation(records).unwrap();
+
+        let mut loader = ram_loader();
+        loader
+            .load_hex_data(&mut Cursor::new(hex(&[
+                Record::ExtendedLinearAddress(0x2000),
+                Record::Data {
+                    offset: 0,
+                    value: vec![0; 8],
+                },
+                Record::StartLinearAddress(0x2000_0101),
+                Record::EndOfFile,
+            ])))
+            .unwrap();
+        assert_eq!(loader.entry_point, Some(0x2000_0101));
+
+        let mut loader = ram_loader();
+        loader
+            .load_hex_data(&mut Cursor::new(hex(&[
+                Record::ExtendedLinearAddress(0x2000),
+                Record::Data {
+                    offset: 0,
+                    value: vec![0; 8],
+                },
+                Record::EndOfFile,
+            ])))
+            .unwrap();
+        assert_eq!(loader.entry_point, None);
+    }
+
+    #[test]
+    fn flash_algorithm_of_extension_region() {
+        let algorithm = |name: &str, range: Range<u32>, default| RawFlashAlgorithm {
+            name: name.to_owned(),
+            default,
+            flash_properties: crate::config::FlashProperties {
+                address_range: range,
+                page_size: 0x100,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let chip = crate::config::Chip {
+            name: "Test".to_owned(),
+            part: None,
+            memory_map: vec![MemoryRegion::Ram(RamRegion {
+                range: 0x2000_0000..0x2001_0000,
+                is_boot_memory: false,
+            })],
+            flash_algorithms: vec![],
+            cores: vec![],
+        };
+
+        let mut target = Target::new(
+            &chip,
+            vec![],
+            crate::config::CoreType::M4,
+            TargetDescriptionSource::Generic,
+        );
+
+        let region = |flash_algorithm: Option<&str>| NvmRegion {
+            range: 0x9000_0000..0x9080_0000,
+            is_boot_memory: false,
+            flash_algorithm: flash_algorithm.map(ToOwned::to_owned),
+        };
+
+        target
+            .apply_extension(crate::config::TargetExtension {
+                name: "Test board".to_owned(),
+                chip: "Test".to_owned(),
+                memory_map: vec![MemoryRegion::Nvm(region(Some("qspi")))],
+                flash_algorithms: vec![
+                    algorithm("qspi", 0x9000_0000..0x9100_0000, false),
+                    algorithm("qspi_fast", 0x9000_0000..0x9100_0000, true),
+                ],
+            })
+            .unwrap();
+
+        // The algorithm specified for the region is used, even if it is not the default.
+        let flash_algorithm =
+            FlashLoader::build_flash_algorithm(&region(Some("qspi")), &target).unwrap();
+        assert_eq!(flash_algorithm.name, "qspi");
+
+        // Without an algorithm for the region, the default one covering it is used.
+        let flash_algorithm = FlashLoader::build_flash_algorithm(&region(None), &target).unwrap();
+        assert_eq!(flash_algorithm.name, "qspi_fast");
+
+        assert!(matches!(
+            FlashLoader::build_flash_algorithm(&region(Some("octospi")), &target),
+            Err(FlashError::UnknownFlashAlgorithm { .. })
+        ));
+    }
+
+    #[test]
+    fn added_flash_algorithm_takes_precedence() {
+        let algorithm = |name: &str, range: Range<u32>| RawFlashAlgorithm {
+            name: name.to_owned(),
+            flash_properties: crate::config::FlashProperties {
+                address_range: range,
+                page_size: 0x100,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let region = |range: Range<u32>| NvmRegion {
+            range,
+            is_boot_memory: false,
+            flash_algorithm: Some("builtin".to_owned()),
+        };
+
+        let chip = crate::config::Chip {
+            name: "Test".to_owned(),
+            part: None,
+            memory_map: vec![
+                MemoryRegion::Ram(RamRegion {
+                    range: 0x2000_0000..0x2001_0000,
+                    is_boot_memory: false,
+                }),
+                MemoryRegion::Nvm(region(0x0800_0000..0x0802_0000)),
+                MemoryRegion::Nvm(region(0x0802_0000..0x0804_0000)),
+            ],
+            flash_algorithms: vec![],
+            cores: vec![],
+        };
+
+        let mut target = Target::new(
+            &chip,
+            vec![algorithm("builtin", 0x0800_0000..0x0804_0000)],
+            crate::config::CoreType::M4,
+            TargetDescriptionSource::Generic,
+        );
+
+        target.add_flash_algorithm(algorithm("custom", 0x0800_0000..0x0802_0000));
+
+        let names: Vec<_> = target
+            .memory_map
+            .iter()
+            .filter_map(|region| match region {
+                MemoryRegion::Nvm(region) => Some(
+                    FlashLoader::build_flash_algorithm(region, &target)
+                        .unwrap()
+                        .name,
+                ),
+                _ => None,
+            })
+            .collect();
+
+        // Only the region covered by the added algorithm uses it.
+        assert_eq!(names, ["custom", "builtin"]);
+    }
+}
@@ -46,20 +46,30 @@
 //!
 
 mod builder;
+pub(crate) mod configuration;
 mod download;
+mod erase;
 mod error;
 mod flash_algorithm;
 mod flasher;
+mod flm;
 mod loader;
 mod progress;
+mod readback;
+mod srec;
+mod uf2;
 mod visualizer;
 
 use builder::*;
+pub use configuration::*;
 pub use download::*;
+pub use erase::*;
 pub use error::*;
 pub use flash_algorithm::*;
 pub use flasher::*;
+pub use flm::*;
 pub use progress::*;
+pub use readback::*;
 pub use visualizer::*;
 
 pub use loader::FlashLoader;
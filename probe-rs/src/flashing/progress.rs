@@ -36,6 +36,11 @@ impl FlashProgress {
         self.emit(ProgressEvent::Initialized { flash_layout });
     }
 
+    /// Signalize that a sector is skipped, because it already contains the data.
+    pub(super) fn sector_skipped(&self, address: u32, size: u32) {
+        self.emit(ProgressEvent::SectorSkipped { address, size });
+    }
+
     /// Signalize that the erasing procedure started.
     pub(super) fn started_erasing(&self) {
         self.emit(ProgressEvent::StartedErasing);
@@ -103,6 +108,7 @@ impl FlashProgress {
 /// following order:
 ///
 /// * `Initialized`
+/// * `SectorSkipped` for every unchanged sector, if unchanged sectors are skipped
 /// * `StartedFilling`
 /// * `PageFilled` for every page
 /// * `FinishedFilling`
@@ -123,6 +129,14 @@ pub enum ProgressEvent {
         /// This is an exact report of what the flashing procedure will do during the flashing process.
         flash_layout: FlashLayout,
     },
+    /// A sector is skipped, because it already contains the data to be written.
+    /// Skipped sectors are not part of the flash layout.
+    SectorSkipped {
+        /// The start address of the sector.
+        address: u32,
+        /// The size of the sector in bytes.
+        size: u32,
+    },
     /// Filling of flash pages has started.
     StartedFilling,
     /// A page has been filled successfully.
@@ -0,0 +1,324 @@
+use ihex::Record;
+use object::elf::{EM_ARM, EM_RISCV, ET_EXEC, PF_R, PF_X};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    ops::Range,
+    path::Path,
+    str::FromStr,
+};
+use thiserror::Error;
+
+use crate::{
+    config::MemoryRegion, core::Architecture, elf_writer::ElfWriter, session::Session, Error,
+    MemoryInterface,
+};
+
+/// The number of bytes which are read from the target at once.
+const CHUNK_SIZE: usize = 4096;
+
+/// The file formats which the flash contents can be written to.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum ReadbackFormat {
+    /// The memory contents 1:1. This format can only hold a single memory range.
+    Bin,
+    /// The [Intel HEX](https://en.wikipedia.org/wiki/Intel_HEX) format.
+    Hex,
+    /// The [ELF](https://en.wikipedia.org/wiki/Executable_and_Linkable_Format) format,
+    /// with one section for every memory range.
+    Elf,
+}
+
+impl FromStr for ReadbackFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "bin" | "binary" => Ok(ReadbackFormat::Bin),
+            "hex" | "ihex" | "intelhex" => Ok(ReadbackFormat::Hex),
+            "elf" => Ok(ReadbackFormat::Elf),
+            _ => Err(format!("Format '{}' is unknown.", s)),
+        }
+    }
+}
+
+/// Options for reading back the flash contents.
+#[derive(Default)]
+pub struct ReadbackOptions<'progress> {
+    /// The address range to read. If this is `None`, all NVM regions of the target are read.
+    pub range: Option<Range<u32>>,
+    /// An optional progress reporter, which is called with the number of bytes
+    /// read so far and the total number of bytes to read.
+    pub progress: Option<&'progress dyn Fn(usize, usize)>,
+}
+
+/// The contents of a memory range, which were read from the target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryContents {
+    /// The start address of the memory range.
+    pub address: u32,
+    /// The data of the memory range.
+    pub data: Vec<u8>,
+}
+
+/// Errors which can occur when reading back the flash contents.
+#[derive(Debug, Error)]
+pub enum ReadbackError {
+    /// The target has no NVM regions in its memory map.
+    #[error("The memory map of the target does not contain any NVM regions.")]
+    NoNvmRegions,
+    /// Binary files can only hold a single memory range.
+    #[error("A binary file can only hold a single memory range, but {0} ranges were read. Select a single range instead.")]
+    MultipleRanges(usize),
+    /// Reading the memory of the target failed.
+    #[error("Failed to read the memory of the target")]
+    Core(#[source] Error),
+    /// The Intel HEX file could not be created.
+    #[error("Failed to create the Intel HEX file")]
+    IhexWrite(#[from] ihex::WriterError),
+    /// An IO error has occured while writing the file.
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+}
+
+/// Reads the flash contents of the target.
+///
+/// Either the range given in the `options` is read, or all NVM regions of the target.
+pub fn read_flash(
+    session: &mut Session,
+    options: &ReadbackOptions<'_>,
+) -> Result<Vec<MemoryContents>, ReadbackError> {
+    let ranges = match &options.range {
+        Some(range) => vec![range.clone()],
+        None => {
+            let ranges: Vec<_> = session
+                .target()
+                .memory_map
+                .iter()
+                .filter_map(|region| match region {
+                    MemoryRegion::Nvm(region) => Some(region.range.clone()),
+                    _ => None,
+                })
+                .collect();
+
+            if ranges.is_empty() {
+                return Err(ReadbackError::NoNvmRegions);
+            }
+
+            ranges
+        }
+    };
+
+    let total: usize = ranges.iter().map(|range| range.len()).sum();
+    let mut read = 0;
+
+    let mut core = session.core(0).map_err(ReadbackError::Core)?;
+
+    let mut contents = Vec::with_capacity(ranges.len());
+
+    for range in ranges {
+        log::info!("Reading memory {:#010x}..{:#010x}", range.start, range.end);
+
+        let mut data = vec![0; range.len()];
+
+        for (index, chunk) in data.chunks_mut(CHUNK_SIZE).enumerate() {
+            let address = range.start + (index * CHUNK_SIZE) as u32;
+
+            core.read_8(address.into(), chunk)
+                .map_err(ReadbackError::Core)?;
+
+            read += chunk.len();
+
+            if let Some(progress) = options.progress {
+                progress(read, total);
+            }
+        }
+
+        contents.push(MemoryContents {
+            address: range.start,
+            data,
+        });
+    }
+
+    Ok(contents)
+}
+
+/// Reads the flash contents of the target, and writes them to the file at `path`.
+///
+/// See [read_flash] for the available options.
+pub fn readback_to_file(
+    session: &mut Session,
+    path: impl AsRef<Path>,
+    format: ReadbackFormat,
+    options: &ReadbackOptions<'_>,
+) -> Result<(), ReadbackError> {
+    let contents = read_flash(session, options)?;
+
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    write_contents(
+        &contents,
+        format,
+        session.target().architecture(),
+        &mut writer,
+    )?;
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes memory contents in the given `format`.
+///
+/// The `architecture` is only used for the machine type of ELF files.
+pub fn write_contents(
+    contents: &[MemoryContents],
+    format: ReadbackFormat,
+    architecture: Architecture,
+    writer: &mut impl Write,
+) -> Result<(), ReadbackError> {
+    match format {
+        ReadbackFormat::Bin => match contents {
+            [contents] => writer.write_all(&contents.data)?,
+            _ => return Err(ReadbackError::MultipleRanges(contents.len())),
+        },
+        ReadbackFormat::Hex => {
+            let records = hex_records(contents);
+            writer.write_all(ihex::create_object_file_representation(&records)?.as_bytes())?;
+        }
+        ReadbackFormat::Elf => write_elf(contents, architecture, writer)?,
+    }
+
+    Ok(())
+}
+
+/// Splits the memory contents into Intel HEX records.
+fn hex_records(contents: &[MemoryContents]) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut upper_address = None;
+
+    for contents in contents {
+        let mut offset = 0;
+
+        while offset < contents.data.len() {
+            let address = contents.address + offset as u32;
+
+            // Data records only hold the lower 16 bits of the address.
+            let upper = (address >> 16) as u16;
+            if upper_address != Some(upper) {
+                records.push(Record::ExtendedLinearAddress(upper));
+                upper_address = Some(upper);
+            }
+
+            // Records may not cross a 64 KiB boundary.
+            let to_boundary = 0x1_0000 - (address & 0xffff) as usize;
+            let size = 16.min(to_boundary).min(contents.data.len() - offset);
+
+            records.push(Record::Data {
+                offset: address as u16,
+                value: contents.data[offset..offset + size].to_vec(),
+            });
+
+            offset += size;
+        }
+    }
+
+    records.push(Record::EndOfFile);
+
+    records
+}
+
+/// Writes memory contents as an ELF file, with one section and one loadable segment
+/// per memory range.
+fn write_elf(
+    contents: &[MemoryContents],
+    architecture: Architecture,
+    writer: &mut impl Write,
+) -> Result<(), std::io::Error> {
+    let machine = match architecture {
+        Architecture::Arm => EM_ARM,
+        Architecture::Riscv => EM_RISCV,
+    };
+
+    let mut elf = ElfWriter::new(ET_EXEC, machine);
+
+    for contents in contents {
+        elf.add_segment(contents.address, &contents.data, PF_R | PF_X);
+    }
+
+    elf.add_sections(".flash");
+
+    elf.write(writer)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use object::{Object, ObjectSection};
+
+    #[test]
+    fn hex_records_split_at_64k_boundary() {
+        let contents = [MemoryContents {
+            address: 0x0800_fff8,
+            data: (0..24).collect(),
+        }];
+
+        let records = hex_records(&contents);
+
+        assert_eq!(
+            records,
+            vec![
+                Record::ExtendedLinearAddress(0x0800),
+                Record::Data {
+                    offset: 0xfff8,
+                    value: (0..8).collect()
+                },
+                Record::ExtendedLinearAddress(0x0801),
+                Record::Data {
+                    offset: 0x0000,
+                    value: (8..24).collect()
+                },
+                Record::EndOfFile,
+            ]
+        );
+    }
+
+    #[test]
+    fn elf_sections() {
+        let contents = [
+            MemoryContents {
+                address: 0x0800_0000,
+                data: vec![1, 2, 3, 4, 5],
+            },
+            MemoryContents {
+                address: 0x1fff_7800,
+                data: vec![6, 7],
+            },
+        ];
+
+        let mut elf = Vec::new();
+        write_contents(&contents, ReadbackFormat::Elf, Architecture::Arm, &mut elf).unwrap();
+
+        let file = object::File::parse(&elf[..]).unwrap();
+        let sections: Vec<_> = file
+            .sections()
+            .map(|section| {
+                (
+                    section.name().unwrap().to_owned(),
+                    section.address(),
+                    section.data().unwrap().to_vec(),
+                )
+            })
+            .filter(|(name, _, _)| name.starts_with(".flash"))
+            .collect();
+
+        assert_eq!(
+            sections,
+            vec![
+                (".flash0".to_owned(), 0x0800_0000, vec![1, 2, 3, 4, 5]),
+                (".flash1".to_owned(), 0x1fff_7800, vec![6, 7]),
+            ]
+        );
+    }
+}
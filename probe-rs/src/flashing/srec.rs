@@ -0,0 +1,129 @@
+//! Parser for the [Motorola S-record](https://en.wikipedia.org/wiki/SREC_(file_format)) file format.
+
+use super::FileDownloadError;
+
+/// A data record of an S-record file.
+#[derive(Debug, PartialEq)]
+pub(super) struct SrecData {
+    pub(super) address: u32,
+    pub(super) data: Vec<u8>,
+}
+
+/// Extract the data records of an S-record file.
+///
+/// The checksum of every record is validated.
+pub(super) fn extract_data(srec: &str) -> Result<Vec<SrecData>, FileDownloadError> {
+    let mut records = Vec::new();
+
+    for (index, line) in srec.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let error = |reason| FileDownloadError::Srec {
+            line: index + 1,
+            reason,
+        };
+
+        let mut chars = line.chars();
+
+        if chars.next() != Some('S') {
+            return Err(error("Record does not start with 'S'"));
+        }
+
+        let record_type = chars
+            .next()
+            .and_then(|c| c.to_digit(10))
+            .ok_or_else(|| error("Invalid record type"))?;
+
+        let bytes = decode_hex(&line[2..]).ok_or_else(|| error("Invalid hex data"))?;
+
+        // The first byte is the number of the following bytes, including the checksum.
+        if bytes.len() < 2 || bytes[0] as usize != bytes.len() - 1 {
+            return Err(error("Invalid byte count"));
+        }
+
+        let sum = bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        if sum != 0xff {
+            return Err(error("Invalid checksum"));
+        }
+
+        let address_size = match record_type {
+            1 => 2,
+            2 => 3,
+            3 => 4,
+            // Header, record counts and start addresses do not contain data.
+            0 | 5..=9 => continue,
+            _ => return Err(error("Invalid record type")),
+        };
+
+        let payload = &bytes[1..bytes.len() - 1];
+
+        if payload.len() < address_size {
+            return Err(error("Invalid byte count"));
+        }
+
+        let address = payload[..address_size]
+            .iter()
+            .fold(0u32, |address, byte| (address << 8) | u32::from(*byte));
+
+        records.push(SrecData {
+            address,
+            data: payload[address_size..].to_vec(),
+        });
+    }
+
+    Ok(records)
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() & 1 != 0 {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn data_records() {
+        let srec = "S00F000068656C6C6F202020202000003C\n\
+                    S11F00007C0802A6900100049421FFF07C6C1B787C8C23783C6000003863000026\n\
+                    S20801000012345678E2\n\
+                    S5030002FA\n\
+                    S9030000FC\n";
+
+        let records = extract_data(srec).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].address, 0x0000);
+        assert_eq!(records[0].data.len(), 28);
+        assert_eq!(
+            records[1],
+            SrecData {
+                address: 0x01_0000,
+                data: vec![0x12, 0x34, 0x56, 0x78],
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_checksum() {
+        let srec = "S20801000012345678E3\n";
+
+        match extract_data(srec) {
+            Err(FileDownloadError::Srec { line: 1, reason }) => {
+                assert_eq!(reason, "Invalid checksum")
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Parser for the [UF2](https://github.com/microsoft/uf2) file format.
+
+use scroll::{Pread, LE};
+
+use super::FileDownloadError;
+
+const BLOCK_SIZE: usize = 512;
+
+const MAGIC_START0: u32 = 0x0A32_4655;
+const MAGIC_START1: u32 = 0x9E5D_5157;
+const MAGIC_END: u32 = 0x0AB1_6F30;
+
+/// The block is not meant for the main flash, and should be skipped.
+const FLAG_NOT_MAIN_FLASH: u32 = 0x0000_0001;
+/// The `file_size` field contains a family ID.
+const FLAG_FAMILY_ID_PRESENT: u32 = 0x0000_2000;
+
+/// Maximum size of the payload of a block.
+const MAX_PAYLOAD_SIZE: usize = 476;
+
+/// A block of a UF2 file, which contains data for the target.
+#[derive(Debug, PartialEq)]
+pub(super) struct Uf2Block<'data> {
+    pub(super) address: u32,
+    pub(super) data: &'data [u8],
+}
+
+/// Extract all blocks of a UF2 file, which are meant for the main flash.
+///
+/// If `family_id` is set, blocks for other families are skipped.
+pub(super) fn extract_blocks(
+    uf2_data: &[u8],
+    family_id: Option<u32>,
+) -> Result<Vec<Uf2Block<'_>>, FileDownloadError> {
+    if uf2_data.len() & (BLOCK_SIZE - 1) != 0 {
+        return Err(FileDownloadError::Uf2(
+            "File size is not a multiple of the block size",
+        ));
+    }
+
+    let mut blocks = Vec::new();
+
+    for block in uf2_data.chunks(BLOCK_SIZE) {
+        let field = |offset: usize| -> u32 { block.pread_with(offset, LE).unwrap() };
+
+        if field(0) != MAGIC_START0 || field(4) != MAGIC_START1 || field(508) != MAGIC_END {
+            return Err(FileDownloadError::Uf2("Invalid magic number in block"));
+        }
+
+        let flags = field(8);
+        let address = field(12);
+        let payload_size = field(16) as usize;
+
+        if flags & FLAG_NOT_MAIN_FLASH != 0 {
+            continue;
+        }
+
+        if let Some(family_id) = family_id {
+            if flags & FLAG_FAMILY_ID_PRESENT != 0 && field(28) != family_id {
+                continue;
+            }
+        }
+
+        if payload_size > MAX_PAYLOAD_SIZE {
+            return Err(FileDownloadError::Uf2("Invalid payload size in block"));
+        }
+
+        blocks.push(Uf2Block {
+            address,
+            data: &block[32..32 + payload_size],
+        });
+    }
+
+    if blocks.is_empty() {
+        return Err(FileDownloadError::Uf2("No blocks for the main flash found"));
+    }
+
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use scroll::Pwrite;
+
+    fn block(flags: u32, address: u32, family_id: u32, data: &[u8]) -> Vec<u8> {
+        let mut block = vec![0; BLOCK_SIZE];
+
+        for (offset, value) in [
+            (0, MAGIC_START0),
+            (4, MAGIC_START1),
+            (8, flags),
+            (12, address),
+            (16, data.len() as u32),
+            (28, family_id),
+            (508, MAGIC_END),
+        ]
+        .iter()
+        {
+            block.pwrite_with(*value, *offset, LE).unwrap();
+        }
+
+        block[32..32 + data.len()].copy_from_slice(data);
+
+        block
+    }
+
+    #[test]
+    fn filter_family_id() {
+        let mut file = block(FLAG_FAMILY_ID_PRESENT, 0x1000, 0xe48b_ff56, &[1, 2, 3]);
+        file.extend(block(FLAG_FAMILY_ID_PRESENT, 0x2000, 0x5ee2_1072, &[4, 5]));
+        file.extend(block(FLAG_NOT_MAIN_FLASH, 0x3000, 0, &[6]));
+        file.extend(block(0, 0x4000, 0, &[7]));
+
+        let blocks = extract_blocks(&file, Some(0xe48b_ff56)).unwrap();
+
+        assert_eq!(
+            blocks,
+            vec![
+                Uf2Block {
+                    address: 0x1000,
+                    data: &[1, 2, 3]
+                },
+                Uf2Block {
+                    address: 0x4000,
+                    data: &[7]
+                }
+            ]
+        );
+
+        assert_eq!(extract_blocks(&file, None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn invalid_magic() {
+        let mut file = block(0, 0x1000, 0, &[1]);
+        file[0] = 0;
+
+        assert!(extract_blocks(&file, None).is_err());
+    }
+}
@@ -69,17 +69,19 @@ pub mod architecture;
 pub mod config;
 mod core;
 pub mod debug;
+mod elf_writer;
 mod error;
 pub mod flashing;
 mod memory;
 mod probe;
+pub mod rtt;
 mod session;
 
 pub use crate::config::{CoreType, Target};
 pub use crate::core::{
     Architecture, Breakpoint, BreakpointId, CommunicationInterface, Core, CoreInformation,
     CoreInterface, CoreList, CoreRegister, CoreRegisterAddress, CoreState, CoreStatus, HaltReason,
-    SpecificCoreState,
+    SpecificCoreState, VectorCatchCondition, Watchpoint, WatchpointKind,
 };
 pub use crate::error::Error;
 pub use crate::memory::{Memory, MemoryInterface, MemoryList};
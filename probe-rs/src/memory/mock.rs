@@ -0,0 +1,183 @@
+//! Target memory for tests.
+
+use super::MemoryInterface;
+use crate::error::Error;
+use std::convert::TryFrom;
+
+/// Target memory made of flat regions of bytes, for tests.
+///
+/// Values are stored in little endian byte order. Accessing memory outside of
+/// the regions returns an error.
+#[derive(Debug, Default)]
+pub(crate) struct MockMemory {
+    regions: Vec<(u64, Vec<u8>)>,
+}
+
+impl MockMemory {
+    /// Create a memory without any regions, where every access returns an error.
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a region of `size` zero bytes at `address`.
+    pub(crate) fn with_region(mut self, address: u64, size: usize) -> Self {
+        self.regions.push((address, vec![0; size]));
+        self
+    }
+
+    fn bytes(&mut self, address: u64, len: usize) -> Result<&mut [u8], Error> {
+        self.regions
+            .iter_mut()
+            .find_map(|(base, data)| {
+                let start = usize::try_from(address.checked_sub(*base)?).ok()?;
+                data.get_mut(start..start.checked_add(len)?)
+            })
+            .ok_or(Error::AddressOutOfRange(address))
+    }
+}
+
+impl MemoryInterface for MockMemory {
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
+        let mut data = [0u32; 1];
+        self.read_32(address, &mut data)?;
+        Ok(data[0])
+    }
+
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        let mut data = [0u16; 1];
+        self.read_16(address, &mut data)?;
+        Ok(data[0])
+    }
+
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
+        Ok(self.bytes(address, 1)?[0])
+    }
+
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
+        let bytes = self.bytes(address, data.len() * 4)?;
+
+        for (value, bytes) in data.iter_mut().zip(bytes.chunks_exact(4)) {
+            *value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        Ok(())
+    }
+
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        let bytes = self.bytes(address, data.len() * 2)?;
+
+        for (value, bytes) in data.iter_mut().zip(bytes.chunks_exact(2)) {
+            *value = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+
+        Ok(())
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        data.copy_from_slice(self.bytes(address, data.len())?);
+        Ok(())
+    }
+
+    fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), Error> {
+        self.write_32(address, &[data])
+    }
+
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), Error> {
+        self.write_16(address, &[data])
+    }
+
+    fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
+        self.write_8(address, &[data])
+    }
+
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), Error> {
+        let bytes = self.bytes(address, data.len() * 4)?;
+
+        for (value, bytes) in data.iter().zip(bytes.chunks_exact_mut(4)) {
+            bytes.copy_from_slice(&value.to_le_bytes());
+        }
+
+        Ok(())
+    }
+
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), Error> {
+        let bytes = self.bytes(address, data.len() * 2)?;
+
+        for (value, bytes) in data.iter().zip(bytes.chunks_exact_mut(2)) {
+            bytes.copy_from_slice(&value.to_le_bytes());
+        }
+
+        Ok(())
+    }
+
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
+        self.bytes(address, data.len())?.copy_from_slice(data);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Implement [`MemoryInterface`] for a test type, by forwarding all accesses
+/// to its [`MockMemory`] field `$field`.
+macro_rules! forward_memory_interface {
+    ($ty:ty, $field:ident) => {
+        impl $crate::MemoryInterface for $ty {
+            fn read_word_32(&mut self, address: u64) -> Result<u32, $crate::Error> {
+                $crate::MemoryInterface::read_word_32(&mut self.$field, address)
+            }
+
+            fn read_word_16(&mut self, address: u64) -> Result<u16, $crate::Error> {
+                $crate::MemoryInterface::read_word_16(&mut self.$field, address)
+            }
+
+            fn read_word_8(&mut self, address: u64) -> Result<u8, $crate::Error> {
+                $crate::MemoryInterface::read_word_8(&mut self.$field, address)
+            }
+
+            fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), $crate::Error> {
+                $crate::MemoryInterface::read_32(&mut self.$field, address, data)
+            }
+
+            fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), $crate::Error> {
+                $crate::MemoryInterface::read_16(&mut self.$field, address, data)
+            }
+
+            fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), $crate::Error> {
+                $crate::MemoryInterface::read_8(&mut self.$field, address, data)
+            }
+
+            fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), $crate::Error> {
+                $crate::MemoryInterface::write_word_32(&mut self.$field, address, data)
+            }
+
+            fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), $crate::Error> {
+                $crate::MemoryInterface::write_word_16(&mut self.$field, address, data)
+            }
+
+            fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), $crate::Error> {
+                $crate::MemoryInterface::write_word_8(&mut self.$field, address, data)
+            }
+
+            fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), $crate::Error> {
+                $crate::MemoryInterface::write_32(&mut self.$field, address, data)
+            }
+
+            fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), $crate::Error> {
+                $crate::MemoryInterface::write_16(&mut self.$field, address, data)
+            }
+
+            fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), $crate::Error> {
+                $crate::MemoryInterface::write_8(&mut self.$field, address, data)
+            }
+
+            fn flush(&mut self) -> Result<(), $crate::Error> {
+                $crate::MemoryInterface::flush(&mut self.$field)
+            }
+        }
+    };
+}
+
+pub(crate) use forward_memory_interface;
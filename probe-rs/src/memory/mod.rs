@@ -5,45 +5,81 @@ use crate::{
 };
 
 use anyhow::Result;
+use std::convert::TryFrom;
+
+#[cfg(test)]
+pub(crate) mod mock;
+
+/// Checks that `address` fits into the 32 bit address space of a target,
+/// and returns it as a 32 bit address.
+pub(crate) fn valid_32bit_address(address: u64) -> Result<u32, error::Error> {
+    u32::try_from(address).map_err(|_| error::Error::AddressOutOfRange(address))
+}
 
 pub trait MemoryInterface {
     /// Read a 32bit word of at `address`.
     ///
     /// The address where the read should be performed at has to be word aligned.
     /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
-    fn read_word_32(&mut self, address: u32) -> Result<u32, error::Error>;
+    fn read_word_32(&mut self, address: u64) -> Result<u32, error::Error>;
+
+    /// Read a 16bit word of at `address`.
+    ///
+    /// The address where the read should be performed at has to be halfword aligned.
+    /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
+    fn read_word_16(&mut self, address: u64) -> Result<u16, error::Error>;
 
     /// Read an 8bit word of at `address`.
-    fn read_word_8(&mut self, address: u32) -> Result<u8, error::Error>;
+    fn read_word_8(&mut self, address: u64) -> Result<u8, error::Error>;
 
     /// Read a block of 32bit words at `address`.
     ///
     /// The number of words read is `data.len()`.
     /// The address where the read should be performed at has to be word aligned.
     /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
-    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), error::Error>;
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), error::Error>;
+
+    /// Read a block of 16bit words at `address`.
+    ///
+    /// The number of words read is `data.len()`.
+    /// The address where the read should be performed at has to be halfword aligned.
+    /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), error::Error>;
 
     /// Read a block of 8bit words at `address`.
-    fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), error::Error>;
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), error::Error>;
 
     /// Write a 32bit word at `address`.
     ///
     /// The address where the write should be performed at has to be word aligned.
     /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
-    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), error::Error>;
+    fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), error::Error>;
+
+    /// Write a 16bit word at `address`.
+    ///
+    /// The address where the write should be performed at has to be halfword aligned.
+    /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), error::Error>;
 
     /// Write an 8bit word at `address`.
-    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), error::Error>;
+    fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), error::Error>;
 
     /// Write a block of 32bit words at `address`.
     ///
     /// The number of words written is `data.len()`.
     /// The address where the write should be performed at has to be word aligned.
     /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
-    fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), error::Error>;
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), error::Error>;
+
+    /// Write a block of 16bit words at `address`.
+    ///
+    /// The number of words written is `data.len()`.
+    /// The address where the write should be performed at has to be halfword aligned.
+    /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), error::Error>;
 
     /// Write a block of 8bit words at `address`.
-    fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), error::Error>;
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), error::Error>;
 
     /// Flush any outstanding operations.
     ///
@@ -58,35 +94,51 @@ impl<T> MemoryInterface for &mut T
 where
     T: MemoryInterface,
 {
-    fn read_word_32(&mut self, address: u32) -> Result<u32, error::Error> {
+    fn read_word_32(&mut self, address: u64) -> Result<u32, error::Error> {
         (*self).read_word_32(address)
     }
 
-    fn read_word_8(&mut self, address: u32) -> Result<u8, error::Error> {
+    fn read_word_16(&mut self, address: u64) -> Result<u16, error::Error> {
+        (*self).read_word_16(address)
+    }
+
+    fn read_word_8(&mut self, address: u64) -> Result<u8, error::Error> {
         (*self).read_word_8(address)
     }
 
-    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), error::Error> {
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), error::Error> {
         (*self).read_32(address, data)
     }
 
-    fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), error::Error> {
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), error::Error> {
+        (*self).read_16(address, data)
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), error::Error> {
         (*self).read_8(address, data)
     }
 
-    fn write_word_32(&mut self, addr: u32, data: u32) -> Result<(), error::Error> {
+    fn write_word_32(&mut self, addr: u64, data: u32) -> Result<(), error::Error> {
         (*self).write_word_32(addr, data)
     }
 
-    fn write_word_8(&mut self, addr: u32, data: u8) -> Result<(), error::Error> {
+    fn write_word_16(&mut self, addr: u64, data: u16) -> Result<(), error::Error> {
+        (*self).write_word_16(addr, data)
+    }
+
+    fn write_word_8(&mut self, addr: u64, data: u8) -> Result<(), error::Error> {
         (*self).write_word_8(addr, data)
     }
 
-    fn write_32(&mut self, addr: u32, data: &[u32]) -> Result<(), error::Error> {
+    fn write_32(&mut self, addr: u64, data: &[u32]) -> Result<(), error::Error> {
         (*self).write_32(addr, data)
     }
 
-    fn write_8(&mut self, addr: u32, data: &[u8]) -> Result<(), error::Error> {
+    fn write_16(&mut self, addr: u64, data: &[u16]) -> Result<(), error::Error> {
+        (*self).write_16(addr, data)
+    }
+
+    fn write_8(&mut self, addr: u64, data: &[u8]) -> Result<(), error::Error> {
         (*self).write_8(addr, data)
     }
 
@@ -115,6 +167,13 @@ impl<'probe> Memory<'probe> {
         Ok(buff[0])
     }
 
+    pub fn read_word_16(&mut self, address: u32) -> Result<u16, error::Error> {
+        let mut buff = [0];
+        self.inner.read_16(self.ap_sel, address, &mut buff)?;
+
+        Ok(buff[0])
+    }
+
     pub fn read_word_8(&mut self, address: u32) -> Result<u8, error::Error> {
         let mut buff = [0];
         self.inner.read_8(self.ap_sel, address, &mut buff)?;
@@ -126,6 +185,10 @@ impl<'probe> Memory<'probe> {
         self.inner.read_32(self.ap_sel, address, data)
     }
 
+    pub fn read_16(&mut self, address: u32, data: &mut [u16]) -> Result<(), error::Error> {
+        self.inner.read_16(self.ap_sel, address, data)
+    }
+
     pub fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), error::Error> {
         self.inner.read_8(self.ap_sel, address, data)
     }
@@ -134,6 +197,10 @@ impl<'probe> Memory<'probe> {
         self.inner.write_32(self.ap_sel, addr, &[data])
     }
 
+    pub fn write_word_16(&mut self, addr: u32, data: u16) -> Result<(), error::Error> {
+        self.inner.write_16(self.ap_sel, addr, &[data])
+    }
+
     pub fn write_word_8(&mut self, addr: u32, data: u8) -> Result<(), error::Error> {
         self.inner.write_8(self.ap_sel, addr, &[data])
     }
@@ -142,6 +209,10 @@ impl<'probe> Memory<'probe> {
         self.inner.write_32(self.ap_sel, addr, data)
     }
 
+    pub fn write_16(&mut self, addr: u32, data: &[u16]) -> Result<(), error::Error> {
+        self.inner.write_16(self.ap_sel, addr, data)
+    }
+
     pub fn write_8(&mut self, addr: u32, data: &[u8]) -> Result<(), error::Error> {
         self.inner.write_8(self.ap_sel, addr, data)
     }
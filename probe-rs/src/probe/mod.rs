@@ -4,7 +4,8 @@ pub(crate) mod ftdi;
 pub(crate) mod jlink;
 pub(crate) mod stlink;
 
-use crate::{architecture::arm::ap::AccessPort, Session};
+use crate::core::Architecture;
+use crate::{architecture::arm::ap::AccessPort, session::get_target_from_selector, Session};
 use crate::{
     architecture::arm::memory::adi_v5_memory_interface::ADIMemoryInterface,
     config::{RegistryError, TargetSelector},
@@ -123,6 +124,8 @@ pub enum DebugProbeError {
     CommandNotSupportedByProbe,
     #[error("Unable to set hardware breakpoint, all available breakpoint units are in use.")]
     BreakpointUnitsExceeded,
+    #[error("Unable to set hardware watchpoint, all available watchpoint units are in use.")]
+    WatchpointUnitsExceeded,
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
@@ -234,58 +237,26 @@ impl Probe {
         ))
     }
 
-    // /// Tries to mass erase a locked nRF52 chip, this process may timeout, if it does, the chip
-    // /// might be unlocked or not, it is advised to try again if flashing fails
-    // pub fn nrf_recover(&mut self) -> Result<(), DebugProbeError> {
-    //     let ctrl_port = match get_ap_by_idr(self, |idr| idr == CTRL_AP_IDR) {
-    //         Some(port) => CtrlAP::from(port),
-    //         None => {
-    //             return Err(DebugProbeError::AccessPortError(
-    //                 AccessPortError::CtrlAPNotFound,
-    //             ));
-    //         }
-    //     };
-    //     log::info!("Starting mass erase...");
-    //     let mut erase_reg = ERASEALL::from(1);
-    //     let status_reg = ERASEALLSTATUS::from(0);
-    //     let mut reset_reg = RESET::from(1);
-
-    //     // Reset first
-    //     self.write_ap_register(ctrl_port, reset_reg)?;
-    //     reset_reg.RESET = false;
-    //     self.write_ap_register(ctrl_port, reset_reg)?;
-
-    //     self.write_ap_register(ctrl_port, erase_reg)?;
-
-    //     // Prepare timeout
-    //     let now = Instant::now();
-    //     let status = self.read_ap_register(ctrl_port, status_reg)?;
-    //     log::info!("Erase status: {:?}", status.ERASEALLSTATUS);
-    //     let timeout = loop {
-    //         let status = self.read_ap_register(ctrl_port, status_reg)?;
-    //         if !status.ERASEALLSTATUS {
-    //             break false;
-    //         }
-    //         if now.elapsed().as_secs() >= UNLOCK_TIMEOUT {
-    //             break true;
-    //         }
-    //     };
-    //     reset_reg.RESET = true;
-    //     self.write_ap_register(ctrl_port, reset_reg)?;
-    //     reset_reg.RESET = false;
-    //     self.write_ap_register(ctrl_port, reset_reg)?;
-    //     erase_reg.ERASEALL = false;
-    //     self.write_ap_register(ctrl_port, erase_reg)?;
-    //     if timeout {
-    //         log::error!(
-    //             "    {} Mass erase process timeout, the chip might still be locked.",
-    //             "Error".red().bold()
-    //         );
-    //     } else {
-    //         log::info!("Mass erase completed, chip unlocked");
-    //     }
-    //     Ok(())
-    // }
+    /// Recover a locked chip, using the recover step of its debug sequence.
+    ///
+    /// This erases all non-volatile memory of the chip. The probe is returned
+    /// afterwards, so that it can be used to attach to the unlocked chip.
+    pub fn recover(mut self, target: impl Into<TargetSelector>) -> Result<Probe, Error> {
+        self.inner.attach()?;
+        self.attached = true;
+
+        let (probe, target) = get_target_from_selector(target, self)?;
+
+        if target.architecture() != Architecture::Arm {
+            return Err(Error::ArchitectureRequired(&["ARMv7", "ARMv8"]));
+        }
+
+        let mut interface = probe.try_into_arm_interface().map_err(|(_, err)| err)?;
+
+        target.debug_sequence.recover(&mut *interface)?;
+
+        Ok(interface.close())
+    }
 
     /// Get human readable name for the probe
     pub fn get_name(&self) -> String {
@@ -751,6 +722,10 @@ impl DebugProbe for FakeProbe {
         self
     }
 
+    fn has_arm_interface(&self) -> bool {
+        true
+    }
+
     fn try_get_arm_interface<'probe>(
         self: Box<Self>,
     ) -> Result<Box<dyn ArmProbeInterface + 'probe>, (Box<dyn DebugProbe>, DebugProbeError)> {
@@ -820,6 +795,23 @@ impl ArmProbeInterface for FakeArmInterface {
         Ok(None)
     }
 
+    fn read_raw_ap_register(
+        &mut self,
+        _port: crate::architecture::arm::ap::GenericAp,
+        _address: u8,
+    ) -> Result<u32, Error> {
+        unimplemented!()
+    }
+
+    fn write_raw_ap_register(
+        &mut self,
+        _port: crate::architecture::arm::ap::GenericAp,
+        _address: u8,
+        _value: u32,
+    ) -> Result<(), Error> {
+        unimplemented!()
+    }
+
     fn close(self: Box<Self>) -> Probe {
         Probe::from_attached_probe(self.probe)
     }
@@ -11,7 +11,7 @@ use crate::{
             valid_access_ports, AccessPort, ApAccess, ApClass, ApRegister, BaseaddrFormat,
             GenericAp, MemoryAp, BASE, BASE2, CSW, IDR,
         },
-        communication_interface::{ArmCommunicationInterfaceState, ArmProbeInterface},
+        communication_interface::{ArmCommunicationInterfaceState, ArmProbeInterface, RawApAccess},
         dp::{DebugPortError, DpAccess, DpBankSel, DpRegister, Select},
         memory::{adi_v5_memory_interface::ArmProbe, Component},
         ApInformation, ArmChipInfo, SwoAccess, SwoConfig, SwoMode,
@@ -35,6 +35,9 @@ const STLINK_MAX_READ_LEN: usize = 6144;
 /// is also a multiple of 4.
 const STLINK_MAX_WRITE_LEN: usize = 0xFFFC;
 
+/// Maximum length of 8 bit reads and writes in bytes on a ST-Link v2.
+const STLINK_V2_MAX_8BIT_LEN: usize = 64;
+
 #[derive(Debug)]
 pub struct StLink<D: StLinkUsb> {
     device: D,
@@ -384,6 +387,9 @@ impl<D: StLinkUsb> StLink<D> {
     /// Firmware version that adds multiple AP support.
     const MIN_JTAG_VERSION_MULTI_AP: u8 = 28;
 
+    /// Firmware version that adds 16-bit memory access commands.
+    const MIN_JTAG_VERSION_16BIT: u8 = 26;
+
     /// Get the current mode of the ST-Link
     fn get_current_mode(&mut self) -> Result<Mode, DebugProbeError> {
         log::trace!("Getting current mode of device...");
@@ -838,6 +844,63 @@ impl<D: StLinkUsb> StLink<D> {
         Ok(())
     }
 
+    fn read_mem_16bit(
+        &mut self,
+        address: u32,
+        data: &mut [u8],
+        apsel: u8,
+    ) -> Result<(), DebugProbeError> {
+        log::debug!(
+            "Read mem 16 bit, address={:08x}, length={}",
+            address,
+            data.len()
+        );
+
+        // Ensure maximum read length is not exceeded.
+        assert!(
+            data.len() <= STLINK_MAX_READ_LEN,
+            "Maximum read length for STLink is {} bytes",
+            STLINK_MAX_READ_LEN
+        );
+
+        assert!(
+            data.len() & 0x1 == 0,
+            "Data length has to be a multiple of 2 for 16 bit reads"
+        );
+
+        if address & 0x1 != 0 {
+            return Err(StlinkError::UnalignedAddress).map_err(DebugProbeError::from);
+        }
+
+        // Ensure this command is actually supported
+        if self.hw_version < 3 && self.jtag_version < Self::MIN_JTAG_VERSION_16BIT {
+            return Err(DebugProbeError::CommandNotSupportedByProbe);
+        }
+
+        let data_length = data.len();
+
+        self.device.write(
+            &[
+                commands::JTAG_COMMAND,
+                commands::JTAG_READMEM_16BIT,
+                address as u8,
+                (address >> 8) as u8,
+                (address >> 16) as u8,
+                (address >> 24) as u8,
+                data_length as u8,
+                (data_length >> 8) as u8,
+                apsel,
+            ],
+            &[],
+            data,
+            TIMEOUT,
+        )?;
+
+        self.get_last_rw_status()?;
+
+        Ok(())
+    }
+
     fn read_mem_8bit(
         &mut self,
         address: u32,
@@ -943,6 +1006,57 @@ impl<D: StLinkUsb> StLink<D> {
         Ok(())
     }
 
+    fn write_mem_16bit(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        apsel: u8,
+    ) -> Result<(), DebugProbeError> {
+        log::trace!("write_mem_16bit");
+        let length = data.len();
+
+        assert!(
+            length <= STLINK_MAX_WRITE_LEN,
+            "Maximum write length for STLink is {} bytes",
+            STLINK_MAX_WRITE_LEN
+        );
+
+        assert!(
+            data.len() & 0x1 == 0,
+            "Data length has to be a multiple of 2 for 16 bit writes"
+        );
+
+        if address & 0x1 != 0 {
+            return Err(StlinkError::UnalignedAddress).map_err(DebugProbeError::from);
+        }
+
+        // Ensure this command is actually supported
+        if self.hw_version < 3 && self.jtag_version < Self::MIN_JTAG_VERSION_16BIT {
+            return Err(DebugProbeError::CommandNotSupportedByProbe);
+        }
+
+        self.device.write(
+            &[
+                commands::JTAG_COMMAND,
+                commands::JTAG_WRITEMEM_16BIT,
+                address as u8,
+                (address >> 8) as u8,
+                (address >> 16) as u8,
+                (address >> 24) as u8,
+                length as u8,
+                (length >> 8) as u8,
+                apsel,
+            ],
+            &data,
+            &mut [],
+            TIMEOUT,
+        )?;
+
+        self.get_last_rw_status()?;
+
+        Ok(())
+    }
+
     fn write_mem_8bit(
         &mut self,
         address: u32,
@@ -1310,6 +1424,18 @@ impl DpAccess for StlinkArmDebug {
     }
 }
 
+impl RawApAccess for StlinkArmDebug {
+    type Probe = StLink<StLinkUsbDevice>;
+
+    fn dap_probe(&mut self) -> &mut Self::Probe {
+        self.probe.as_mut()
+    }
+
+    fn select_ap_and_ap_bank(&mut self, port: u8, ap_bank: u8) -> Result<(), DebugProbeError> {
+        StlinkArmDebug::select_ap_and_ap_bank(self, port, ap_bank)
+    }
+}
+
 impl<'probe> ArmProbeInterface for StlinkArmDebug {
     fn memory_interface(&mut self, access_port: MemoryAp) -> Result<Memory<'_>, ProbeRsError> {
         let interface = StLinkMemoryInterface { probe: self };
@@ -1361,6 +1487,19 @@ impl<'probe> ArmProbeInterface for StlinkArmDebug {
         Ok(None)
     }
 
+    fn read_raw_ap_register(&mut self, port: GenericAp, address: u8) -> Result<u32, ProbeRsError> {
+        Ok(RawApAccess::read_raw_register(self, port, address)?)
+    }
+
+    fn write_raw_ap_register(
+        &mut self,
+        port: GenericAp,
+        address: u8,
+        value: u32,
+    ) -> Result<(), ProbeRsError> {
+        Ok(RawApAccess::write_raw_register(self, port, address, value)?)
+    }
+
     fn num_access_ports(&self) -> usize {
         self.state.ap_information.len()
     }
@@ -1521,6 +1660,46 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
         Ok(())
     }
 
+    fn read_16(
+        &mut self,
+        ap: MemoryAp,
+        address: u32,
+        data: &mut [u16],
+    ) -> Result<(), ProbeRsError> {
+        self.probe.select_ap(ap)?;
+
+        // Read needs to be chunked into chunks with appropriate max length (see STLINK_MAX_READ_LEN).
+        for (index, chunk) in data.chunks_mut(STLINK_MAX_READ_LEN / 2).enumerate() {
+            let mut buff = vec![0u8; 2 * chunk.len()];
+            let chunk_address = address + (index * STLINK_MAX_READ_LEN) as u32;
+
+            match self
+                .probe
+                .probe
+                .read_mem_16bit(chunk_address, &mut buff, ap.port_number())
+            {
+                Err(DebugProbeError::CommandNotSupportedByProbe) => {
+                    // Older firmware has no 16-bit commands, so the halfwords are read bytewise.
+                    for (offset, bytes) in buff.chunks_mut(STLINK_V2_MAX_8BIT_LEN).enumerate() {
+                        let data = self.probe.probe.read_mem_8bit(
+                            chunk_address + (offset * STLINK_V2_MAX_8BIT_LEN) as u32,
+                            bytes.len() as u16,
+                            ap.port_number(),
+                        )?;
+                        bytes.copy_from_slice(&data[..bytes.len()]);
+                    }
+                }
+                result => result?,
+            }
+
+            for (index, halfword) in buff.chunks_exact(2).enumerate() {
+                chunk[index] = u16::from_le_bytes(halfword.try_into().unwrap());
+            }
+        }
+
+        Ok(())
+    }
+
     fn read_8(&mut self, ap: MemoryAp, address: u32, data: &mut [u8]) -> Result<(), ProbeRsError> {
         self.probe.select_ap(ap)?;
 
@@ -1575,6 +1754,39 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
         Ok(())
     }
 
+    fn write_16(&mut self, ap: MemoryAp, address: u32, data: &[u16]) -> Result<(), ProbeRsError> {
+        self.probe.select_ap(ap)?;
+
+        let tx_buffer: Vec<u8> = data
+            .iter()
+            .flat_map(|halfword| halfword.to_le_bytes().to_vec())
+            .collect();
+
+        for (index, chunk) in tx_buffer.chunks(STLINK_MAX_WRITE_LEN).enumerate() {
+            let chunk_address = address + (index * STLINK_MAX_WRITE_LEN) as u32;
+
+            match self
+                .probe
+                .probe
+                .write_mem_16bit(chunk_address, chunk, ap.port_number())
+            {
+                Err(DebugProbeError::CommandNotSupportedByProbe) => {
+                    // Older firmware has no 16-bit commands, so the halfwords are written bytewise.
+                    for (offset, bytes) in chunk.chunks(STLINK_V2_MAX_8BIT_LEN).enumerate() {
+                        self.probe.probe.write_mem_8bit(
+                            chunk_address + (offset * STLINK_V2_MAX_8BIT_LEN) as u32,
+                            bytes,
+                            ap.port_number(),
+                        )?;
+                    }
+                }
+                result => result?,
+            }
+        }
+
+        Ok(())
+    }
+
     fn write_8(&mut self, ap: MemoryAp, address: u32, data: &[u8]) -> Result<(), ProbeRsError> {
         self.probe.select_ap(ap)?;
 
@@ -1754,8 +1966,10 @@ mod test {
                     Ok(())
                 }
                 commands::JTAG_COMMAND => {
-                    // Return a status of OK for JTAG commands
-                    read_data[0] = 0x80;
+                    // Return a status of OK for JTAG commands, which don't all read data.
+                    if let Some(status) = read_data.first_mut() {
+                        *status = 0x80;
+                    }
 
                     Ok(())
                 }
@@ -1775,6 +1989,39 @@ mod test {
         }
     }
 
+    #[test]
+    fn firmware_without_16bit_support() {
+        let usb_mock = MockUsb {
+            hw_version: 2,
+            jtag_version: 25,
+            swim_version: 0,
+            target_voltage_a0: 1.0,
+            target_voltage_a1: 2.0,
+        };
+
+        let mut probe = usb_mock.build();
+        probe.hw_version = 2;
+        probe.jtag_version = 25;
+
+        assert!(matches!(
+            probe.read_mem_16bit(0x2000_0000, &mut [0; 4], 0),
+            Err(DebugProbeError::CommandNotSupportedByProbe)
+        ));
+        assert!(matches!(
+            probe.write_mem_16bit(0x2000_0000, &[0; 4], 0),
+            Err(DebugProbeError::CommandNotSupportedByProbe)
+        ));
+
+        probe.jtag_version = 26;
+
+        probe
+            .read_mem_16bit(0x2000_0000, &mut [0; 4], 0)
+            .expect("16 bit read failed");
+        probe
+            .write_mem_16bit(0x2000_0000, &[0; 4], 0)
+            .expect("16 bit write failed");
+    }
+
     #[test]
     fn detect_old_firmware() {
         // Test that the init function detects old, unsupported firmware.
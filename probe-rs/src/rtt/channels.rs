@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+
+/// List of RTT channels.
+///
+/// Channels are identified by their number, as defined by the control block on the target.
+#[derive(Debug)]
+pub struct Channels<T>(BTreeMap<usize, T>);
+
+impl<T> Channels<T> {
+    pub(crate) fn new() -> Self {
+        Channels(BTreeMap::new())
+    }
+
+    pub(crate) fn insert(&mut self, number: usize, channel: T) {
+        self.0.insert(number, channel);
+    }
+
+    /// Returns the number of channels on the list.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns a reference to the channel corresponding to the number.
+    pub fn get(&self, number: usize) -> Option<&T> {
+        self.0.get(&number)
+    }
+
+    /// Removes the channel corresponding to the number from the list and returns it.
+    ///
+    /// This is useful if you want to store the channel in a separate variable.
+    pub fn take(&mut self, number: usize) -> Option<T> {
+        self.0.remove(&number)
+    }
+
+    /// Gets an iterator over the channels on the list, sorted by number.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.0.values()
+    }
+
+    /// Removes all channels from the list and returns them, sorted by number.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> {
+        std::mem::take(&mut self.0).into_values()
+    }
+}
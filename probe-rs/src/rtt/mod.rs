@@ -0,0 +1,469 @@
+#![warn(missing_docs)]
+
+//! Host side implementation of the RTT (Real-Time Transfer) I/O protocol over probe-rs
+//!
+//! RTT implements input and output to/from a microcontroller using in-memory ring buffers and
+//! memory polling. This enables debug logging from the microcontroller with minimal delays and no
+//! blocking, making it usable even in real-time applications where e.g. semihosting delays cannot
+//! be tolerated.
+//!
+//! Because only plain memory accesses are used, RTT works with every probe and every architecture
+//! supported by probe-rs, in contrast to SWO.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use probe_rs::Session;
+//! use probe_rs::rtt::Rtt;
+//!
+//! // First obtain a probe-rs session (see probe-rs documentation for details)
+//! let mut session = Session::auto_attach("nrf52")?;
+//! let memory_map = session.target().memory_map.clone();
+//! let mut core = session.core(0)?;
+//!
+//! // Attach to RTT
+//! let mut rtt = Rtt::attach(&mut core, &memory_map)?;
+//!
+//! // Read from a channel
+//! if let Some(input) = rtt.up_channels().take(0) {
+//!     let mut buf = [0u8; 1024];
+//!     let count = input.read(&mut core, &mut buf[..])?;
+//!
+//!     println!("Read data: {:?}", &buf[..count]);
+//! }
+//!
+//! // Write to a channel
+//! if let Some(output) = rtt.down_channels().take(0) {
+//!     output.write(&mut core, b"Hello, computer!\n")?;
+//! }
+//!
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+mod channel;
+mod channels;
+
+pub use channel::{ChannelMode, DownChannel, UpChannel};
+pub use channels::Channels;
+
+use crate::config::MemoryRegion;
+use crate::MemoryInterface;
+use channel::Channel;
+use scroll::{Pread, LE};
+use std::ops::Range;
+
+/// The RTT interface.
+///
+/// Use [`Rtt::attach`] or [`Rtt::attach_region`] to attach to a probe-rs [`Core`] and detect the
+/// channels, as they were configured on the target. The timing of when this is called is really
+/// important, or else unexpected results can be expected.
+///
+/// ## Examples of how timing between host and target effects the results
+///
+/// 1. **Scenario: Ideal configuration** The host RTT interface is created after the target program
+///    has successfully executing the RTT initialization, by calling an api such as
+///    [rtt_init_print](https://docs.rs/rtt-target/0.3.1/rtt_target/macro.rtt_init_print.html).
+///
+///    Outcome: The host will successfully attach to the control block and find all configured
+///    channels.
+///
+/// 2. **Scenario: Host attaches too early** The host RTT interface is created before the target
+///    program has initialized RTT, e.g. right after flashing while the core is halted at the reset
+///    vector.
+///
+///    Outcome: The control block is not yet present in RAM, and
+///    [`Error::ControlBlockNotFound`] is returned. Retry after letting the target run for a
+///    while.
+///
+/// 3. **Scenario: Stale control block** The target was reprogrammed with a firmware which places
+///    the control block at a different address, and the previous control block was not cleared
+///    from RAM.
+///
+///    Outcome: Scanning the whole RAM can find more than one control block, and
+///    [`Error::MultipleControlBlocksFound`] is returned. Use [`ScanRegion::Exact`] with the
+///    address of the `_SEGGER_RTT` symbol from the ELF file to avoid this.
+///
+/// [`Core`]: crate::Core
+#[derive(Debug)]
+pub struct Rtt {
+    ptr: u32,
+    up_channels: Channels<UpChannel>,
+    down_channels: Channels<DownChannel>,
+}
+
+// Rtt must follow this data layout when reading/writing memory in order to be compatible with the
+// official RTT implementation.
+//
+// struct ControlBlock {
+//     char id[16]; // Used to find/validate the control block.
+//     // Maximum number of up (target to host) channels in following array
+//     unsigned int max_up_channels;
+//     // Maximum number of down (host to target) channels in following array.
+//     unsigned int max_down_channels;
+//     RttChannel up_channels[max_up_channels]; // Array of up (target to host) channels.
+//     RttChannel down_channels[max_down_channels]; // array of down (host to target) channels.
+// }
+impl Rtt {
+    /// The magic string expected to be found at the beginning of the RTT control block.
+    pub const RTT_ID: [u8; 16] = *b"SEGGER RTT\0\0\0\0\0\0";
+
+    /// Offset of the `max_up_channels` field in the control block.
+    const O_MAX_UP_CHANNELS: usize = 16;
+
+    /// Offset of the `max_down_channels` field in the control block.
+    const O_MAX_DOWN_CHANNELS: usize = 20;
+
+    /// Offset of the channel descriptor array in the control block.
+    const O_CHANNEL_ARRAYS: usize = 24;
+
+    /// Upper limit for the number of channels, used to detect corrupted control blocks.
+    const MAX_CHANNELS: usize = 255;
+
+    /// Tries to attach to the RTT control block located at `ptr`, using the contents of `mem`
+    /// which was read from the target starting at `ptr`.
+    ///
+    /// Returns `Ok(None)` if no valid control block is located at `ptr`.
+    fn from(
+        core: &mut impl MemoryInterface,
+        memory_map: &[MemoryRegion],
+        ptr: u32,
+        mem_in: Option<&[u8]>,
+    ) -> Result<Option<Rtt>, Error> {
+        let mut mem = match mem_in {
+            Some(mem) => mem.to_vec(),
+            None => {
+                // If memory wasn't passed in, read the minimum header size
+                let mut mem = vec![0u8; Self::O_CHANNEL_ARRAYS];
+                core.read_8(ptr.into(), &mut mem)?;
+                mem
+            }
+        };
+
+        // Validate that the control block starts with the ID bytes
+        if mem.len() < Self::O_CHANNEL_ARRAYS || mem[..Self::RTT_ID.len()] != Self::RTT_ID {
+            return Ok(None);
+        }
+
+        let max_up_channels = mem.pread_with::<u32>(Self::O_MAX_UP_CHANNELS, LE).unwrap() as usize;
+        let max_down_channels = mem
+            .pread_with::<u32>(Self::O_MAX_DOWN_CHANNELS, LE)
+            .unwrap() as usize;
+
+        // *Very* conservative sanity check, most people only use a handful of RTT channels
+        if max_up_channels > Self::MAX_CHANNELS || max_down_channels > Self::MAX_CHANNELS {
+            return Err(Error::ControlBlockCorrupted(format!(
+                "Nonsensical array sizes at {:08x}: max_up_channels={} max_down_channels={}",
+                ptr, max_up_channels, max_down_channels
+            )));
+        }
+
+        let cb_len = Self::O_CHANNEL_ARRAYS + (max_up_channels + max_down_channels) * Channel::SIZE;
+
+        if mem.len() < cb_len {
+            // The control block was not fully contained in the memory that was passed in,
+            // re-read the whole control block from the target.
+            mem = vec![0u8; cb_len];
+            core.read_8(ptr.into(), &mut mem)?;
+
+            if mem[..Self::RTT_ID.len()] != Self::RTT_ID {
+                return Ok(None);
+            }
+        }
+
+        let mut up_channels = Channels::new();
+        let mut down_channels = Channels::new();
+
+        for i in 0..max_up_channels {
+            let offset = Self::O_CHANNEL_ARRAYS + i * Channel::SIZE;
+
+            if let Some(chan) = Channel::from(
+                core,
+                memory_map,
+                i,
+                ptr + offset as u32,
+                &mem[offset..offset + Channel::SIZE],
+            )? {
+                up_channels.insert(i, UpChannel(chan));
+            } else {
+                log::warn!("Buffer for up channel {} not initialized", i);
+            }
+        }
+
+        for i in 0..max_down_channels {
+            let offset =
+                Self::O_CHANNEL_ARRAYS + (max_up_channels * Channel::SIZE) + i * Channel::SIZE;
+
+            if let Some(chan) = Channel::from(
+                core,
+                memory_map,
+                i,
+                ptr + offset as u32,
+                &mem[offset..offset + Channel::SIZE],
+            )? {
+                down_channels.insert(i, DownChannel(chan));
+            } else {
+                log::warn!("Buffer for down channel {} not initialized", i);
+            }
+        }
+
+        Ok(Some(Rtt {
+            ptr,
+            up_channels,
+            down_channels,
+        }))
+    }
+
+    /// Attempts to detect an RTT control block anywhere in the target RAM and returns an instance
+    /// if a valid control block was found.
+    ///
+    /// `core` can be any [`MemoryInterface`], usually the [`Core`] the target program runs on.
+    ///
+    /// [`Core`]: crate::Core
+    pub fn attach(
+        core: &mut impl MemoryInterface,
+        memory_map: &[MemoryRegion],
+    ) -> Result<Rtt, Error> {
+        Self::attach_region(core, memory_map, &ScanRegion::Ram)
+    }
+
+    /// Attempts to detect an RTT control block in the specified RAM region(s) and returns an
+    /// instance if a valid control block was found.
+    ///
+    /// `core` can be any [`MemoryInterface`], usually the [`Core`] the target program runs on.
+    ///
+    /// [`Core`]: crate::Core
+    pub fn attach_region(
+        core: &mut impl MemoryInterface,
+        memory_map: &[MemoryRegion],
+        region: &ScanRegion,
+    ) -> Result<Rtt, Error> {
+        let ranges: Vec<Range<u32>> = match region {
+            ScanRegion::Exact(addr) => {
+                return Rtt::from(core, memory_map, *addr, None)?
+                    .ok_or(Error::ControlBlockNotFound);
+            }
+            ScanRegion::Ram => memory_map
+                .iter()
+                .filter_map(|r| match r {
+                    MemoryRegion::Ram(r) => Some(r.range.clone()),
+                    _ => None,
+                })
+                .collect(),
+            ScanRegion::Range(region) => vec![region.clone()],
+        };
+
+        if ranges.is_empty() {
+            return Err(Error::NoRamRegions);
+        }
+
+        let mut instances = Vec::new();
+
+        for range in ranges {
+            if range.end <= range.start {
+                continue;
+            }
+
+            let mut mem = vec![0; (range.end - range.start) as usize];
+            core.read_8(range.start.into(), &mut mem)?;
+
+            for offset in find_all(&mem, &Self::RTT_ID) {
+                let ptr = range.start + offset as u32;
+
+                if let Some(rtt) = Rtt::from(core, memory_map, ptr, Some(&mem[offset..]))? {
+                    instances.push(rtt);
+                }
+            }
+        }
+
+        match instances.len() {
+            0 => Err(Error::ControlBlockNotFound),
+            1 => Ok(instances.remove(0)),
+            _ => Err(Error::MultipleControlBlocksFound(
+                instances.into_iter().map(|i| i.ptr).collect(),
+            )),
+        }
+    }
+
+    /// Returns the memory address of the control block in target memory.
+    pub fn ptr(&self) -> u32 {
+        self.ptr
+    }
+
+    /// Gets the detected up channels.
+    pub fn up_channels(&mut self) -> &mut Channels<UpChannel> {
+        &mut self.up_channels
+    }
+
+    /// Gets the detected down channels.
+    pub fn down_channels(&mut self) -> &mut Channels<DownChannel> {
+        &mut self.down_channels
+    }
+}
+
+/// Returns the offsets of all occurences of `needle` in `haystack`.
+fn find_all<'a>(haystack: &'a [u8], needle: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    haystack
+        .windows(needle.len())
+        .enumerate()
+        .filter(move |(_, window)| *window == needle)
+        .map(|(offset, _)| offset)
+}
+
+/// Looks up the address of the RTT control block in an ELF file.
+///
+/// The control block is placed in the `_SEGGER_RTT` symbol by both the official SEGGER
+/// implementation and the `rtt-target` crate. The returned address can be used with
+/// [`ScanRegion::Exact`].
+pub fn get_rtt_symbol(elf: &[u8]) -> Option<u32> {
+    use object::read::{Object, ObjectSymbol};
+
+    let file = object::File::parse(elf).ok()?;
+
+    file.symbols()
+        .find(|symbol| symbol.name() == Ok("_SEGGER_RTT"))
+        .map(|symbol| symbol.address() as u32)
+}
+
+/// Used to specify which memory regions to scan for the RTT control block.
+#[derive(Clone, Debug)]
+pub enum ScanRegion {
+    /// Scans all RAM regions known to probe-rs. This should always work, however
+    /// if your device has a lot of RAM, scanning all of it is slow.
+    Ram,
+
+    /// Limit scanning to these memory addresses in target memory. It is up to the user to ensure
+    /// that reading from this range will not read from undefined memory.
+    Range(Range<u32>),
+
+    /// Tries to find the control block starting at this exact address. It is up to the user to
+    /// ensure that reading the necessary bytes after the pointer will no read from undefined
+    /// memory.
+    Exact(u32),
+}
+
+/// Error type for RTT operations.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// RTT control block not found in target memory. Make sure RTT is initialized on the target.
+    #[error(
+        "RTT control block not found in target memory. Make sure RTT is initialized on the target."
+    )]
+    ControlBlockNotFound,
+
+    /// Multiple control blocks found in target memory. The data contains the control block
+    /// addresses (up to 5).
+    #[error("Multiple control blocks found in target memory: {}", display_list(.0))]
+    MultipleControlBlocksFound(Vec<u32>),
+
+    /// The control block has been corrupted. The data contains a detailed error.
+    #[error("Control block corrupted: {0}")]
+    ControlBlockCorrupted(String),
+
+    /// The target has no RAM regions to scan for the control block.
+    #[error("No RAM regions are defined for the target, and no scan range was given.")]
+    NoRamRegions,
+
+    /// Wraps errors propagated up from probe-rs.
+    #[error("Error communicating with probe: {0}")]
+    Probe(#[from] crate::Error),
+}
+
+fn display_list(list: &[u32]) -> String {
+    list.iter()
+        .take(5)
+        .map(|ptr| format!("{:#010x}", ptr))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{config::RamRegion, memory::mock::MockMemory};
+
+    const BASE: u32 = 0x2000_0000;
+    const CB: u32 = BASE + 0x100;
+    const NAME: u32 = BASE + 0x40;
+    const UP_BUFFER: u32 = BASE + 0x200;
+    const DOWN_BUFFER: u32 = BASE + 0x300;
+
+    /// Creates a target memory with a control block containing one up and one down channel,
+    /// both with a buffer size of 16 bytes.
+    fn memory_with_control_block() -> (MockMemory, Vec<MemoryRegion>) {
+        let mut mem = MockMemory::new().with_region(BASE.into(), 0x400);
+
+        mem.write_8(NAME.into(), b"Terminal\0").unwrap();
+
+        mem.write_8(CB.into(), &Rtt::RTT_ID).unwrap();
+        mem.write_32((CB + 16).into(), &[1, 1]).unwrap();
+        // Up channel: name, buffer, size, write, read, flags
+        mem.write_32((CB + 24).into(), &[NAME, UP_BUFFER, 16, 0, 0, 0])
+            .unwrap();
+        // Down channel
+        mem.write_32((CB + 48).into(), &[NAME, DOWN_BUFFER, 16, 0, 0, 0])
+            .unwrap();
+
+        let memory_map = vec![MemoryRegion::Ram(RamRegion {
+            range: BASE..BASE + 0x400,
+            is_boot_memory: false,
+        })];
+
+        (mem, memory_map)
+    }
+
+    #[test]
+    fn find_control_block_in_ram() {
+        let (mut mem, memory_map) = memory_with_control_block();
+
+        let mut rtt = Rtt::attach(&mut mem, &memory_map).unwrap();
+
+        assert_eq!(rtt.ptr(), CB);
+        assert_eq!(rtt.up_channels().len(), 1);
+        assert_eq!(rtt.down_channels().len(), 1);
+
+        let up = rtt.up_channels().take(0).unwrap();
+        assert_eq!(up.name(), Some("Terminal"));
+        assert_eq!(up.buffer_size(), 16);
+    }
+
+    #[test]
+    fn attach_exact_address_without_control_block() {
+        let (mut mem, memory_map) = memory_with_control_block();
+
+        let result = Rtt::attach_region(&mut mem, &memory_map, &ScanRegion::Exact(BASE));
+
+        assert!(matches!(result, Err(Error::ControlBlockNotFound)));
+    }
+
+    #[test]
+    fn read_wrapping_up_channel() {
+        let (mut mem, memory_map) = memory_with_control_block();
+        let mut rtt = Rtt::attach(&mut mem, &memory_map).unwrap();
+        let up = rtt.up_channels().take(0).unwrap();
+
+        // Target wrote 6 bytes, wrapping around the end of the buffer.
+        mem.write_8((UP_BUFFER + 12).into(), b"abcd").unwrap();
+        mem.write_8(UP_BUFFER.into(), b"ef").unwrap();
+        mem.write_word_32((CB + 24 + 12).into(), 2).unwrap();
+        mem.write_word_32((CB + 24 + 16).into(), 12).unwrap();
+
+        let mut buf = [0u8; 32];
+        let count = up.read(&mut mem, &mut buf).unwrap();
+
+        assert_eq!(&buf[..count], b"abcdef");
+        assert_eq!(mem.read_word_32((CB + 24 + 16).into()).unwrap(), 2);
+    }
+
+    #[test]
+    fn write_down_channel_stops_when_full() {
+        let (mut mem, memory_map) = memory_with_control_block();
+        let mut rtt = Rtt::attach(&mut mem, &memory_map).unwrap();
+        let down = rtt.down_channels().take(0).unwrap();
+
+        // One byte of the ring buffer always stays unused.
+        let count = down.write(&mut mem, &[0x55; 20]).unwrap();
+
+        assert_eq!(count, 15);
+        assert_eq!(mem.read_word_32((CB + 48 + 12).into()).unwrap(), 15);
+    }
+}
@@ -6,13 +6,14 @@ use crate::architecture::{
             ApInformation::{MemoryAp, Other},
             ArmProbeInterface, MemoryApInformation,
         },
-        core::{debug_core_start, reset_catch_clear, reset_catch_set},
         memory::Component,
         SwoConfig,
     },
     riscv::communication_interface::RiscvCommunicationInterface,
 };
-use crate::config::{ChipInfo, MemoryRegion, RegistryError, Target, TargetSelector};
+use crate::config::{
+    ChipInfo, CoreAccessOptions, MemoryRegion, RegistryError, Target, TargetSelector,
+};
 use crate::core::{Architecture, CoreState, SpecificCoreState};
 use crate::{AttachMethod, Core, CoreType, Error, Probe};
 use anyhow::anyhow;
@@ -65,13 +66,20 @@ impl ArchitectureInterface {
         core: &'probe mut SpecificCoreState,
         core_state: &'probe mut CoreState,
     ) -> Result<Core<'probe>, Error> {
-        match self {
-            ArchitectureInterface::Arm(state) => {
-                let memory = state.memory_interface(0.into())?;
+        let core_access_options = core_state.core_access_options().clone();
+
+        match (self, core_access_options) {
+            (ArchitectureInterface::Arm(state), CoreAccessOptions::Arm(options)) => {
+                let memory = state.memory_interface(options.ap.into())?;
 
                 core.attach_arm(core_state, memory)
             }
-            ArchitectureInterface::Riscv(state) => core.attach_riscv(core_state, state),
+            (ArchitectureInterface::Riscv(state), CoreAccessOptions::Riscv(options)) => {
+                state.select_hart(options.hart_id)?;
+
+                core.attach_riscv(core_state, state)
+            }
+            _ => Err(Error::CoreArchitectureMismatch),
         }
     }
 
@@ -114,25 +122,37 @@ impl Session {
                     .into());
                 }
 
-                let core = (
-                    SpecificCoreState::from_core_type(target.core_type),
-                    Core::create_state(0),
-                );
+                let cores = create_core_states(&target);
+
+                let mut interface = probe.try_into_arm_interface().map_err(|(_, err)| err)?;
 
-                let interface = probe.try_into_arm_interface().map_err(|(_, err)| err)?;
+                let sequence = target.debug_sequence.clone();
+
+                sequence.pre_attach(&mut *interface)?;
 
                 let mut session = Session {
                     target,
                     interface: ArchitectureInterface::Arm(interface),
-                    cores: vec![core],
+                    cores,
                 };
 
                 // Enable debug mode
-                debug_core_start(&mut session.core(0)?)?;
+                sequence.post_attach(&mut session.core(0)?)?;
+
+                // Secondary cores can be powered down or held in reset by the
+                // primary core, so failing to reach them is not an error here.
+                for n in 1..session.cores.len() {
+                    if let Err(err) = session
+                        .core(n)
+                        .and_then(|mut core| sequence.post_attach(&mut core))
+                    {
+                        log::warn!("Unable to enable debug mode for core {}: {}", n, err);
+                    }
+                }
 
                 if attach_method == AttachMethod::UnderReset {
                     // we need to halt the chip here
-                    reset_catch_set(&mut session.core(0)?)?;
+                    sequence.reset_catch_set(&mut session.core(0)?)?;
 
                     // Deassert the reset pin
                     session.interface.target_reset_deassert()?;
@@ -142,7 +162,7 @@ impl Session {
 
                     core.wait_for_core_halted(Duration::from_millis(100))?;
 
-                    reset_catch_clear(&mut core)?;
+                    sequence.reset_catch_clear(&mut core)?;
                 }
 
                 session
@@ -150,10 +170,7 @@ impl Session {
             Architecture::Riscv => {
                 // TODO: Handle attach under reset
 
-                let core = (
-                    SpecificCoreState::from_core_type(target.core_type),
-                    Core::create_state(0),
-                );
+                let cores = create_core_states(&target);
 
                 let interface = probe
                     .try_into_riscv_interface()
@@ -162,7 +179,7 @@ impl Session {
                 let mut session = Session {
                     target,
                     interface: ArchitectureInterface::Riscv(Box::new(interface)),
-                    cores: vec![core],
+                    cores,
                 };
 
                 {
@@ -175,7 +192,16 @@ impl Session {
             }
         };
 
-        session.clear_all_hw_breakpoints()?;
+        session.core(0)?.clear_all_hw_breakpoints()?;
+
+        for n in 1..session.cores.len() {
+            if let Err(err) = session
+                .core(n)
+                .and_then(|mut core| core.clear_all_hw_breakpoints())
+            {
+                log::warn!("Unable to clear breakpoints of core {}: {}", n, err);
+            }
+        }
 
         Ok(session)
     }
@@ -206,6 +232,10 @@ impl Session {
 
     /// Attaches to the core with the given number.
     ///
+    /// The cores are numbered in the order they are listed in the target description.
+    /// The core is accessed through the MEM-AP (ARM) or hart (RISC-V) given in its
+    /// [`CoreAccessOptions`].
+    ///
     /// ## Usage
     /// Everytime you want to perform an operation on the chip, you need to get the Core handle with the [Session::core() method. This [Core] handle is merely a view into the core. And provides a convenient API surface.
     ///
@@ -372,6 +402,21 @@ impl Session {
     }
 }
 
+/// Create the state for all cores of the target.
+fn create_core_states(target: &Target) -> Vec<(SpecificCoreState, CoreState)> {
+    target
+        .cores
+        .iter()
+        .enumerate()
+        .map(|(id, core)| {
+            (
+                SpecificCoreState::from_core_type(core.core_type),
+                Core::create_state(id, core.core_access_options.clone()),
+            )
+        })
+        .collect()
+}
+
 // This test ensures that [Session] is fully [Send] + [Sync].
 static_assertions::assert_impl_all!(Session: Send);
 
@@ -385,6 +430,15 @@ impl Drop for Session {
         if let Err(err) = result {
             log::warn!("Could not clear all hardware breakpoints: {:?}", err);
         }
+
+        let result = { 0..self.cores.len() }.try_for_each(|i| {
+            self.core(i)
+                .and_then(|mut core| core.clear_all_set_hw_watchpoints())
+        });
+
+        if let Err(err) = result {
+            log::warn!("Could not clear all hardware watchpoints: {:?}", err);
+        }
     }
 }
 /// Determine the [Target] from a [TargetSelector].
@@ -392,7 +446,7 @@ impl Drop for Session {
 /// If the selector is [TargetSelector::Unspecified], the target will be looked up in the registry.
 /// If it its [TargetSelector::Auto], probe-rs will try to determine the target automatically, based on
 /// information read from the chip.
-fn get_target_from_selector(
+pub(crate) fn get_target_from_selector(
     target: impl Into<TargetSelector>,
     probe: Probe,
 ) -> Result<(Probe, Target), Error> {
@@ -0,0 +1,491 @@
+//! Conversion of pack device descriptions into probe-rs target descriptions.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use probe_rs::config::{
+    ArmCoreAccessOptions, Chip, ChipFamily, Core, CoreAccessOptions, CoreType, FlashProperties,
+    MemoryRange, MemoryRegion, NvmRegion, RamRegion, RawFlashAlgorithm, TargetDescriptionSource,
+};
+use probe_rs::flashing::read_flm;
+
+use crate::pdsc::{Algorithm, Device, Memory};
+
+/// Create the chip families for the devices of a pack.
+///
+/// The flash algorithms referenced by the devices are loaded from `pack_dir`.
+/// Devices with an unsupported core or an invalid memory map are skipped.
+pub fn create_families(pack_dir: &Path, devices: &[Device]) -> Vec<ChipFamily> {
+    build_families(devices, |path| load_algorithm(pack_dir, path))
+}
+
+/// Create the chip families for `devices`, using `load` to load the flash algorithm files.
+fn build_families(
+    devices: &[Device],
+    mut load: impl FnMut(&str) -> Result<RawFlashAlgorithm>,
+) -> Vec<ChipFamily> {
+    let mut families: Vec<ChipFamily> = Vec::new();
+    let mut algorithms = HashMap::new();
+
+    // The name of the algorithm in a family, for each combination of file and flash range.
+    let mut algorithm_names: HashMap<(String, String, Range<u32>), String> = HashMap::new();
+
+    for device in devices {
+        let cores = match cores(device) {
+            Ok(cores) => cores,
+            Err(e) => {
+                log::warn!("Skipping device {}: {}", device.name, e);
+                continue;
+            }
+        };
+
+        let memory_map = match device.memories.iter().map(memory_region).collect() {
+            Ok(memory_map) => memory_map,
+            Err(e) => {
+                log::warn!("Skipping device {}: {}", device.name, e);
+                continue;
+            }
+        };
+
+        let family = match families.iter_mut().find(|f| f.name == device.family) {
+            Some(family) => family,
+            None => {
+                families.push(ChipFamily {
+                    name: device.family.clone(),
+                    manufacturer: None,
+                    variants: vec![],
+                    flash_algorithms: vec![],
+                    core: cores[0].core_type,
+                    debug_sequence: None,
+                    configuration: None,
+                    source: TargetDescriptionSource::External,
+                });
+                families.last_mut().unwrap()
+            }
+        };
+
+        // The cores only have to be listed, if they differ from the single core of the family.
+        let cores = if cores.len() == 1 && is_family_core(&cores[0], family.core) {
+            vec![]
+        } else {
+            cores
+        };
+
+        let mut chip = Chip {
+            name: device.name.clone(),
+            part: None,
+            memory_map,
+            flash_algorithms: vec![],
+            cores,
+        };
+
+        for algorithm in &device.algorithms {
+            let raw_algorithm = algorithms.entry(algorithm.path.clone()).or_insert_with(|| {
+                match load(&algorithm.path) {
+                    Ok(raw_algorithm) => Some(raw_algorithm),
+                    Err(e) => {
+                        log::warn!("Failed to load flash algorithm {}: {:?}", algorithm.path, e);
+                        None
+                    }
+                }
+            });
+
+            let raw_algorithm = match raw_algorithm {
+                Some(raw_algorithm) => raw_algorithm,
+                None => continue,
+            };
+
+            let range = match algorithm_range(algorithm, raw_algorithm) {
+                Ok(range) => range,
+                Err(e) => {
+                    log::warn!("Skipping flash algorithm {}: {}", algorithm.path, e);
+                    continue;
+                }
+            };
+
+            let key = (family.name.clone(), algorithm.path.clone(), range.clone());
+
+            let name = match algorithm_names.get(&key) {
+                Some(name) => {
+                    // The algorithm is only the default of the family, if it is
+                    // the default for all variants using it.
+                    if let Some(existing) =
+                        family.flash_algorithms.iter_mut().find(|a| &a.name == name)
+                    {
+                        existing.default &= algorithm.default;
+                    }
+
+                    name.clone()
+                }
+                None => {
+                    let name = unique_name(&raw_algorithm.name, &family.flash_algorithms);
+
+                    family.flash_algorithms.push(RawFlashAlgorithm {
+                        name: name.clone(),
+                        default: algorithm.default,
+                        flash_properties: FlashProperties {
+                            address_range: range.clone(),
+                            ..raw_algorithm.flash_properties.clone()
+                        },
+                        ..raw_algorithm.clone()
+                    });
+
+                    algorithm_names.insert(key, name.clone());
+                    name
+                }
+            };
+
+            // The default algorithm of this variant is specified for its regions,
+            // so it does not depend on the algorithms of the other variants.
+            if algorithm.default {
+                for region in &mut chip.memory_map {
+                    if let MemoryRegion::Nvm(region) = region {
+                        if region.flash_algorithm.is_none() && range.contains_range(&region.range) {
+                            region.flash_algorithm = Some(name.clone());
+                        }
+                    }
+                }
+            }
+
+            if !chip.flash_algorithms.contains(&name) {
+                chip.flash_algorithms.push(name);
+            }
+        }
+
+        family.variants.push(chip);
+    }
+
+    families
+}
+
+/// The flash range of an algorithm, which is specified in the pack or,
+/// if it is missing there, in the FLM file.
+fn algorithm_range(algorithm: &Algorithm, raw_algorithm: &RawFlashAlgorithm) -> Result<Range<u32>> {
+    match (algorithm.start, algorithm.size) {
+        (Some(start), Some(size)) => start
+            .checked_add(size)
+            .map(|end| start..end)
+            .ok_or_else(|| anyhow!("The flash range exceeds the address space")),
+        _ => Ok(raw_algorithm.flash_properties.address_range.clone()),
+    }
+}
+
+/// Make `name` unique among the names of `algorithms`, by appending a number if necessary.
+fn unique_name(name: &str, algorithms: &[RawFlashAlgorithm]) -> String {
+    let is_used = |name: &str| algorithms.iter().any(|a| a.name == name);
+
+    if !is_used(name) {
+        return name.to_owned();
+    }
+
+    (1..)
+        .map(|index| format!("{}_{}", name, index))
+        .find(|name| !is_used(name))
+        .unwrap()
+}
+
+/// Load a flash algorithm, and name it after its file.
+fn load_algorithm(pack_dir: &Path, path: &str) -> Result<RawFlashAlgorithm> {
+    let data =
+        std::fs::read(pack_dir.join(path)).with_context(|| format!("Failed to read {}", path))?;
+
+    let mut algorithm = read_flm(&data)?;
+
+    algorithm.name = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| anyhow!("Invalid file name {}", path))?
+        .to_lowercase();
+
+    Ok(algorithm)
+}
+
+fn cores(device: &Device) -> Result<Vec<Core>> {
+    if device.processors.is_empty() {
+        return Err(anyhow!("No processor specified"));
+    }
+
+    device
+        .processors
+        .iter()
+        .enumerate()
+        .map(|(index, processor)| {
+            let core = processor.core.as_deref().unwrap_or_default();
+
+            Ok(Core {
+                name: processor.name.clone().unwrap_or_else(|| "main".to_owned()),
+                core_type: core_type(core)?,
+                core_access_options: CoreAccessOptions::Arm(ArmCoreAccessOptions {
+                    ap: processor.ap.unwrap_or(index as u8),
+                }),
+            })
+        })
+        .collect()
+}
+
+/// Whether `core` is the core of a chip without a list of cores, in a family with `family_core`.
+fn is_family_core(core: &Core, family_core: CoreType) -> bool {
+    core.core_type == family_core
+        && matches!(
+            core.core_access_options,
+            CoreAccessOptions::Arm(ArmCoreAccessOptions { ap: 0 })
+        )
+}
+
+fn core_type(core: &str) -> Result<CoreType> {
+    Ok(match core {
+        "Cortex-M0" | "Cortex-M0+" => CoreType::M0,
+        "Cortex-M3" => CoreType::M3,
+        "Cortex-M4" => CoreType::M4,
+        "Cortex-M7" => CoreType::M7,
+        "Cortex-M33" => CoreType::M33,
+        _ => return Err(anyhow!("Unsupported core '{}'", core)),
+    })
+}
+
+fn memory_region(memory: &Memory) -> Result<MemoryRegion> {
+    let end = memory
+        .start
+        .checked_add(memory.size)
+        .ok_or_else(|| anyhow!("Memory {} exceeds the address space", memory.name))?;
+    let range = memory.start..end;
+
+    Ok(if memory.is_ram() {
+        MemoryRegion::Ram(RamRegion {
+            range,
+            is_boot_memory: memory.startup,
+        })
+    } else {
+        MemoryRegion::Nvm(NvmRegion {
+            range,
+            is_boot_memory: memory.startup,
+            flash_algorithm: None,
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pdsc::Processor;
+
+    fn device(name: &str, memories: Vec<Memory>, algorithms: Vec<Algorithm>) -> Device {
+        Device {
+            name: name.to_owned(),
+            family: "Test Series".to_owned(),
+            processors: vec![processor("Cortex-M4", None)],
+            memories,
+            algorithms,
+        }
+    }
+
+    fn processor(core: &str, ap: Option<u8>) -> Processor {
+        Processor {
+            name: None,
+            core: Some(core.to_owned()),
+            ap,
+        }
+    }
+
+    fn flash(start: u32, size: u32) -> Memory {
+        Memory {
+            name: "IROM1".to_owned(),
+            access: "rx".to_owned(),
+            start,
+            size,
+            startup: true,
+        }
+    }
+
+    fn algorithm(path: &str, range: Option<Range<u32>>, default: bool) -> Algorithm {
+        Algorithm {
+            path: path.to_owned(),
+            start: range.as_ref().map(|range| range.start),
+            size: range.map(|range| range.end - range.start),
+            default,
+        }
+    }
+
+    /// Load an algorithm for 256 kB of flash, named like `load_algorithm` does.
+    fn load(path: &str) -> Result<RawFlashAlgorithm> {
+        Ok(RawFlashAlgorithm {
+            name: Path::new(path)
+                .file_stem()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_lowercase(),
+            flash_properties: FlashProperties {
+                address_range: 0x0800_0000..0x0804_0000,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    fn flash_algorithm_of_region(chip: &Chip) -> Option<&str> {
+        chip.memory_map.iter().find_map(|region| match region {
+            MemoryRegion::Nvm(region) => region.flash_algorithm.as_deref(),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn memory_exceeding_address_space() {
+        let devices = [
+            device("TEST1", vec![flash(0xffff_0000, 0x2_0000)], vec![]),
+            device("TEST2", vec![flash(0x0800_0000, 0x2_0000)], vec![]),
+        ];
+
+        let families = build_families(&devices, load);
+
+        assert_eq!(families[0].variants.len(), 1);
+        assert_eq!(families[0].variants[0].name, "TEST2");
+    }
+
+    #[test]
+    fn cores_differing_from_family() {
+        let mut devices = [
+            device("TEST1", vec![], vec![]),
+            device("TEST2", vec![], vec![]),
+            device("TEST3", vec![], vec![]),
+        ];
+        devices[1].processors = vec![processor("Cortex-M0+", None)];
+        devices[2].processors = vec![processor("Cortex-M4", Some(1))];
+
+        let families = build_families(&devices, load);
+        let family = &families[0];
+
+        assert_eq!(family.core, CoreType::M4);
+        assert!(family.variants[0].cores.is_empty());
+
+        let cores = &family.variants[1].cores;
+        assert_eq!(cores.len(), 1);
+        assert_eq!(cores[0].core_type, CoreType::M0);
+
+        let cores = &family.variants[2].cores;
+        assert_eq!(cores.len(), 1);
+        assert!(matches!(
+            cores[0].core_access_options,
+            CoreAccessOptions::Arm(ArmCoreAccessOptions { ap: 1 })
+        ));
+    }
+
+    #[test]
+    fn algorithm_range_from_pack() {
+        let devices = [device(
+            "TEST1",
+            vec![flash(0x0800_0000, 0x2_0000)],
+            vec![
+                algorithm("Flash/TEST.FLM", Some(0x0800_0000..0x0802_0000), true),
+                algorithm("Flash/OTHER.FLM", None, false),
+            ],
+        )];
+
+        let families = build_families(&devices, load);
+        let algorithms = &families[0].flash_algorithms;
+
+        assert_eq!(
+            algorithms[0].flash_properties.address_range,
+            0x0800_0000..0x0802_0000
+        );
+        assert_eq!(
+            algorithms[1].flash_properties.address_range,
+            0x0800_0000..0x0804_0000
+        );
+
+        // The end of the range does not fit into 32 bits.
+        let devices = [device(
+            "TEST1",
+            vec![],
+            vec![Algorithm {
+                path: "Flash/TEST.FLM".to_owned(),
+                start: Some(0xffff_0000),
+                size: Some(0x2_0000),
+                default: false,
+            }],
+        )];
+
+        let families = build_families(&devices, load);
+
+        assert!(families[0].flash_algorithms.is_empty());
+        assert!(families[0].variants[0].flash_algorithms.is_empty());
+    }
+
+    #[test]
+    fn default_algorithm_of_variant() {
+        let devices = [
+            device(
+                "TEST1",
+                vec![flash(0x0800_0000, 0x2_0000)],
+                vec![
+                    algorithm("Flash/TEST.FLM", None, true),
+                    algorithm("Flash/OTHER.FLM", None, false),
+                ],
+            ),
+            device(
+                "TEST2",
+                vec![flash(0x0800_0000, 0x2_0000)],
+                vec![
+                    algorithm("Flash/TEST.FLM", None, false),
+                    algorithm("Flash/OTHER.FLM", None, true),
+                ],
+            ),
+            device(
+                "TEST3",
+                vec![flash(0x0800_0000, 0x2_0000)],
+                vec![algorithm("Flash/TEST.FLM", None, false)],
+            ),
+        ];
+
+        let families = build_families(&devices, load);
+        let family = &families[0];
+
+        assert_eq!(family.flash_algorithms.len(), 2);
+        assert!(family.flash_algorithms.iter().all(|a| !a.default));
+
+        assert_eq!(flash_algorithm_of_region(&family.variants[0]), Some("test"));
+        assert_eq!(
+            flash_algorithm_of_region(&family.variants[1]),
+            Some("other")
+        );
+        assert_eq!(flash_algorithm_of_region(&family.variants[2]), None);
+    }
+
+    #[test]
+    fn unique_algorithm_names() {
+        let devices = [
+            device(
+                "TEST1",
+                vec![],
+                vec![
+                    algorithm("Flash/TEST.FLM", None, false),
+                    algorithm("Other/test.flm", None, false),
+                ],
+            ),
+            device(
+                "TEST2",
+                vec![],
+                vec![
+                    algorithm("Flash/TEST.FLM", None, false),
+                    algorithm("Flash/TEST.FLM", Some(0x0800_0000..0x0802_0000), false),
+                ],
+            ),
+        ];
+
+        let families = build_families(&devices, load);
+        let family = &families[0];
+
+        let names: Vec<_> = family
+            .flash_algorithms
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect();
+        assert_eq!(names, ["test", "test_1", "test_2"]);
+
+        assert_eq!(family.variants[0].flash_algorithms, ["test", "test_1"]);
+        assert_eq!(family.variants[1].flash_algorithms, ["test", "test_2"]);
+    }
+}
@@ -0,0 +1,79 @@
+//! Generate probe-rs target descriptions from CMSIS-Packs.
+//!
+//! The device descriptions of an unpacked CMSIS-Pack are read from its `.pdsc` file,
+//! and one YAML target description is written for each device family.
+//! The flash algorithms of the devices are loaded from the FLM files of the pack.
+
+mod generate;
+mod pdsc;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "target-gen",
+    about = "Generate probe-rs target descriptions from CMSIS-Packs"
+)]
+struct Opt {
+    /// Directory of the unpacked CMSIS-Pack, containing the .pdsc file.
+    #[structopt(parse(from_os_str))]
+    pack: PathBuf,
+
+    /// Directory the target descriptions are written to.
+    #[structopt(parse(from_os_str))]
+    output: PathBuf,
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let opt = Opt::from_args();
+
+    let pdsc = find_pdsc(&opt.pack)?;
+    log::info!("Reading pack description {}", pdsc.display());
+
+    let pdsc =
+        fs::read_to_string(&pdsc).with_context(|| format!("Failed to read {}", pdsc.display()))?;
+
+    let devices = pdsc::parse_devices(&pdsc)?;
+    let families = generate::create_families(&opt.pack, &devices);
+
+    if families.is_empty() {
+        return Err(anyhow!("The pack does not contain any supported devices"));
+    }
+
+    fs::create_dir_all(&opt.output)?;
+
+    for family in &families {
+        let path = opt.output.join(format!("{}.yaml", family.name));
+
+        fs::write(&path, serde_yaml::to_string(family)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        println!(
+            "Generated {} with {} variants and {} flash algorithms",
+            path.display(),
+            family.variants.len(),
+            family.flash_algorithms.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Find the pack description in the directory of an unpacked pack.
+fn find_pdsc(pack: &Path) -> Result<PathBuf> {
+    for entry in fs::read_dir(pack).with_context(|| format!("Failed to read {}", pack.display()))? {
+        let path = entry?.path();
+
+        if path.extension().and_then(|e| e.to_str()) == Some("pdsc") {
+            return Ok(path);
+        }
+    }
+
+    Err(anyhow!("No .pdsc file found in {}", pack.display()))
+}
@@ -0,0 +1,307 @@
+//! Parsing of the device descriptions in CMSIS-Pack description (`.pdsc`) files.
+//!
+//! Devices are described in a hierarchy of families, sub-families, devices and
+//! variants. Each level inherits the processors, memories and flash algorithms
+//! of its parent, so every returned [`Device`] contains its complete description.
+
+use anyhow::{anyhow, Context, Result};
+use roxmltree::{Document, Node};
+use std::convert::TryFrom;
+
+/// A device, or a variant of a device, described in a pack.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Device {
+    /// The name of the device or variant.
+    pub name: String,
+    /// The name of the family the device belongs to.
+    pub family: String,
+    /// The processors of the device.
+    pub processors: Vec<Processor>,
+    /// The memory regions of the device.
+    pub memories: Vec<Memory>,
+    /// The flash algorithms of the device.
+    pub algorithms: Vec<Algorithm>,
+}
+
+/// A processor of a device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Processor {
+    /// The name of the processor, only set for devices with multiple processors.
+    pub name: Option<String>,
+    /// The type of the core, e.g. `Cortex-M4`.
+    pub core: Option<String>,
+    /// The index of the access port used to debug the processor.
+    pub ap: Option<u8>,
+}
+
+/// A memory region of a device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Memory {
+    /// The name or ID of the memory, e.g. `IROM1`.
+    pub name: String,
+    /// The access permissions, e.g. `rx` for flash.
+    pub access: String,
+    /// The start address of the memory.
+    pub start: u32,
+    /// The size of the memory in bytes.
+    pub size: u32,
+    /// True if the device boots from this memory.
+    pub startup: bool,
+}
+
+impl Memory {
+    /// Returns true if the memory is RAM, and false for non-volatile memory.
+    pub fn is_ram(&self) -> bool {
+        self.access.contains('w')
+    }
+}
+
+/// A reference to a flash algorithm file of the pack.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Algorithm {
+    /// The path of the FLM file, relative to the pack directory.
+    pub path: String,
+    /// The start address of the flash the algorithm is used for.
+    pub start: Option<u32>,
+    /// The size of the flash the algorithm is used for.
+    pub size: Option<u32>,
+    /// True if the algorithm is used by default for its memory region.
+    pub default: bool,
+}
+
+/// Parse all devices described in a `.pdsc` file.
+pub fn parse_devices(pdsc: &str) -> Result<Vec<Device>> {
+    let document = Document::parse(pdsc).context("Failed to parse the pack description")?;
+
+    let devices = document
+        .root_element()
+        .children()
+        .find(|node| node.has_tag_name("devices"))
+        .ok_or_else(|| anyhow!("The pack description does not contain any devices"))?;
+
+    let mut result = Vec::new();
+
+    for family in devices.children().filter(|n| n.has_tag_name("family")) {
+        let description = Device {
+            family: required_attribute(family, "Dfamily")?.to_owned(),
+            ..Default::default()
+        };
+
+        parse_level(family, description, &mut result)?;
+    }
+
+    Ok(result)
+}
+
+/// Parse one level of the device hierarchy, adding the devices
+/// contained in it to `devices`.
+fn parse_level(node: Node, mut description: Device, devices: &mut Vec<Device>) -> Result<()> {
+    for child in node.children().filter(|n| n.is_element()) {
+        match child.tag_name().name() {
+            "processor" => parse_processor(child, &mut description),
+            "debug" => parse_debug(child, &mut description)?,
+            "memory" => description.memories.push(parse_memory(child)?),
+            "algorithm" => description.algorithms.push(parse_algorithm(child)?),
+            _ => (),
+        }
+    }
+
+    let mut has_children = false;
+
+    for child in node.children().filter(|n| n.is_element()) {
+        let name = match child.tag_name().name() {
+            "subFamily" => None,
+            "device" => Some(required_attribute(child, "Dname")?),
+            "variant" => Some(required_attribute(child, "Dvariant")?),
+            _ => continue,
+        };
+
+        let mut child_description = description.clone();
+        if let Some(name) = name {
+            child_description.name = name.to_owned();
+        }
+
+        parse_level(child, child_description, devices)?;
+        has_children = true;
+    }
+
+    if !has_children && (node.has_tag_name("device") || node.has_tag_name("variant")) {
+        devices.push(description);
+    }
+
+    Ok(())
+}
+
+/// Merge a processor description into the existing processor with the same name.
+fn processor_entry<'a>(description: &'a mut Device, name: Option<&str>) -> &'a mut Processor {
+    let index = description
+        .processors
+        .iter()
+        .position(|p| p.name.as_deref() == name);
+
+    let index = index.unwrap_or_else(|| {
+        description.processors.push(Processor {
+            name: name.map(ToOwned::to_owned),
+            core: None,
+            ap: None,
+        });
+        description.processors.len() - 1
+    });
+
+    &mut description.processors[index]
+}
+
+fn parse_processor(node: Node, description: &mut Device) {
+    let processor = processor_entry(description, node.attribute("Pname"));
+
+    if let Some(core) = node.attribute("Dcore") {
+        processor.core = Some(core.to_owned());
+    }
+}
+
+fn parse_debug(node: Node, description: &mut Device) -> Result<()> {
+    if let Some(ap) = node.attribute("__ap") {
+        let ap = parse_number(ap)?;
+        let ap = u8::try_from(ap)
+            .map_err(|_| anyhow!("The access port __ap={} does not fit into 8 bits", ap))?;
+        let processor = processor_entry(description, node.attribute("Pname"));
+
+        processor.ap = Some(ap);
+    }
+
+    Ok(())
+}
+
+fn parse_memory(node: Node) -> Result<Memory> {
+    let name = node
+        .attribute("name")
+        .or_else(|| node.attribute("id"))
+        .ok_or_else(|| anyhow!("Memory without name or ID"))?;
+
+    // Older packs only specify an ID, which determines the type of memory.
+    let access = match node.attribute("access") {
+        Some(access) => access,
+        None if name.starts_with("IRAM") || name.starts_with("RAM") => "rwx",
+        None => "rx",
+    };
+
+    Ok(Memory {
+        name: name.to_owned(),
+        access: access.to_owned(),
+        start: parse_number(required_attribute(node, "start")?)?,
+        size: parse_number(required_attribute(node, "size")?)?,
+        startup: node.attribute("startup") == Some("1"),
+    })
+}
+
+fn parse_algorithm(node: Node) -> Result<Algorithm> {
+    Ok(Algorithm {
+        path: required_attribute(node, "name")?.replace('\\', "/"),
+        start: node.attribute("start").map(parse_number).transpose()?,
+        size: node.attribute("size").map(parse_number).transpose()?,
+        default: node.attribute("default") == Some("1"),
+    })
+}
+
+fn required_attribute<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str> {
+    node.attribute(name).ok_or_else(|| {
+        anyhow!(
+            "Missing attribute '{}' of element '{}'",
+            name,
+            node.tag_name().name()
+        )
+    })
+}
+
+fn parse_number(value: &str) -> Result<u32> {
+    let value = value.trim();
+
+    let number = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+
+    number.with_context(|| format!("Invalid number '{}'", value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn inherit_descriptions() {
+        let pdsc = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <package>
+              <devices>
+                <family Dfamily="Test Series" Dvendor="Test:1">
+                  <processor Dcore="Cortex-M4"/>
+                  <memory id="IRAM1" start="0x20000000" size="0x10000"/>
+                  <device Dname="TEST1">
+                    <memory id="IROM1" start="0x08000000" size="0x20000" startup="1"/>
+                    <algorithm name="Flash\TEST_128.FLM" start="0x08000000" size="0x20000" default="1"/>
+                    <variant Dvariant="TEST1A"/>
+                    <variant Dvariant="TEST1B"/>
+                  </device>
+                  <subFamily DsubFamily="TEST2">
+                    <device Dname="TEST2">
+                      <memory name="Flash" access="rx" start="0" size="65536" startup="1"/>
+                    </device>
+                  </subFamily>
+                </family>
+              </devices>
+            </package>"#;
+
+        let devices = parse_devices(pdsc).unwrap();
+
+        let names: Vec<_> = devices.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["TEST1A", "TEST1B", "TEST2"]);
+
+        assert_eq!(devices[0].family, "Test Series");
+        assert_eq!(devices[0].processors[0].core.as_deref(), Some("Cortex-M4"));
+        assert_eq!(devices[0].memories.len(), 2);
+        assert!(devices[0].memories[0].is_ram());
+        assert!(!devices[0].memories[1].is_ram());
+        assert_eq!(
+            devices[0].algorithms,
+            vec![Algorithm {
+                path: "Flash/TEST_128.FLM".to_owned(),
+                start: Some(0x0800_0000),
+                size: Some(0x2_0000),
+                default: true
+            }]
+        );
+
+        assert_eq!(
+            devices[2].memories[1],
+            Memory {
+                name: "Flash".to_owned(),
+                access: "rx".to_owned(),
+                start: 0,
+                size: 0x10000,
+                startup: true,
+            }
+        );
+        assert!(devices[2].algorithms.is_empty());
+    }
+
+    #[test]
+    fn access_port_out_of_range() {
+        let pdsc = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <package>
+              <devices>
+                <family Dfamily="Test Series" Dvendor="Test:1">
+                  <processor Dcore="Cortex-M4"/>
+                  <debug __ap="0x100"/>
+                  <device Dname="TEST1"/>
+                </family>
+              </devices>
+            </package>"#;
+
+        let error = parse_devices(pdsc).unwrap_err();
+
+        assert!(format!("{:#}", error).contains("__ap=256"));
+    }
+}